@@ -18,12 +18,15 @@ pub enum TopLevelExpr {
     Import(ImportExpr),
     GlobalDef(GlobalDefExpr),
     StructDef(StructDefExpr),
+    EnumDef(EnumDefExpr),
     TypeDef(TypeDefExpr),
     ConstDef(ConstDefExpr),
     MemoryDef(MemoryDefExpr),
     StaticDataStore(StaticDataStoreExpr),
     ExportExistingFn(ExportExistingFnExpr),
     MacroDef(MacroDefExpr),
+    TraitDef(TraitDefExpr),
+    TraitImpl(TraitImplExpr),
 }
 
 #[derive(Debug)]
@@ -94,6 +97,7 @@ pub struct GlobalDefExpr {
 #[derive(Debug)]
 pub struct StructDefExpr {
     pub struct_name: IdentExpr,
+    pub type_params: Vec<TypeParam>,
     pub fields: Vec<StructDefField>,
     pub loc: LoLocation,
 }
@@ -105,6 +109,22 @@ pub struct StructDefField {
     pub loc: LoLocation,
 }
 
+#[derive(Debug)]
+pub struct EnumDefExpr {
+    pub enum_name: IdentExpr,
+    pub tag_type: Option<TypeExpr>,
+    pub variants: Vec<EnumVariantDef>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct EnumVariantDef {
+    pub variant_name: String,
+    pub payload_type: Option<TypeExpr>,
+    pub tag_value: Option<CodeExpr>,
+    pub loc: LoLocation,
+}
+
 #[derive(Debug)]
 pub struct TypeDefExpr {
     pub type_name: IdentExpr,
@@ -150,12 +170,34 @@ pub struct ExportExistingFnExpr {
 pub struct MacroDefExpr {
     pub macro_name: IdentExpr,
     pub macro_params: Vec<FnParam>,
-    pub macro_type_params: Vec<String>,
+    pub macro_type_params: Vec<TypeParam>,
     pub return_type: Option<TypeExpr>,
     pub body: CodeBlockExpr,
     pub loc: LoLocation,
 }
 
+#[derive(Debug)]
+pub struct TypeParam {
+    pub name: String,
+    pub trait_bounds: Vec<IdentExpr>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct TraitDefExpr {
+    pub trait_name: IdentExpr,
+    pub methods: Vec<FnDeclExpr>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct TraitImplExpr {
+    pub trait_name: IdentExpr,
+    pub type_name: IdentExpr,
+    pub methods: Vec<FnDefExpr>,
+    pub loc: LoLocation,
+}
+
 #[derive(Debug)]
 pub struct CodeBlockExpr {
     pub exprs: Vec<CodeExpr>,
@@ -170,12 +212,15 @@ impl Locatable for TopLevelExpr {
             TopLevelExpr::Import(e) => &e.loc,
             TopLevelExpr::GlobalDef(e) => &e.loc,
             TopLevelExpr::StructDef(e) => &e.loc,
+            TopLevelExpr::EnumDef(e) => &e.loc,
             TopLevelExpr::TypeDef(e) => &e.loc,
             TopLevelExpr::ConstDef(e) => &e.loc,
             TopLevelExpr::MemoryDef(e) => &e.loc,
             TopLevelExpr::StaticDataStore(e) => &e.loc,
             TopLevelExpr::ExportExistingFn(e) => &e.loc,
             TopLevelExpr::MacroDef(e) => &e.loc,
+            TopLevelExpr::TraitDef(e) => &e.loc,
+            TopLevelExpr::TraitImpl(e) => &e.loc,
         }
     }
 }
@@ -203,6 +248,34 @@ pub enum TypeExpr {
         item_type: Box<TypeExpr>,
         loc: LoLocation,
     },
+    Generic {
+        name: IdentExpr,
+        type_args: Vec<TypeExpr>,
+        loc: LoLocation,
+    },
+    Array {
+        item_type: Box<TypeExpr>,
+        length: u32,
+        loc: LoLocation,
+    },
+    Slice {
+        item_type: Box<TypeExpr>,
+        loc: LoLocation,
+    },
+    Fn {
+        inputs: Vec<TypeExpr>,
+        output: Option<Box<TypeExpr>>,
+        loc: LoLocation,
+    },
+    Closure {
+        inputs: Vec<TypeExpr>,
+        output: Option<Box<TypeExpr>>,
+        loc: LoLocation,
+    },
+    Tuple {
+        item_types: Vec<TypeExpr>,
+        loc: LoLocation,
+    },
 }
 
 impl Locatable for TypeExpr {
@@ -213,6 +286,12 @@ impl Locatable for TypeExpr {
             TypeExpr::SequencePointer { loc, .. } => loc,
             TypeExpr::Result { loc, .. } => loc,
             TypeExpr::Of { loc, .. } => loc,
+            TypeExpr::Generic { loc, .. } => loc,
+            TypeExpr::Array { loc, .. } => loc,
+            TypeExpr::Slice { loc, .. } => loc,
+            TypeExpr::Fn { loc, .. } => loc,
+            TypeExpr::Closure { loc, .. } => loc,
+            TypeExpr::Tuple { loc, .. } => loc,
         }
     }
 }
@@ -223,13 +302,18 @@ pub enum CodeExpr {
     BoolLiteral(BoolLiteralExpr),
     CharLiteral(CharLiteralExpr),
     IntLiteral(IntLiteralExpr),
+    FloatLiteral(FloatLiteralExpr),
     StringLiteral(StringLiteralExpr),
     StructLiteral(StructLiteralExpr),
     ArrayLiteral(ArrayLiteralExpr),
+    TupleLiteral(TupleLiteralExpr),
 
     // variables
     Ident(IdentExpr),
     Let(LetExpr),
+    LetTuple(LetTupleExpr),
+    FnRef(FnRefExpr),
+    Closure(ClosureExpr),
 
     // operations
     InfixOp(InfixOpExpr),
@@ -237,6 +321,8 @@ pub enum CodeExpr {
     Cast(CastExpr),
     Assign(AssignExpr),
     FieldAccess(FieldAccessExpr),
+    Index(IndexExpr),
+    Slice(SliceExpr),
     PropagateError(PropagateErrorExpr),
     FnCall(FnCallExpr),
     MethodCall(MethodCallExpr),
@@ -249,10 +335,12 @@ pub enum CodeExpr {
     // control flow
     Return(ReturnExpr),
     If(IfExpr),
+    Match(MatchExpr),
     Loop(LoopExpr),
     Break(BreakExpr),
     Unreachable(UnreachableExpr),
     ForLoop(ForLoopExpr),
+    WhileLoop(WhileLoopExpr),
     Continue(ContinueExpr),
     Defer(DeferExpr),
     Catch(CatchExpr),
@@ -275,7 +363,15 @@ pub struct CharLiteralExpr {
 #[derive(Debug)]
 pub struct IntLiteralExpr {
     pub repr: String,
-    pub value: u32,
+    pub value: u64,
+    pub tag: Option<String>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct FloatLiteralExpr {
+    pub repr: String,
+    pub value: f64,
     pub tag: Option<String>,
     pub loc: LoLocation,
 }
@@ -335,18 +431,30 @@ pub enum ElseBlock {
 #[derive(Debug)]
 pub struct LetExpr {
     pub local_name: String,
+    pub local_type: Option<TypeExpr>,
+    pub value: Box<CodeExpr>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct LetTupleExpr {
+    pub local_names: Vec<String>, // `_` skips the item
+    pub local_type: Option<TypeExpr>,
     pub value: Box<CodeExpr>,
     pub loc: LoLocation,
 }
 
 #[derive(Debug)]
 pub struct LoopExpr {
+    pub label: Option<String>,
     pub body: Box<CodeBlockExpr>,
     pub loc: LoLocation,
 }
 
 #[derive(Debug)]
 pub struct BreakExpr {
+    pub label: Option<String>,
+    pub value: Option<Box<CodeExpr>>,
     pub loc: LoLocation,
 }
 
@@ -357,15 +465,38 @@ pub struct UnreachableExpr {
 
 #[derive(Debug)]
 pub struct ForLoopExpr {
-    pub counter: String,
+    pub label: Option<String>,
+    pub item_name: String,
+    pub iterable: ForLoopIterable,
+    pub body: Box<CodeBlockExpr>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub enum ForLoopIterable {
+    Range(RangeExpr),
+    Items(Box<CodeExpr>),
+}
+
+#[derive(Debug)]
+pub struct RangeExpr {
     pub start: Box<CodeExpr>,
     pub end: Box<CodeExpr>,
+    pub is_inclusive: bool,
+    pub step: Option<Box<CodeExpr>>, // integer literal, negative for descending ranges
+}
+
+#[derive(Debug)]
+pub struct WhileLoopExpr {
+    pub label: Option<String>,
+    pub cond: Box<CodeExpr>,
     pub body: Box<CodeBlockExpr>,
     pub loc: LoLocation,
 }
 
 #[derive(Debug)]
 pub struct ContinueExpr {
+    pub label: Option<String>,
     pub loc: LoLocation,
 }
 
@@ -391,6 +522,7 @@ pub struct CastExpr {
 #[derive(Debug)]
 pub struct StructLiteralExpr {
     pub struct_name: IdentExpr,
+    pub type_args: Vec<TypeExpr>,
     pub fields: Vec<StructLiteralField>,
     pub loc: LoLocation,
 }
@@ -402,6 +534,12 @@ pub struct ArrayLiteralExpr {
     pub loc: LoLocation,
 }
 
+#[derive(Debug)]
+pub struct TupleLiteralExpr {
+    pub items: Vec<CodeExpr>,
+    pub loc: LoLocation,
+}
+
 #[derive(Debug)]
 pub struct StructLiteralField {
     pub field_name: String,
@@ -424,6 +562,63 @@ pub struct FieldAccessExpr {
     pub loc: LoLocation,
 }
 
+#[derive(Debug)]
+pub struct IndexExpr {
+    pub lhs: Box<CodeExpr>,
+    pub index: Box<CodeExpr>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct SliceExpr {
+    pub lhs: Box<CodeExpr>,
+    pub start: Option<Box<CodeExpr>>,
+    pub end: Option<Box<CodeExpr>>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct FnRefExpr {
+    pub fn_name: IdentExpr,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct ClosureExpr {
+    pub params: Vec<ClosureParam>,
+    pub return_type: Option<TypeExpr>,
+    pub body: ClosureBody,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct ClosureParam {
+    pub param_name: String,
+    pub param_type: Option<TypeExpr>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub enum ClosureBody {
+    Expr(Box<CodeExpr>),
+    Block(CodeBlockExpr),
+}
+
+#[derive(Debug)]
+pub struct MatchExpr {
+    pub expr: Box<CodeExpr>,
+    pub arms: Vec<MatchArm>,
+    pub loc: LoLocation,
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+    pub variant_name: IdentExpr, // `_` matches any variant
+    pub payload_bind: Option<IdentExpr>,
+    pub body: CodeBlockExpr,
+    pub loc: LoLocation,
+}
+
 #[derive(Debug)]
 pub struct CatchExpr {
     pub lhs: Box<CodeExpr>,
@@ -493,16 +688,23 @@ impl Locatable for CodeExpr {
             CodeExpr::BoolLiteral(e) => &e.loc,
             CodeExpr::CharLiteral(e) => &e.loc,
             CodeExpr::IntLiteral(e) => &e.loc,
+            CodeExpr::FloatLiteral(e) => &e.loc,
             CodeExpr::StringLiteral(e) => &e.loc,
             CodeExpr::ArrayLiteral(e) => &e.loc,
+            CodeExpr::TupleLiteral(e) => &e.loc,
             CodeExpr::Return(e) => &e.loc,
             CodeExpr::Ident(e) => &e.loc,
             CodeExpr::InfixOp(e) => &e.loc,
             CodeExpr::If(e) => &e.loc,
+            CodeExpr::Match(e) => &e.loc,
             CodeExpr::Let(e) => &e.loc,
+            CodeExpr::LetTuple(e) => &e.loc,
+            CodeExpr::FnRef(e) => &e.loc,
+            CodeExpr::Closure(e) => &e.loc,
             CodeExpr::Loop(e) => &e.loc,
             CodeExpr::Break(e) => &e.loc,
             CodeExpr::ForLoop(e) => &e.loc,
+            CodeExpr::WhileLoop(e) => &e.loc,
             CodeExpr::Continue(e) => &e.loc,
             CodeExpr::Dbg(e) => &e.loc,
             CodeExpr::Defer(e) => &e.loc,
@@ -510,6 +712,8 @@ impl Locatable for CodeExpr {
             CodeExpr::StructLiteral(e) => &e.loc,
             CodeExpr::Assign(e) => &e.loc,
             CodeExpr::FieldAccess(e) => &e.loc,
+            CodeExpr::Index(e) => &e.loc,
+            CodeExpr::Slice(e) => &e.loc,
             CodeExpr::Catch(e) => &e.loc,
             CodeExpr::Paren(e) => &e.loc,
             CodeExpr::FnCall(e) => &e.loc,
@@ -1,10 +1,6 @@
 use crate::{ast::*, core::*, lexer::*, parser_v2::*, wasm::*};
-use alloc::{
-    boxed::Box,
-    format,
-    string::{String, ToString},
-    vec::Vec,
-};
+use alloc::{boxed::Box, collections::BTreeMap, format, rc::Rc, string::String, vec, vec::Vec};
+use core::cell::RefCell;
 
 #[derive(Clone, PartialEq)]
 pub enum LoType {
@@ -30,10 +26,31 @@ pub enum LoType {
     StructInstance {
         struct_name: String,
     },
+    EnumInstance {
+        enum_name: String,
+    },
+    Array {
+        item_type: Box<LoType>,
+        length: u32,
+    },
+    Slice {
+        item_type: Box<LoType>,
+    },
+    Fn {
+        inputs: Vec<LoType>,
+        output: Box<LoType>,
+    },
+    Closure {
+        inputs: Vec<LoType>,
+        output: Box<LoType>,
+    },
     Result {
         ok_type: Box<LoType>,
         err_type: Box<LoType>,
     },
+    Tuple {
+        item_types: Vec<LoType>,
+    },
 }
 
 impl core::fmt::Display for LoType {
@@ -55,7 +72,30 @@ impl core::fmt::Display for LoType {
             LoType::Pointer { pointee } => write!(f, "&{pointee}"),
             LoType::SequencePointer { pointee } => write!(f, "*&{pointee}"),
             LoType::StructInstance { struct_name } => f.write_str(&struct_name),
+            LoType::EnumInstance { enum_name } => f.write_str(&enum_name),
+            LoType::Array { item_type, length } => write!(f, "[{item_type}; {length}]"),
+            LoType::Slice { item_type } => write!(f, "[]{item_type}"),
+            LoType::Fn { inputs, output } => {
+                write!(f, "fn({})", ListDisplay(inputs))?;
+                if output.as_ref() != &LoType::Void {
+                    write!(f, ": {output}")?;
+                }
+                Ok(())
+            }
+            LoType::Closure { inputs, output } => {
+                write!(f, "|{}|", ListDisplay(inputs))?;
+                if output.as_ref() != &LoType::Void {
+                    write!(f, ": {output}")?;
+                }
+                Ok(())
+            }
             LoType::Result { ok_type, err_type } => write!(f, "Result<{ok_type}, {err_type}>"),
+            LoType::Tuple { item_types } => {
+                if item_types.len() == 1 {
+                    return write!(f, "({},)", item_types[0]);
+                }
+                write!(f, "({})", ListDisplay(item_types))
+            }
         }
     }
 }
@@ -68,6 +108,15 @@ impl LoType {
             other => other,
         }
     }
+
+    fn get_call_signature(&self) -> Option<(&Vec<LoType>, &LoType)> {
+        match self {
+            LoType::Fn { inputs, output } | LoType::Closure { inputs, output } => {
+                Some((inputs, output))
+            }
+            _ => None,
+        }
+    }
 }
 
 struct LoFnInfo {
@@ -75,11 +124,11 @@ struct LoFnInfo {
     fn_type: LoFnType,
     fn_source: LoFnSource,
     definition_loc: LoLocation,
+    exported_as: Vec<String>,
 }
 
 enum LoFnSource {
     Guest {
-        exported_as: Option<String>,
         ctx: LoExprContext,
         body: CodeBlockExpr,
     },
@@ -89,6 +138,7 @@ enum LoFnSource {
     },
 }
 
+#[derive(Clone)]
 struct LoFnType {
     inputs: Vec<LoType>,
     output: LoType,
@@ -99,6 +149,57 @@ struct LoExprContext {
     locals: Vec<LoLocal>,
     last_local_index: u32,
     scopes: Vec<LoScope>,
+    fn_return_type: Option<LoType>,
+    type_scope: Vec<LoTypeDef>,
+    closure_env: Option<LoClosureEnv>,
+}
+
+#[derive(Clone)]
+struct LoClosureEnv {
+    enclosing_ctx: Box<LoExprContext>,
+    env_local_index: u32,
+    captures: RefCell<Vec<LoCapture>>,
+}
+
+// captured locals are copied into the environment when the closure is created
+#[derive(Clone)]
+struct LoCapture {
+    local_name: String,
+    local_type: LoType,
+    env_offset: u32,
+}
+
+// closures and instances of generic methods are created during codegen
+// and appended after all other functions
+struct LoLateFn {
+    fn_name: String,
+    type_index: u32,
+    wasm_fn: WasmFn,
+}
+
+// `fn Box::get(self)` is compiled for every `Box<T>` it's called on, e.g. as `Box<u32>::get`
+#[derive(Clone)]
+struct LoGenericMethodInstance {
+    fn_name: String,
+    fn_type: LoFnType,
+    late_fn_index: u32,
+}
+
+// instance bodies are compiled once all regular functions are done
+struct LoPendingFnBody {
+    ctx: LoExprContext,
+    generic_method_index: usize,
+    late_fn_index: u32,
+    fn_name: String,
+    instantiated_at: LoLocation,
+}
+
+// call expression parts passed down to macro expansion and intrinsics
+struct LoCallSite<'a> {
+    name: &'a str,
+    receiver: Option<&'a CodeExpr>,
+    args: &'a Vec<CodeExpr>,
+    loc: &'a LoLocation,
 }
 
 #[derive(Clone)]
@@ -113,14 +214,26 @@ struct LoLocal {
 enum LoScopeType {
     Function,
     Block,
-    Loop,
-    ForLoop,
+    Loop {
+        label: Option<String>,
+        value_type: LoType,
+    },
+    ForLoop {
+        label: Option<String>,
+    },
+    WhileLoop {
+        label: Option<String>,
+    },
+    MatchArm {
+        block_depth: u32,
+    },
 }
 
 #[derive(Clone)]
 struct LoScope {
     scope_type: LoScopeType,
     locals: Vec<LoScopedLocal>,
+    deferred: Vec<Vec<WasmInstr>>,
 }
 
 #[derive(Clone)]
@@ -135,6 +248,7 @@ impl LoExprContext {
         let mut new_scope = LoScope {
             scope_type,
             locals: Vec::new(),
+            deferred: Vec::new(),
         };
 
         if let Some(parent_scope) = self.scopes.last() {
@@ -163,7 +277,13 @@ impl LoExprContext {
     }
 
     fn get_local(&self, local_name: &str) -> Option<&LoLocal> {
-        for local in &self.current_scope().locals {
+        // const contexts (global initializers) have no scopes
+        let Some(scope) = self.scopes.last() else {
+            return None;
+        };
+
+        // later locals shadow the earlier ones, e.g. macro params shadow the caller's
+        for local in scope.locals.iter().rev() {
             if local.local_name == local_name {
                 return Some(&self.locals[local.lo_local_index]);
             }
@@ -179,6 +299,7 @@ struct WasmFnInfo {
     wasm_fn_index: u32,
 }
 
+#[derive(Clone)]
 struct LoTypeDef {
     name: String,
     value: LoType,
@@ -187,8 +308,30 @@ struct LoTypeDef {
 
 struct LoStructDef {
     struct_name: String,
+    generic_struct_name: Option<String>,
+    type_args: Vec<LoType>,
     fields: Vec<LoStructField>,
-    fully_defined: bool, // used for self-reference checks
+    loc: LoLocation,
+}
+
+struct LoTraitImpl {
+    trait_name: String,
+    impl_type: LoType,
+    loc: LoLocation,
+}
+
+struct LoEnumDef {
+    enum_name: String,
+    tag_type: LoType,
+    variants: Vec<LoEnumVariant>,
+}
+
+struct LoEnumVariant {
+    variant_name: String,
+    tag: i64,
+    payload_type: LoType,
+    field_index: u32,
+    byte_offset: u32,
     loc: LoLocation,
 }
 
@@ -227,6 +370,13 @@ enum VariableInfo {
         field_offset: u32,
         value_type: LoType,
     },
+    // item of an array local picked by a runtime index, read and written through `select`s,
+    // `item_local_indices` holds the first local of every item the index can pick
+    LocalItem {
+        item_local_indices: Vec<u32>,
+        index_local_index: u32,
+        value_type: LoType,
+    },
 }
 
 impl VariableInfo {
@@ -245,22 +395,67 @@ impl VariableInfo {
                 field_offset: _,
                 value_type,
             } => value_type,
+            VariableInfo::LocalItem {
+                item_local_indices: _,
+                index_local_index: _,
+                value_type,
+            } => value_type,
+        }
+    }
+
+    fn get_type_ref(&self) -> &LoType {
+        match self {
+            VariableInfo::Local {
+                local_index: _,
+                local_type,
+            } => local_type,
+            VariableInfo::Global {
+                global_index: _,
+                global_type,
+            } => global_type,
+            VariableInfo::Stored {
+                address_local_index: _,
+                field_offset: _,
+                value_type,
+            } => value_type,
+            VariableInfo::LocalItem {
+                item_local_indices: _,
+                index_local_index: _,
+                value_type,
+            } => value_type,
         }
     }
 }
 
 #[derive(Default)]
 pub struct CodeGen {
-    pub errors: LoErrorManager,
+    pub errors: RefCell<LoErrorManager>,
+    pub skip_bounds_checks: bool,
     lo_functions: Vec<LoFnInfo>,
     wasm_functions: Vec<WasmFnInfo>,
     type_defs: Vec<LoTypeDef>,
-    struct_defs: Vec<LoStructDef>,
+    struct_defs: RefCell<Vec<Rc<LoStructDef>>>,
+    generic_struct_defs: Vec<StructDefExpr>,
+    enum_defs: Vec<LoEnumDef>,
     memory: Option<MemoryDefExpr>,
     memory_imported_from: Option<String>,
+    wasm_types: RefCell<Vec<WasmFnType>>,
     static_data_stores: Vec<StaticDataStoreExpr>,
     globals: Vec<LoGlobalDef>,
     const_defs: Vec<ConstDefExpr>,
+    macro_defs: Vec<MacroDefExpr>,
+    trait_defs: Vec<TraitDefExpr>,
+    trait_impls: Vec<LoTraitImpl>,
+    datas: RefCell<Vec<WasmData>>,
+    data_size: RefCell<u32>,
+    string_pool: RefCell<BTreeMap<String, u32>>,
+    enum_name_tables: RefCell<BTreeMap<String, u32>>,
+    fn_table: RefCell<Vec<u32>>,
+    generic_methods: Vec<FnDefExpr>,
+    generic_method_instances: RefCell<Vec<LoGenericMethodInstance>>,
+    pending_generic_method_bodies: RefCell<Vec<LoPendingFnBody>>,
+    late_fns: RefCell<Vec<LoLateFn>>,
+    late_fns_start: u32,
 }
 
 impl CodeGen {
@@ -338,71 +533,7 @@ impl CodeGen {
         for expr in file.ast.exprs {
             match expr {
                 TopLevelExpr::Include(_) => {} // skip, processed earlier
-                TopLevelExpr::FnDef(fn_def) => {
-                    let output = match &fn_def.decl.return_type {
-                        Some(return_type) => self.build_type(return_type)?,
-                        _ => LoType::Void,
-                    };
-
-                    let mut ctx = LoExprContext::default();
-                    ctx.enter_scope(LoScopeType::Function);
-
-                    let mut inputs = Vec::new();
-                    'param_loop: for fn_param in &fn_def.decl.fn_params {
-                        for var in &ctx.current_scope().locals {
-                            if var.local_name == fn_param.param_name {
-                                self.errors.report(LoError {
-                                    message: format!(
-                                        "Duplicate function parameter name: {}",
-                                        fn_param.param_name
-                                    ),
-                                    loc: fn_param.loc.clone(),
-                                });
-                                continue 'param_loop;
-                            }
-                        }
-
-                        let param_type = self.get_fn_param_type(&fn_def.decl, fn_param)?;
-                        inputs.push(param_type.clone());
-
-                        self.define_local(
-                            &mut ctx,
-                            fn_param.loc.clone(),
-                            fn_param.param_name.clone(),
-                            &param_type,
-                            true,
-                        )?;
-                    }
-
-                    let mut exported_as = None;
-                    if fn_def.exported {
-                        exported_as = Some(fn_def.decl.fn_name.repr.clone())
-                    }
-
-                    for fn_info in &self.lo_functions {
-                        if fn_info.fn_name == fn_def.decl.fn_name.repr {
-                            self.errors.report(LoError {
-                                message: format!(
-                                    "Duplicate function definition: {}, previously defined at {}",
-                                    fn_def.decl.fn_name.repr, fn_info.definition_loc
-                                ),
-                                loc: fn_def.decl.loc.clone(),
-                            });
-                            break;
-                        }
-                    }
-
-                    self.lo_functions.push(LoFnInfo {
-                        fn_name: fn_def.decl.fn_name.repr,
-                        fn_type: LoFnType { inputs, output },
-                        fn_source: LoFnSource::Guest {
-                            exported_as,
-                            ctx,
-                            body: fn_def.body,
-                        },
-                        definition_loc: fn_def.loc.clone(),
-                    });
-                }
+                TopLevelExpr::FnDef(fn_def) => self.add_fn_def(fn_def)?,
                 TopLevelExpr::Import(ImportExpr {
                     module_name,
                     items,
@@ -424,6 +555,10 @@ impl CodeGen {
                                     });
                                 }
 
+                                if let Some(data_start) = memory.data_start {
+                                    *self.data_size.borrow_mut() = data_start;
+                                }
+
                                 self.memory = Some(memory);
                                 self.memory_imported_from = Some(module_name.clone());
                                 continue;
@@ -435,7 +570,8 @@ impl CodeGen {
                             output: LoType::Void,
                         };
                         for fn_param in &fn_decl.fn_params {
-                            let param_type = self.get_fn_param_type(&fn_decl, fn_param)?;
+                            let param_type =
+                                self.get_fn_param_type(&fn_decl, fn_param, &Vec::new(), None)?;
                             fn_type.inputs.push(param_type.clone());
                         }
                         if let Some(return_type) = fn_decl.return_type {
@@ -444,7 +580,7 @@ impl CodeGen {
 
                         for fn_info in &self.lo_functions {
                             if fn_info.fn_name == fn_decl.fn_name.repr {
-                                self.errors.report(LoError {
+                                self.errors.borrow_mut().report(LoError {
                                     message: format!(
                                         "Duplicate function definition: {}, previously defined at {}",
                                         fn_decl.fn_name.repr, fn_info.definition_loc
@@ -463,6 +599,7 @@ impl CodeGen {
                                 external_fn_name: fn_decl.fn_name.parts.last().unwrap().clone(),
                             },
                             definition_loc: loc.clone(),
+                            exported_as: Vec::new(),
                         });
                     }
                 }
@@ -497,27 +634,40 @@ impl CodeGen {
                         global_index: self.globals.len() as u32,
                     });
                 }
-                TopLevelExpr::StructDef(StructDefExpr {
-                    struct_name,
-                    fields,
-                    loc,
-                }) => {
-                    if let Some(existing_typedef) = self.get_typedef(&struct_name.repr) {
+                TopLevelExpr::StructDef(struct_def) => {
+                    if let Some(existing_type_loc) =
+                        self.get_defined_type_loc(&struct_def.struct_name.repr)
+                    {
                         return Err(LoError {
                             message: format!(
                                 "Cannot redefine type {}, already defined at {}",
-                                struct_name.repr, existing_typedef.loc
+                                struct_def.struct_name.repr, existing_type_loc
                             ),
-                            loc: struct_name.loc,
+                            loc: struct_def.struct_name.loc,
                         });
                     }
 
-                    self.struct_defs.push(LoStructDef {
+                    // generic structs are instantiated on use
+                    if struct_def.type_params.len() != 0 {
+                        self.check_type_param_bounds(&struct_def.type_params);
+                        self.generic_struct_defs.push(struct_def);
+                        continue;
+                    }
+
+                    let StructDefExpr {
+                        struct_name,
+                        type_params: _,
+                        fields,
+                        loc,
+                    } = struct_def;
+
+                    self.struct_defs.borrow_mut().push(Rc::new(LoStructDef {
                         struct_name: struct_name.repr.clone(),
+                        generic_struct_name: None,
+                        type_args: Vec::new(),
                         fields: Vec::new(),
-                        fully_defined: false,
                         loc: loc.clone(),
-                    });
+                    }));
 
                     self.type_defs.push(LoTypeDef {
                         name: struct_name.repr.clone(),
@@ -527,47 +677,174 @@ impl CodeGen {
                         loc,
                     });
 
-                    let mut struct_layout = LoTypeLayout::default();
-                    let mut struct_fields = Vec::<LoStructField>::new();
+                    let struct_fields =
+                        self.build_struct_fields(&struct_name.repr, &Vec::new(), &fields)?;
+                    self.complete_struct_def(&struct_name.repr, struct_fields);
+                }
+                TopLevelExpr::EnumDef(EnumDefExpr {
+                    enum_name,
+                    tag_type,
+                    variants,
+                    loc,
+                }) => {
+                    if let Some(existing_type_loc) = self.get_defined_type_loc(&enum_name.repr) {
+                        return Err(LoError {
+                            message: format!(
+                                "Cannot redefine type {}, already defined at {}",
+                                enum_name.repr, existing_type_loc
+                            ),
+                            loc: enum_name.loc,
+                        });
+                    }
+
+                    let tag_type = match &tag_type {
+                        Some(tag_type) => self.build_type(tag_type)?,
+                        None => LoType::U32,
+                    };
+                    let (min_tag_value, max_tag_value) = match tag_type {
+                        LoType::U8 => (0, u8::MAX as i64),
+                        LoType::I8 => (i8::MIN as i64, i8::MAX as i64),
+                        LoType::U16 => (0, u16::MAX as i64),
+                        LoType::I16 => (i16::MIN as i64, i16::MAX as i64),
+                        LoType::U32 => (0, u32::MAX as i64),
+                        LoType::I32 => (i32::MIN as i64, i32::MAX as i64),
+                        _ => {
+                            return Err(LoError {
+                                message: format!(
+                                    "Invalid enum tag type: {tag_type}, expected an integer type up to 32 bits"
+                                ),
+                                loc: enum_name.loc,
+                            });
+                        }
+                    };
+
+                    // declared before the variants are built to allow self-references
+                    self.enum_defs.push(LoEnumDef {
+                        enum_name: enum_name.repr.clone(),
+                        tag_type: tag_type.clone(),
+                        variants: Vec::new(),
+                    });
+
+                    self.type_defs.push(LoTypeDef {
+                        name: enum_name.repr.clone(),
+                        value: LoType::EnumInstance {
+                            enum_name: enum_name.repr.clone(),
+                        },
+                        loc,
+                    });
+
+                    // variant tag comes first
+                    let mut enum_layout = LoTypeLayout::default();
+                    self.get_type_layout(&tag_type, &mut enum_layout);
+                    let tag_byte_length = enum_layout.byte_length;
+
+                    let mut enum_variants = Vec::<LoEnumVariant>::new();
+                    let mut next_tag = 0i64;
+
+                    for variant in variants {
+                        for existing_variant in &enum_variants {
+                            if existing_variant.variant_name == variant.variant_name {
+                                return Err(LoError {
+                                    message: format!(
+                                        "Cannot define enum variant with duplicate name: '{}' in enum {}",
+                                        variant.variant_name, enum_name.repr,
+                                    ),
+                                    loc: variant.loc,
+                                });
+                            }
+                        }
+
+                        if let Some(tag_value) = &variant.tag_value {
+                            // negative literals are only matched for signed tag types
+                            let Some((literal, is_negative, _)) =
+                                self.get_contextual_int_literal(tag_value, Some(&tag_type))
+                            else {
+                                return Err(LoError {
+                                    message: format!(
+                                        "Enum variant value must be an untagged integer literal"
+                                    ),
+                                    loc: tag_value.loc().clone(),
+                                });
+                            };
+
+                            next_tag = literal.value.min(i64::MAX as u64) as i64;
+                            if is_negative {
+                                next_tag = -next_tag;
+                            }
+                        }
+
+                        if next_tag < min_tag_value || next_tag > max_tag_value {
+                            return Err(LoError {
+                                message: format!(
+                                    "Enum variant value {next_tag} of {}::{} does not fit into {tag_type}",
+                                    enum_name.repr, variant.variant_name
+                                ),
+                                loc: variant.loc,
+                            });
+                        }
+                        let tag = next_tag;
+                        next_tag += 1;
 
-                    for field in fields {
-                        for existing_field in &struct_fields {
-                            if existing_field.field_name == field.field_name {
+                        for existing_variant in &enum_variants {
+                            if existing_variant.tag == tag {
                                 return Err(LoError {
                                     message: format!(
-                                        "Cannot define struct field with duplicate name: '{}' in struct {}",
-                                        field.field_name, struct_name.repr,
+                                        "Duplicate enum value {tag} of {}::{}, already used by {}::{}",
+                                        enum_name.repr,
+                                        variant.variant_name,
+                                        enum_name.repr,
+                                        existing_variant.variant_name
                                     ),
-                                    loc: field.loc,
+                                    loc: variant.loc,
                                 });
                             }
                         }
 
-                        // TODO: add self reference check
-                        let field_type = self.build_type(&field.field_type)?;
+                        let payload_type = match &variant.payload_type {
+                            Some(payload_type) => self.build_type(payload_type)?,
+                            None => LoType::Void,
+                        };
+
+                        let mut cycle_path = vec![format!(
+                            "{}::{} at {}",
+                            enum_name.repr, variant.variant_name, variant.loc
+                        )];
+                        if self.find_struct_cycle(&enum_name.repr, &payload_type, &mut cycle_path) {
+                            return Err(LoError {
+                                message: format!(
+                                    "Cannot define recursive enum {}: {} -> {}",
+                                    enum_name.repr,
+                                    cycle_path.join(" -> "),
+                                    enum_name.repr
+                                ),
+                                loc: variant.loc,
+                            });
+                        }
 
-                        struct_fields.push(LoStructField {
-                            field_name: field.field_name,
-                            field_type: field_type.clone(),
-                            field_index: struct_layout.primities_count,
-                            byte_offset: struct_layout.byte_length,
-                            loc: field.loc,
+                        enum_variants.push(LoEnumVariant {
+                            variant_name: variant.variant_name,
+                            tag,
+                            payload_type: payload_type.clone(),
+                            field_index: enum_layout.primities_count,
+                            // all payloads share the bytes right after the tag
+                            byte_offset: tag_byte_length,
+                            loc: variant.loc,
                         });
 
-                        // append field's layout to total struct layout
-                        self.get_type_layout(&field_type, &mut struct_layout);
+                        self.get_type_layout(&payload_type, &mut enum_layout);
                     }
 
-                    let struct_def = self.get_struct_def_mut(&struct_name.repr).unwrap();
-                    struct_def.fields.append(&mut struct_fields);
-                    struct_def.fully_defined = true;
+                    let enum_def = self.enum_defs.last_mut().unwrap();
+                    enum_def.variants.append(&mut enum_variants);
                 }
                 TopLevelExpr::TypeDef(typedef) => {
-                    if let Some(existing_typedef) = self.get_typedef(&typedef.type_name.repr) {
+                    if let Some(existing_type_loc) =
+                        self.get_defined_type_loc(&typedef.type_name.repr)
+                    {
                         return Err(LoError {
                             message: format!(
                                 "Cannot redefine type {}, already defined at {}",
-                                typedef.type_name.repr, existing_typedef.loc
+                                typedef.type_name.repr, existing_type_loc
                             ),
                             loc: typedef.loc,
                         });
@@ -605,73 +882,469 @@ impl CodeGen {
                         });
                     }
 
+                    if let Some(data_start) = memory.data_start {
+                        *self.data_size.borrow_mut() = data_start;
+                    }
+
                     self.memory = Some(memory);
                 }
                 TopLevelExpr::StaticDataStore(static_data_store) => {
                     self.static_data_stores.push(static_data_store);
                 }
-                TopLevelExpr::ExportExistingFn(_) => return Err(LoError::todo(file!(), line!())),
-                TopLevelExpr::MacroDef(_) => return Err(LoError::todo(file!(), line!())),
+                TopLevelExpr::ExportExistingFn(ExportExistingFnExpr {
+                    in_fn_name,
+                    out_fn_name,
+                    loc,
+                }) => {
+                    let out_fn_name = Lexer::unescape_string(&out_fn_name);
+
+                    for fn_info in &self.lo_functions {
+                        if fn_info.exported_as.contains(&out_fn_name) {
+                            self.errors.borrow_mut().report(LoError {
+                                message: format!(
+                                    "Cannot export {} as \"{}\", already exported by {}",
+                                    in_fn_name.repr, out_fn_name, fn_info.fn_name
+                                ),
+                                loc: loc.clone(),
+                            });
+                        }
+                    }
+
+                    let Some(fn_info) = self
+                        .lo_functions
+                        .iter_mut()
+                        .find(|fn_info| fn_info.fn_name == in_fn_name.repr)
+                    else {
+                        self.errors.borrow_mut().report(LoError {
+                            message: format!("Cannot export unknown function {}", in_fn_name.repr),
+                            loc: in_fn_name.loc.clone(),
+                        });
+                        continue;
+                    };
+
+                    fn_info.exported_as.push(out_fn_name);
+                }
+                TopLevelExpr::MacroDef(macro_def) => {
+                    if let Some(existing_macro) = self.get_macro_def(&macro_def.macro_name.repr) {
+                        self.errors.borrow_mut().report(LoError {
+                            message: format!(
+                                "Duplicate macro definition: {}, previously defined at {}",
+                                macro_def.macro_name.repr, existing_macro.loc
+                            ),
+                            loc: macro_def.macro_name.loc.clone(),
+                        });
+                        continue;
+                    }
+
+                    self.check_type_param_bounds(&macro_def.macro_type_params);
+                    self.macro_defs.push(macro_def);
+                }
+                TopLevelExpr::TraitDef(trait_def) => {
+                    if let Some(existing_trait) = self.get_trait_def(&trait_def.trait_name.repr) {
+                        self.errors.borrow_mut().report(LoError {
+                            message: format!(
+                                "Duplicate trait definition: {}, previously defined at {}",
+                                trait_def.trait_name.repr, existing_trait.loc
+                            ),
+                            loc: trait_def.trait_name.loc.clone(),
+                        });
+                        continue;
+                    }
+
+                    for (method, method_index) in trait_def.methods.iter().zip(0..) {
+                        let previous_methods = &trait_def.methods[..method_index];
+                        if let Some(existing_method) = previous_methods
+                            .iter()
+                            .find(|m| m.fn_name.repr == method.fn_name.repr)
+                        {
+                            self.errors.borrow_mut().report(LoError {
+                                message: format!(
+                                    "Duplicate trait method: {}, previously defined at {}",
+                                    method.fn_name.repr, existing_method.loc
+                                ),
+                                loc: method.loc.clone(),
+                            });
+                        }
+                    }
+
+                    self.trait_defs.push(trait_def);
+                }
+                TopLevelExpr::TraitImpl(trait_impl) => self.add_trait_impl(trait_impl)?,
             }
         }
 
         Ok(())
     }
 
-    pub fn generate(&mut self) -> Result<WasmModule, LoError> {
-        let mut wasm_module = WasmModule::default();
+    fn add_fn_def(&mut self, fn_def: FnDefExpr) -> Result<(), LoError> {
+        if self.is_generic_method_def(&fn_def.decl) {
+            self.generic_methods.push(fn_def);
+            return Ok(());
+        }
+
+        let fn_name = self.get_fn_def_name(&fn_def.decl)?;
+        let (ctx, fn_type) = self.build_fn_ctx(&fn_def.decl, &Vec::new(), None)?;
+
+        let mut exported_as = Vec::new();
+        if fn_def.exported {
+            exported_as.push(fn_def.decl.fn_name.repr.clone());
+        }
 
-        let mut fn_imports_count = 0;
         for fn_info in &self.lo_functions {
-            if let LoFnSource::Host { .. } = fn_info.fn_source {
-                fn_imports_count += 1;
+            if fn_info.fn_name == fn_name {
+                self.errors.borrow_mut().report(LoError {
+                    message: format!(
+                        "Duplicate function definition: {}, previously defined at {}",
+                        fn_name, fn_info.definition_loc
+                    ),
+                    loc: fn_def.decl.loc.clone(),
+                });
+                break;
             }
         }
 
-        // resolve wasm fn indicies and populate type, import and export sections
-        let mut wasm_import_fn_index = 0;
-        let mut wasm_fn_index = fn_imports_count;
-        for lo_fn_index in 0..self.lo_functions.len() {
-            let fn_info = &self.lo_functions[lo_fn_index];
+        self.lo_functions.push(LoFnInfo {
+            fn_name,
+            fn_type,
+            fn_source: LoFnSource::Guest {
+                ctx,
+                body: fn_def.body,
+            },
+            definition_loc: fn_def.loc.clone(),
+            exported_as,
+        });
 
-            let mut wasm_fn_type = WasmFnType {
-                inputs: Vec::new(),
-                outputs: Vec::new(),
-            };
-            for lo_input_type in &fn_info.fn_type.inputs {
-                self.lower_type(lo_input_type, &mut wasm_fn_type.inputs);
-            }
-            self.lower_type(&fn_info.fn_type.output, &mut wasm_fn_type.outputs);
+        Ok(())
+    }
+
+    // methods of generic structs with an untyped `self` are compiled per instantiation
+    fn is_generic_method_def(&self, fn_decl: &FnDeclExpr) -> bool {
+        let Some((self_type_name, _)) = fn_decl.fn_name.repr.rsplit_once("::") else {
+            return false;
+        };
+        if self.get_generic_struct_def(self_type_name).is_none() {
+            return false;
+        }
 
-            let mut fn_type_index = wasm_module.types.len() as u32;
-            for (existing_fn_type, existing_type_index) in wasm_module.types.iter().zip(0..) {
-                if wasm_fn_type == *existing_fn_type {
-                    fn_type_index = existing_type_index;
+        for fn_param in &fn_decl.fn_params {
+            if fn_param.param_name == "self" {
+                if let FnParamType::Type { expr: _ } = fn_param.param_type {
+                    return false;
                 }
             }
-            if fn_type_index == wasm_module.types.len() as u32 {
-                wasm_module.types.push(wasm_fn_type);
+        }
+
+        true
+    }
+
+    // `fn Box::get(self: Box<u32>)` is only a method of `Box<u32>`
+    fn get_fn_def_name(&self, fn_decl: &FnDeclExpr) -> Result<String, LoError> {
+        let fn_name = &fn_decl.fn_name.repr;
+        let Some((self_type_name, method_name)) = fn_name.rsplit_once("::") else {
+            return Ok(fn_name.clone());
+        };
+
+        for fn_param in &fn_decl.fn_params {
+            let (FnParamType::Type { expr }, "self") =
+                (&fn_param.param_type, fn_param.param_name.as_str())
+            else {
+                continue;
+            };
+
+            let self_type = self.build_type(expr)?;
+            if let LoType::StructInstance { struct_name } = self_type.deref_rec() {
+                let struct_def = self.get_struct_def(struct_name).unwrap();
+                if struct_def.generic_struct_name.as_deref() == Some(self_type_name) {
+                    return Ok(format!("{struct_name}::{method_name}"));
+                }
             }
+        }
 
-            match &fn_info.fn_source {
-                LoFnSource::Guest {
-                    exported_as,
-                    ctx: _,
-                    body: _,
-                } => {
+        Ok(fn_name.clone())
+    }
+
+    fn build_fn_ctx(
+        &self,
+        fn_decl: &FnDeclExpr,
+        type_scope: &Vec<LoTypeDef>,
+        self_type: Option<&LoType>,
+    ) -> Result<(LoExprContext, LoFnType), LoError> {
+        let output = match &fn_decl.return_type {
+            Some(return_type) => self.build_type_in_scope(type_scope, return_type)?,
+            _ => LoType::Void,
+        };
+
+        let mut ctx = LoExprContext::default();
+        ctx.fn_return_type = Some(output.clone());
+        ctx.type_scope = type_scope.clone();
+        ctx.enter_scope(LoScopeType::Function);
+
+        let mut inputs = Vec::new();
+        'param_loop: for fn_param in &fn_decl.fn_params {
+            for var in &ctx.current_scope().locals {
+                if var.local_name == fn_param.param_name {
+                    self.errors.borrow_mut().report(LoError {
+                        message: format!(
+                            "Duplicate function parameter name: {}",
+                            fn_param.param_name
+                        ),
+                        loc: fn_param.loc.clone(),
+                    });
+                    continue 'param_loop;
+                }
+            }
+
+            let param_type = self.get_fn_param_type(fn_decl, fn_param, type_scope, self_type)?;
+            inputs.push(param_type.clone());
+
+            self.define_local(
+                &mut ctx,
+                fn_param.loc.clone(),
+                fn_param.param_name.clone(),
+                &param_type,
+                true,
+            )?;
+        }
+
+        Ok((ctx, LoFnType { inputs, output }))
+    }
+
+    // trait methods become regular `Type::method` functions, calls to them are resolved statically
+    fn add_trait_impl(&mut self, trait_impl: TraitImplExpr) -> Result<(), LoError> {
+        let TraitImplExpr {
+            trait_name,
+            type_name,
+            methods,
+            loc,
+        } = trait_impl;
+
+        let Some(trait_def) = self.get_trait_def(&trait_name.repr) else {
+            return Err(LoError {
+                message: format!("Unknown trait: {}", trait_name.repr),
+                loc: trait_name.loc,
+            });
+        };
+
+        let impl_type = self.get_type_or_err(&type_name.repr, &type_name.loc)?;
+
+        for existing_impl in &self.trait_impls {
+            if existing_impl.trait_name == trait_name.repr && existing_impl.impl_type == impl_type {
+                self.errors.borrow_mut().report(LoError {
+                    message: format!(
+                        "Duplicate implementation of trait {} for {}, previously implemented at {}",
+                        trait_name.repr, impl_type, existing_impl.loc
+                    ),
+                    loc,
+                });
+                return Ok(());
+            }
+        }
+
+        let mut trait_methods = Vec::new();
+        for method in &trait_def.methods {
+            let method_type = self.build_method_type(method, &impl_type)?;
+            trait_methods.push((method.fn_name.repr.clone(), method_type, method.loc.clone()));
+        }
+
+        for (method_name, _, method_loc) in &trait_methods {
+            if !methods.iter().any(|m| &m.decl.fn_name.repr == method_name) {
+                self.errors.borrow_mut().report(LoError {
+                    message: format!(
+                        "Missing method {} in implementation of trait {} for {}, declared at {}",
+                        method_name, trait_name.repr, impl_type, method_loc
+                    ),
+                    loc: loc.clone(),
+                });
+            }
+        }
+
+        self.trait_impls.push(LoTraitImpl {
+            trait_name: trait_name.repr.clone(),
+            impl_type: impl_type.clone(),
+            loc,
+        });
+
+        for mut method in methods {
+            let method_name = method.decl.fn_name.repr.clone();
+
+            let Some((_, expected_type, method_loc)) = trait_methods
+                .iter()
+                .find(|(name, _, _)| *name == method_name)
+            else {
+                self.errors.borrow_mut().report(LoError {
+                    message: format!(
+                        "Method {} is not a member of trait {}",
+                        method_name, trait_name.repr
+                    ),
+                    loc: method.decl.loc.clone(),
+                });
+                continue;
+            };
+
+            let method_type = self.build_method_type(&method.decl, &impl_type)?;
+            if method_type.inputs != expected_type.inputs
+                || method_type.output != expected_type.output
+            {
+                self.errors.borrow_mut().report(LoError {
+                    message: format!(
+                        "Method {}::{} has type {}, expected {} as declared in trait {} at {}",
+                        type_name.repr,
+                        method_name,
+                        LoType::Fn {
+                            inputs: method_type.inputs,
+                            output: Box::new(method_type.output),
+                        },
+                        LoType::Fn {
+                            inputs: expected_type.inputs.clone(),
+                            output: Box::new(expected_type.output.clone()),
+                        },
+                        trait_name.repr,
+                        method_loc
+                    ),
+                    loc: method.decl.loc.clone(),
+                });
+                continue;
+            }
+
+            let mut fn_name_parts = type_name.parts.clone();
+            fn_name_parts.push(method_name.clone());
+            method.decl.fn_name = IdentExpr {
+                repr: format!("{}::{}", type_name.repr, method_name),
+                parts: fn_name_parts,
+                loc: method.decl.fn_name.loc,
+            };
+
+            self.add_fn_def(method)?;
+        }
+
+        Ok(())
+    }
+
+    // `self` params take the implementing type
+    fn build_method_type(
+        &self,
+        method: &FnDeclExpr,
+        self_type: &LoType,
+    ) -> Result<LoFnType, LoError> {
+        let mut inputs = Vec::new();
+        for fn_param in &method.fn_params {
+            inputs.push(match &fn_param.param_type {
+                FnParamType::Self_ => self_type.clone(),
+                FnParamType::SelfRef => LoType::Pointer {
+                    pointee: Box::new(self_type.clone()),
+                },
+                FnParamType::Type { expr } => self.build_type(expr)?,
+            });
+        }
+
+        let output = match &method.return_type {
+            Some(return_type) => self.build_type(return_type)?,
+            None => LoType::Void,
+        };
+
+        Ok(LoFnType { inputs, output })
+    }
+
+    fn check_type_param_bounds(&self, type_params: &Vec<TypeParam>) {
+        for type_param in type_params {
+            for trait_bound in &type_param.trait_bounds {
+                if let None = self.get_trait_def(&trait_bound.repr) {
+                    self.errors.borrow_mut().report(LoError {
+                        message: format!("Unknown trait: {}", trait_bound.repr),
+                        loc: trait_bound.loc.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_trait_bounds(
+        &self,
+        type_param: &TypeParam,
+        type_arg: &LoType,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        for trait_bound in &type_param.trait_bounds {
+            // unknown traits are reported at the definition
+            let Some(trait_def) = self.get_trait_def(&trait_bound.repr) else {
+                continue;
+            };
+
+            let is_implemented = self.trait_impls.iter().any(|trait_impl| {
+                trait_impl.trait_name == trait_bound.repr && trait_impl.impl_type == *type_arg
+            });
+            if is_implemented {
+                continue;
+            }
+
+            let bound = format!(
+                "bound `{}: {}` at {}",
+                type_param.name, trait_bound.repr, trait_bound.loc
+            );
+
+            for method in &trait_def.methods {
+                let fn_name = self.get_fn_name_from_method(type_arg, &method.fn_name.repr);
+                if !self.lo_functions.iter().any(|f| f.fn_name == fn_name) {
+                    return Err(LoError {
+                        message: format!(
+                            "Type {} does not implement trait {}: missing method {} declared at {}, required by {}",
+                            type_arg, trait_bound.repr, fn_name, method.loc, bound
+                        ),
+                        loc: loc.clone(),
+                    });
+                }
+            }
+
+            return Err(LoError {
+                message: format!(
+                    "Type {} does not implement trait {}, required by {}",
+                    type_arg, trait_bound.repr, bound
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn generate(&mut self) -> Result<WasmModule, LoError> {
+        let mut wasm_module = WasmModule::default();
+
+        let mut fn_imports_count = 0;
+        for fn_info in &self.lo_functions {
+            if let LoFnSource::Host { .. } = fn_info.fn_source {
+                fn_imports_count += 1;
+            }
+        }
+
+        // resolve wasm fn indicies and populate type, import and export sections
+        let mut wasm_import_fn_index = 0;
+        let mut wasm_fn_index = fn_imports_count;
+        for lo_fn_index in 0..self.lo_functions.len() {
+            let fn_info = &self.lo_functions[lo_fn_index];
+
+            let wasm_fn_type = self.lower_fn_type(&fn_info.fn_type.inputs, &fn_info.fn_type.output);
+            let fn_type_index = self.insert_fn_type(wasm_fn_type);
+
+            match &fn_info.fn_source {
+                LoFnSource::Guest { ctx: _, body: _ } => {
                     wasm_module.functions.push(fn_type_index);
                     self.wasm_functions.push(WasmFnInfo {
                         fn_name: fn_info.fn_name.clone(),
                         lo_fn_index,
                         wasm_fn_index,
                     });
-                    if let Some(export_name) = &exported_as {
+                    for export_name in &fn_info.exported_as {
                         wasm_module.exports.push(WasmExport {
                             export_type: WasmExportType::Func,
                             export_name: export_name.clone(),
                             exported_item_index: wasm_fn_index,
                         });
                     }
+                    wasm_module.debug_fn_info.push(WasmDebugFnInfo {
+                        fn_index: wasm_fn_index,
+                        fn_name: fn_info.fn_name.clone(),
+                    });
 
                     wasm_fn_index += 1;
                 }
@@ -684,6 +1357,13 @@ impl CodeGen {
                         lo_fn_index,
                         wasm_fn_index: wasm_import_fn_index,
                     });
+                    for export_name in &fn_info.exported_as {
+                        wasm_module.exports.push(WasmExport {
+                            export_type: WasmExportType::Func,
+                            export_name: export_name.clone(),
+                            exported_item_index: wasm_import_fn_index,
+                        });
+                    }
                     wasm_module.imports.push(WasmImport {
                         module_name: module_name.clone(),
                         item_name: external_fn_name.clone(),
@@ -696,54 +1376,55 @@ impl CodeGen {
             }
         }
 
+        // closures are appended after all other functions
+        self.late_fns_start = wasm_fn_index;
+
         // build function codes
         for i in 0..self.wasm_functions.len() {
             let wasm_fn_info = &self.wasm_functions[i];
             let lo_fn_info = &self.lo_functions[wasm_fn_info.lo_fn_index];
 
-            let LoFnSource::Guest {
-                exported_as: _,
-                ctx,
-                body,
-            } = &lo_fn_info.fn_source
-            else {
+            let LoFnSource::Guest { ctx, body } = &lo_fn_info.fn_source else {
                 continue;
             };
 
             let mut ctx = ctx.clone();
             let mut wasm_expr = WasmExpr { instrs: Vec::new() };
-            for expr in &body.exprs {
-                self.codegen(&mut ctx, &mut wasm_expr.instrs, expr)?;
-            }
+            self.codegen_code_block(&mut ctx, &mut wasm_expr.instrs, &body.exprs);
+            self.codegen_deferred(&mut wasm_expr.instrs, ctx.current_scope());
 
-            let mut wasm_locals_flat = Vec::new();
-            for local in &ctx.locals {
-                if local.is_fn_param {
-                    continue;
-                }
-
-                self.lower_type(&local.local_type, &mut wasm_locals_flat);
-            }
+            wasm_module.codes.push(WasmFn {
+                locals: self.lower_locals(&ctx),
+                expr: wasm_expr,
+            });
+        }
 
-            let mut wasm_locals = Vec::<WasmLocals>::new();
-            for wasm_local_type in wasm_locals_flat {
-                if let Some(wasm_locals_of_type) = wasm_locals.last_mut() {
-                    if wasm_locals_of_type.value_type == wasm_local_type {
-                        wasm_locals_of_type.count += 1;
-                        continue;
-                    }
-                }
+        // instances may instantiate more generic methods while being compiled
+        loop {
+            let Some(pending) = self.pending_generic_method_bodies.borrow_mut().pop() else {
+                break;
+            };
 
-                wasm_locals.push(WasmLocals {
-                    count: 1,
-                    value_type: wasm_local_type,
-                });
-            }
+            let mut ctx = pending.ctx;
+            let body = &self.generic_methods[pending.generic_method_index].body;
+            let errors_before = self.errors.borrow().count();
+            let mut wasm_expr = WasmExpr { instrs: Vec::new() };
+            self.codegen_code_block(&mut ctx, &mut wasm_expr.instrs, &body.exprs);
+            self.codegen_deferred(&mut wasm_expr.instrs, ctx.current_scope());
+
+            // the same body can fail for one instance only, so point at the one that failed
+            self.errors.borrow_mut().add_note_since(
+                errors_before,
+                &format!(
+                    ", in {} instantiated at {}",
+                    pending.fn_name, pending.instantiated_at
+                ),
+            );
 
-            wasm_module.codes.push(WasmFn {
-                locals: wasm_locals,
+            self.late_fns.borrow_mut()[pending.late_fn_index as usize].wasm_fn = WasmFn {
+                locals: self.lower_locals(&ctx),
                 expr: wasm_expr,
-            });
+            };
         }
 
         if let Some(memory) = &self.memory {
@@ -794,16 +1475,23 @@ impl CodeGen {
         }
 
         let mut wasm_types_buf = Vec::with_capacity(1);
+        let mut indicies_of_data_size_globals = Vec::new();
         for global in &self.globals {
             self.lower_type(&global.global_type, &mut wasm_types_buf);
             let wasm_value_type = wasm_types_buf.pop().unwrap();
 
             let mut initial_value = WasmExpr { instrs: Vec::new() };
-            self.codegen(
+            if let CodeExpr::GetDataSize(_) = &global.def_expr.expr {
+                // patched below, once all data segments are placed
+                indicies_of_data_size_globals.push(wasm_module.globals.len());
+                initial_value.instrs.push(WasmInstr::I32Const { value: 0 });
+            } else if let Err(err) = self.codegen(
                 const_expr_ctx,
                 &mut initial_value.instrs,
                 &global.def_expr.expr,
-            )?;
+            ) {
+                self.errors.borrow_mut().report(err);
+            }
 
             wasm_module.globals.push(WasmGlobal {
                 mutable: true,
@@ -812,13 +1500,52 @@ impl CodeGen {
             });
         }
 
+        let data_size = *self.data_size.borrow();
+        for global_index in indicies_of_data_size_globals {
+            wasm_module.globals[global_index].initial_value.instrs = vec![WasmInstr::I32Const {
+                value: data_size as i32,
+            }];
+        }
+
+        for (late_fn, late_fn_index) in self.late_fns.borrow_mut().drain(..).zip(0..) {
+            wasm_module.functions.push(late_fn.type_index);
+            wasm_module.debug_fn_info.push(WasmDebugFnInfo {
+                fn_index: self.late_fns_start + late_fn_index,
+                fn_name: late_fn.fn_name,
+            });
+            wasm_module.codes.push(late_fn.wasm_fn);
+        }
+
+        // slot 0 is left empty so calling a zeroed function pointer traps
+        let fn_table = self.fn_table.borrow();
+        if fn_table.len() != 0 {
+            let table_size = fn_table.len() as u32 + 1;
+            wasm_module.tables.push(WasmTable {
+                limits: WasmLimits {
+                    min: table_size,
+                    max: Some(table_size),
+                },
+            });
+            wasm_module.elements.push(WasmElement::Passive {
+                expr: WasmExpr {
+                    instrs: vec![WasmInstr::I32Const { value: 1 }],
+                },
+                fn_idx: fn_table.clone(),
+            });
+        }
+
+        wasm_module.types.append(&mut self.wasm_types.borrow_mut());
+        wasm_module.datas.append(&mut self.datas.borrow_mut());
+
         Ok(wasm_module)
     }
 
     fn get_fn_param_type(
-        &mut self,
+        &self,
         fn_decl: &FnDeclExpr,
         fn_param: &FnParam,
+        type_scope: &Vec<LoTypeDef>,
+        self_type: Option<&LoType>,
     ) -> Result<LoType, LoError> {
         match &fn_param.param_type {
             FnParamType::Self_ | FnParamType::SelfRef => {
@@ -829,8 +1556,10 @@ impl CodeGen {
                     });
                 }
 
-                let self_type_name = &fn_decl.fn_name.parts[0];
-                let self_type = self.get_type_or_err(self_type_name, &fn_decl.loc)?;
+                let self_type = match self_type {
+                    Some(self_type) => self_type.clone(),
+                    None => self.get_type_or_err(&fn_decl.fn_name.parts[0], &fn_decl.loc)?,
+                };
 
                 if let FnParamType::Self_ = fn_param.param_type {
                     return Ok(self_type);
@@ -840,20 +1569,43 @@ impl CodeGen {
                     pointee: Box::new(self_type),
                 });
             }
-            FnParamType::Type { expr } => self.build_type(&expr),
+            FnParamType::Type { expr } => self.build_type_in_scope(type_scope, &expr),
         }
     }
 
     fn build_type(&self, type_expr: &TypeExpr) -> Result<LoType, LoError> {
+        self.build_type_in_scope(&Vec::new(), type_expr)
+    }
+
+    fn build_type_in_scope(
+        &self,
+        type_scope: &Vec<LoTypeDef>,
+        type_expr: &TypeExpr,
+    ) -> Result<LoType, LoError> {
         match type_expr {
-            TypeExpr::Named { name } => self.get_type_or_err(&name.repr, &name.loc),
+            TypeExpr::Named { name } => {
+                for type_def in type_scope.iter().rev() {
+                    if type_def.name == name.repr {
+                        return Ok(type_def.value.clone());
+                    }
+                }
+
+                if let Some(_) = self.get_generic_struct_def(&name.repr) {
+                    return Err(LoError {
+                        message: format!("Missing type args for generic struct {}", name.repr),
+                        loc: name.loc.clone(),
+                    });
+                }
+
+                self.get_type_or_err(&name.repr, &name.loc)
+            }
             TypeExpr::Pointer { pointee, loc: _ } => {
-                let pointee = Box::new(self.build_type(&pointee)?);
+                let pointee = Box::new(self.build_type_in_scope(type_scope, &pointee)?);
 
                 Ok(LoType::Pointer { pointee })
             }
             TypeExpr::SequencePointer { pointee, loc: _ } => {
-                let pointee = Box::new(self.build_type(&pointee)?);
+                let pointee = Box::new(self.build_type_in_scope(type_scope, &pointee)?);
 
                 Ok(LoType::SequencePointer { pointee })
             }
@@ -862,76 +1614,359 @@ impl CodeGen {
                 err_type,
                 loc: _,
             } => {
-                let ok_type = Box::new(self.build_type(&ok_type)?);
-                let err_type = Box::new(self.build_type(&err_type)?);
+                let ok_type = Box::new(self.build_type_in_scope(type_scope, &ok_type)?);
+                let err_type = Box::new(self.build_type_in_scope(type_scope, &err_type)?);
 
                 Ok(LoType::Result { ok_type, err_type })
             }
             TypeExpr::Of {
                 container_type,
-                item_type: _,
-                loc: _,
+                item_type,
+                loc,
             } => {
-                let actual_type = self.build_type(container_type)?;
+                if let TypeExpr::Named { name } = container_type.as_ref() {
+                    if let Some(generic_def) = self.get_generic_struct_def(&name.repr) {
+                        let item_type = self.build_type_in_scope(type_scope, item_type)?;
+                        let struct_def =
+                            self.get_struct_instance(generic_def, vec![item_type], loc)?;
+
+                        return Ok(LoType::StructInstance {
+                            struct_name: struct_def.struct_name.clone(),
+                        });
+                    }
+                }
+
+                let actual_type = self.build_type_in_scope(type_scope, container_type)?;
 
                 Ok(actual_type)
             }
-        }
-    }
+            TypeExpr::Generic {
+                name,
+                type_args,
+                loc,
+            } => {
+                let struct_def =
+                    self.get_generic_struct_instance(type_scope, name, type_args, loc)?;
 
-    fn codegen(
-        &self,
-        ctx: &mut LoExprContext,
-        instrs: &mut Vec<WasmInstr>,
-        expr: &CodeExpr,
-    ) -> Result<(), LoError> {
-        match expr {
-            CodeExpr::BoolLiteral(BoolLiteralExpr { value, loc: _ }) => {
-                if *value {
-                    instrs.push(WasmInstr::I32Const { value: 1 });
-                } else {
-                    instrs.push(WasmInstr::I32Const { value: 0 });
+                Ok(LoType::StructInstance {
+                    struct_name: struct_def.struct_name.clone(),
+                })
+            }
+            TypeExpr::Array {
+                item_type,
+                length,
+                loc: _,
+            } => {
+                let item_type = Box::new(self.build_type_in_scope(type_scope, item_type)?);
+
+                Ok(LoType::Array {
+                    item_type,
+                    length: *length,
+                })
+            }
+            TypeExpr::Slice { item_type, loc: _ } => {
+                let item_type = Box::new(self.build_type_in_scope(type_scope, item_type)?);
+
+                Ok(LoType::Slice { item_type })
+            }
+            TypeExpr::Fn {
+                inputs,
+                output,
+                loc: _,
+            } => {
+                let mut input_types = Vec::new();
+                for input in inputs {
+                    input_types.push(self.build_type_in_scope(type_scope, input)?);
                 }
+
+                let output = match output {
+                    Some(output) => self.build_type_in_scope(type_scope, output)?,
+                    None => LoType::Void,
+                };
+
+                Ok(LoType::Fn {
+                    inputs: input_types,
+                    output: Box::new(output),
+                })
             }
-            CodeExpr::CharLiteral(_) => todo!(),
-            CodeExpr::IntLiteral(IntLiteralExpr {
-                repr: _,
-                value,
-                tag,
+            TypeExpr::Closure {
+                inputs,
+                output,
                 loc: _,
-            }) => match tag.as_deref() {
-                Some("u32") | Some("i32") | None => instrs.push(WasmInstr::I32Const {
-                    value: *value as i32,
-                }),
-                Some("u64") | Some("i64") => instrs.push(WasmInstr::I64Const {
-                    value: *value as i64,
-                }),
-                _ => todo!(),
-            },
-            CodeExpr::StringLiteral(_) => todo!(),
-            CodeExpr::StructLiteral(StructLiteralExpr {
-                struct_name,
-                fields,
-                loc,
-            }) => {
-                let Some(struct_def) = self.get_struct_def(&struct_name.repr) else {
-                    return Err(LoError {
-                        message: format!("Unknown struct: {}", struct_name.repr),
-                        loc: loc.clone(),
-                    });
+            } => {
+                let mut input_types = Vec::new();
+                for input in inputs {
+                    input_types.push(self.build_type_in_scope(type_scope, input)?);
+                }
+
+                let output = match output {
+                    Some(output) => self.build_type_in_scope(type_scope, output)?,
+                    None => LoType::Void,
                 };
 
-                for field_index in 0..fields.len() {
-                    let field_literal = &fields[field_index];
-                    let Some(struct_field) = struct_def.fields.get(field_index) else {
-                        return Err(LoError {
-                            message: format!("Excess field values"),
-                            loc: field_literal.loc.clone(),
-                        });
-                    };
+                Ok(LoType::Closure {
+                    inputs: input_types,
+                    output: Box::new(output),
+                })
+            }
+            TypeExpr::Tuple { item_types, loc: _ } => {
+                let mut lo_item_types = Vec::new();
+                for item_type in item_types {
+                    lo_item_types.push(self.build_type_in_scope(type_scope, item_type)?);
+                }
 
-                    if &field_literal.field_name != &struct_field.field_name {
-                        return Err(LoError {
+                Ok(LoType::Tuple {
+                    item_types: lo_item_types,
+                })
+            }
+        }
+    }
+
+    fn get_generic_struct_instance(
+        &self,
+        type_scope: &Vec<LoTypeDef>,
+        struct_name: &IdentExpr,
+        type_args: &Vec<TypeExpr>,
+        loc: &LoLocation,
+    ) -> Result<Rc<LoStructDef>, LoError> {
+        let Some(generic_def) = self.get_generic_struct_def(&struct_name.repr) else {
+            return Err(LoError {
+                message: format!("Unknown generic struct: {}", struct_name.repr),
+                loc: struct_name.loc.clone(),
+            });
+        };
+
+        let mut type_arg_values = Vec::new();
+        for type_arg in type_args {
+            type_arg_values.push(self.build_type_in_scope(type_scope, type_arg)?);
+        }
+
+        self.get_struct_instance(generic_def, type_arg_values, loc)
+    }
+
+    fn get_struct_instance(
+        &self,
+        generic_def: &StructDefExpr,
+        type_args: Vec<LoType>,
+        loc: &LoLocation,
+    ) -> Result<Rc<LoStructDef>, LoError> {
+        if type_args.len() != generic_def.type_params.len() {
+            return Err(LoError {
+                message: format!(
+                    "Invalid number of type params, expected {}, got {}",
+                    generic_def.type_params.len(),
+                    type_args.len()
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        let struct_name = format!(
+            "{}<{}>",
+            generic_def.struct_name.repr,
+            ListDisplay(&type_args)
+        );
+        if let Some(struct_def) = self.get_struct_def(&struct_name) {
+            return Ok(struct_def);
+        }
+
+        let mut type_scope = Vec::new();
+        for (type_param, type_arg) in generic_def.type_params.iter().zip(&type_args) {
+            self.check_trait_bounds(type_param, type_arg, loc)?;
+
+            type_scope.push(LoTypeDef {
+                name: type_param.name.clone(),
+                value: type_arg.clone(),
+                loc: generic_def.loc.clone(),
+            });
+        }
+
+        // pushed before the fields are built so self-references resolve to this instance
+        self.struct_defs.borrow_mut().push(Rc::new(LoStructDef {
+            struct_name: struct_name.clone(),
+            generic_struct_name: Some(generic_def.struct_name.repr.clone()),
+            type_args,
+            fields: Vec::new(),
+            loc: generic_def.loc.clone(),
+        }));
+
+        let struct_fields =
+            match self.build_struct_fields(&struct_name, &type_scope, &generic_def.fields) {
+                Ok(struct_fields) => struct_fields,
+                Err(err) => {
+                    self.struct_defs
+                        .borrow_mut()
+                        .retain(|struct_def| struct_def.struct_name != struct_name);
+
+                    return Err(LoError {
+                        message: format!("{}, in {struct_name} instantiated at {loc}", err.message),
+                        loc: err.loc,
+                    });
+                }
+            };
+
+        Ok(self.complete_struct_def(&struct_name, struct_fields))
+    }
+
+    fn build_struct_fields(
+        &self,
+        struct_name: &str,
+        type_scope: &Vec<LoTypeDef>,
+        fields: &Vec<StructDefField>,
+    ) -> Result<Vec<LoStructField>, LoError> {
+        let mut struct_layout = LoTypeLayout::default();
+        let mut struct_fields = Vec::<LoStructField>::new();
+
+        for field in fields {
+            for existing_field in &struct_fields {
+                if existing_field.field_name == field.field_name {
+                    return Err(LoError {
+                        message: format!(
+                            "Cannot define struct field with duplicate name: '{}' in struct {}",
+                            field.field_name, struct_name,
+                        ),
+                        loc: field.loc.clone(),
+                    });
+                }
+            }
+
+            let field_type = self.build_type_in_scope(type_scope, &field.field_type)?;
+
+            let mut cycle_path = vec![format!(
+                "{}.{} at {}",
+                struct_name, field.field_name, field.loc
+            )];
+            if self.find_struct_cycle(struct_name, &field_type, &mut cycle_path) {
+                return Err(LoError {
+                    message: format!(
+                        "Cannot define recursive struct {}: {} -> {}",
+                        struct_name,
+                        cycle_path.join(" -> "),
+                        struct_name
+                    ),
+                    loc: field.loc.clone(),
+                });
+            }
+
+            struct_fields.push(LoStructField {
+                field_name: field.field_name.clone(),
+                field_type: field_type.clone(),
+                field_index: struct_layout.primities_count,
+                byte_offset: struct_layout.byte_length,
+                loc: field.loc.clone(),
+            });
+
+            // append field's layout to total struct layout
+            self.get_type_layout(&field_type, &mut struct_layout);
+        }
+
+        Ok(struct_fields)
+    }
+
+    fn complete_struct_def(
+        &self,
+        struct_name: &str,
+        struct_fields: Vec<LoStructField>,
+    ) -> Rc<LoStructDef> {
+        let mut struct_defs = self.struct_defs.borrow_mut();
+        let struct_def = struct_defs
+            .iter_mut()
+            .find(|struct_def| struct_def.struct_name == struct_name)
+            .unwrap();
+
+        *struct_def = Rc::new(LoStructDef {
+            struct_name: struct_def.struct_name.clone(),
+            generic_struct_name: struct_def.generic_struct_name.clone(),
+            type_args: struct_def.type_args.clone(),
+            fields: struct_fields,
+            loc: struct_def.loc.clone(),
+        });
+
+        struct_def.clone()
+    }
+
+    fn codegen(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        expr: &CodeExpr,
+    ) -> Result<(), LoError> {
+        match expr {
+            CodeExpr::BoolLiteral(BoolLiteralExpr { value, loc: _ }) => {
+                if *value {
+                    instrs.push(WasmInstr::I32Const { value: 1 });
+                } else {
+                    instrs.push(WasmInstr::I32Const { value: 0 });
+                }
+            }
+            CodeExpr::CharLiteral(CharLiteralExpr {
+                repr: _,
+                value,
+                loc: _,
+            }) => {
+                instrs.push(WasmInstr::I32Const {
+                    value: *value as i32,
+                });
+            }
+            CodeExpr::IntLiteral(literal) => {
+                let literal_type = self.get_expr_type(ctx, expr)?;
+                self.codegen_int_literal(instrs, literal, false, &literal_type)?;
+            }
+            CodeExpr::FloatLiteral(literal) => {
+                let literal_type = self.get_expr_type(ctx, expr)?;
+                self.codegen_float_literal(instrs, literal, false, &literal_type)?;
+            }
+            CodeExpr::StringLiteral(StringLiteralExpr {
+                repr: _,
+                value,
+                zero_terminated,
+                loc,
+            }) => {
+                if self.memory.is_none() {
+                    return Err(LoError {
+                        message: format!("Cannot use strings with no memory defined"),
+                        loc: loc.clone(),
+                    });
+                }
+
+                let mut value = value.clone();
+                if *zero_terminated {
+                    value.push('\0');
+                }
+
+                let string_len = value.as_bytes().len() as u32;
+                let string_ptr = self.get_or_append_string(value);
+
+                instrs.push(WasmInstr::I32Const {
+                    value: string_ptr as i32,
+                });
+
+                if !*zero_terminated {
+                    instrs.push(WasmInstr::I32Const {
+                        value: string_len as i32,
+                    });
+                }
+            }
+            CodeExpr::StructLiteral(StructLiteralExpr {
+                struct_name,
+                type_args,
+                fields,
+                loc,
+            }) => {
+                let struct_def =
+                    self.get_struct_literal_def(&ctx.type_scope, struct_name, type_args, loc)?;
+
+                for field_index in 0..fields.len() {
+                    let field_literal = &fields[field_index];
+                    let Some(struct_field) = struct_def.fields.get(field_index) else {
+                        return Err(LoError {
+                            message: format!("Excess field values"),
+                            loc: field_literal.loc.clone(),
+                        });
+                    };
+
+                    if &field_literal.field_name != &struct_field.field_name {
+                        return Err(LoError {
                             message: format!(
                                 "Unexpected struct field name, expecting: `{}`",
                                 struct_field.field_name
@@ -940,12 +1975,16 @@ impl CodeGen {
                         });
                     }
 
-                    let field_value_type = self.get_expr_type(ctx, &field_literal.value)?;
-                    if field_value_type != struct_field.field_type {
+                    let field_value_type = self.get_expr_type_with_expected(
+                        ctx,
+                        &field_literal.value,
+                        Some(&struct_field.field_type),
+                    )?;
+                    if !self.is_type_compatible(&field_value_type, &struct_field.field_type) {
                         return Err(LoError {
                             message: format!(
                                 "Invalid type for struct field {}.{}, expected: {}, got: {}",
-                                struct_name.repr,
+                                struct_def.struct_name,
                                 struct_field.field_name,
                                 struct_field.field_type,
                                 field_value_type
@@ -954,7 +1993,12 @@ impl CodeGen {
                         });
                     }
 
-                    self.codegen(ctx, instrs, &field_literal.value)?;
+                    self.codegen_with_expected_type(
+                        ctx,
+                        instrs,
+                        &field_literal.value,
+                        Some(&struct_field.field_type),
+                    )?;
                 }
 
                 if fields.len() < struct_def.fields.len() {
@@ -969,21 +2013,111 @@ impl CodeGen {
                     });
                 }
             }
-            CodeExpr::ArrayLiteral(_) => todo!(),
+            CodeExpr::TupleLiteral(literal) => {
+                self.codegen_tuple_literal(ctx, instrs, literal, None)?;
+            }
+            CodeExpr::ArrayLiteral(ArrayLiteralExpr {
+                item_type,
+                items,
+                loc,
+            }) => {
+                if self.memory.is_none() {
+                    return Err(LoError {
+                        message: format!("Cannot use array literals with no memory defined"),
+                        loc: loc.clone(),
+                    });
+                }
+
+                let item_type = self.build_type_in_scope(&ctx.type_scope, item_type)?;
+
+                let mut bytes = Vec::new();
+                for item in items {
+                    let actual_item_type = match (item, &item_type) {
+                        // untagged int literals take the array's item type
+                        (
+                            CodeExpr::IntLiteral(IntLiteralExpr {
+                                repr: _,
+                                value: _,
+                                tag: None,
+                                loc: _,
+                            }),
+                            LoType::U8
+                            | LoType::I8
+                            | LoType::U16
+                            | LoType::I16
+                            | LoType::U32
+                            | LoType::I32
+                            | LoType::U64
+                            | LoType::I64,
+                        ) => item_type.clone(),
+                        (
+                            CodeExpr::FloatLiteral(FloatLiteralExpr {
+                                repr: _,
+                                value: _,
+                                tag: None,
+                                loc: _,
+                            }),
+                            LoType::F32 | LoType::F64,
+                        ) => item_type.clone(),
+                        _ => self.get_expr_type(ctx, item)?,
+                    };
+                    if !self.is_type_compatible(&actual_item_type, &item_type) {
+                        return Err(LoError {
+                            message: format!(
+                                "Invalid array item type: {}, expected: {}",
+                                actual_item_type, item_type
+                            ),
+                            loc: item.loc().clone(),
+                        });
+                    }
+
+                    self.append_const_bytes(&mut bytes, &item_type, item)?;
+                }
+
+                let array_ptr = self.append_data(bytes);
+
+                instrs.push(WasmInstr::I32Const {
+                    value: array_ptr as i32,
+                });
+            }
 
             CodeExpr::Ident(IdentExpr {
                 repr,
                 parts: _,
                 loc,
             }) => {
+                if let Some((enum_def, variant_index)) = self.get_enum_variant(repr) {
+                    return self.codegen_enum_value(
+                        ctx,
+                        instrs,
+                        enum_def,
+                        variant_index,
+                        None,
+                        loc,
+                    );
+                }
+
+                if let Some(const_def) = self.get_const_def(repr) {
+                    let const_ctx = &mut LoExprContext::default();
+                    return self.codegen(const_ctx, instrs, &const_def.const_value);
+                }
+
                 let var = self.var_from_ident(ctx, repr, loc)?;
                 self.codegen_var_get(instrs, &var);
             }
             CodeExpr::Let(LetExpr {
                 local_name,
+                local_type,
                 value,
                 loc,
             }) => {
+                let local_type = match local_type {
+                    Some(local_type) => {
+                        Some(self.build_type_in_scope(&ctx.type_scope, local_type)?)
+                    }
+                    None => None,
+                };
+
                 if local_name == "_" {
                     self.codegen(ctx, instrs, value)?;
 
@@ -994,1009 +2128,5452 @@ impl CodeGen {
                     return Ok(());
                 }
 
-                let local_type = self.get_expr_type(ctx, &value)?;
+                let value_type =
+                    self.get_expr_type_with_expected(ctx, value, local_type.as_ref())?;
+                if let Some(local_type) = &local_type {
+                    if !self.is_type_compatible(&value_type, local_type) {
+                        // the local still exists for the rest of the body to avoid follow-up errors
+                        self.define_local(ctx, loc.clone(), local_name.clone(), local_type, false)?;
+
+                        return Err(LoError {
+                            message: format!(
+                                "Invalid value for local {local_name}: {value_type}, expected {local_type}"
+                            ),
+                            loc: value.loc().clone(),
+                        });
+                    }
+                }
+
+                let local_type = local_type.unwrap_or(value_type);
                 self.define_local(ctx, loc.clone(), local_name.clone(), &local_type, false)?;
 
                 let var = self.var_from_ident(ctx, local_name, loc)?;
                 self.codegen_var_set(ctx, instrs, &var, value)?;
             }
-            CodeExpr::Cast(CastExpr {
-                expr,
-                casted_to,
+            CodeExpr::LetTuple(LetTupleExpr {
+                local_names,
+                local_type,
+                value,
                 loc,
             }) => {
-                let castee_type = self.get_expr_type(ctx, expr)?;
-                let casted_to = self.build_type(casted_to)?;
-
-                self.codegen(ctx, instrs, expr)?;
+                let local_type = match local_type {
+                    Some(local_type) => {
+                        Some(self.build_type_in_scope(&ctx.type_scope, local_type)?)
+                    }
+                    None => None,
+                };
 
-                match (&castee_type, &casted_to) {
-                    (LoType::U32, LoType::Pointer { .. }) => {}
-                    _ => {
+                let value_type =
+                    self.get_expr_type_with_expected(ctx, value, local_type.as_ref())?;
+                if let Some(local_type) = &local_type {
+                    if !self.is_type_compatible(&value_type, local_type) {
                         return Err(LoError {
-                            message: format!("Cannot cast from {castee_type} to {casted_to}"),
-                            loc: loc.clone(),
-                        })
+                            message: format!(
+                                "Invalid value for locals ({}): {value_type}, expected {local_type}",
+                                local_names.join(", ")
+                            ),
+                            loc: value.loc().clone(),
+                        });
                     }
-                };
-            }
-            CodeExpr::PrefixOp(_) => todo!(),
-            CodeExpr::InfixOp(InfixOpExpr {
-                op_tag,
-                op_loc,
-                lhs,
-                rhs,
-                loc: _,
-            }) => {
-                if let Some(base_op) = self.get_compound_assignment_base_op(op_tag) {
-                    return self.codegen_compound_assignment(
-                        ctx,
-                        instrs,
-                        Some(base_op),
-                        op_loc,
-                        lhs,
-                        rhs,
-                    );
                 }
 
-                let lhs_type = self.get_expr_type(ctx, lhs)?;
-                let rhs_type = self.get_expr_type(ctx, rhs)?;
-
-                if lhs_type != rhs_type {
+                let tuple_type = local_type.unwrap_or(value_type);
+                let LoType::Tuple { item_types } = &tuple_type else {
                     return Err(LoError {
                         message: format!(
-                            "Operands are not of the same type: lhs = {}, rhs = {}",
-                            lhs_type, rhs_type
+                            "Cannot destructure value of type {tuple_type}, expected a tuple"
                         ),
-                        loc: op_loc.clone(),
+                        loc: value.loc().clone(),
+                    });
+                };
+                if item_types.len() != local_names.len() {
+                    return Err(LoError {
+                        message: format!(
+                            "Cannot destructure {tuple_type} into {} locals",
+                            local_names.len()
+                        ),
+                        loc: loc.clone(),
                     });
                 }
 
-                self.codegen(ctx, instrs, lhs)?;
-                self.codegen(ctx, instrs, rhs)?;
+                self.codegen_with_expected_type(ctx, instrs, value, Some(&tuple_type))?;
 
-                let kind = self.get_binary_op_kind(op_tag, &lhs_type, op_loc)?;
-                instrs.push(WasmInstr::BinaryOp { kind });
-            }
+                let mut local_indices = Vec::new();
+                for (local_name, item_type) in local_names.iter().zip(item_types) {
+                    if local_name == "_" {
+                        local_indices.push(None);
+                        continue;
+                    }
 
-            CodeExpr::Assign(AssignExpr {
-                op_loc,
-                lhs,
-                rhs,
-                loc: _,
-            }) => {
-                return self.codegen_compound_assignment(ctx, instrs, None, op_loc, lhs, rhs);
-            }
-            CodeExpr::FieldAccess(FieldAccessExpr {
-                lhs,
-                field_name,
-                loc: _,
-            }) => {
-                let var = self.var_from_field_access(ctx, lhs, &field_name)?;
-                self.codegen_var_get(instrs, &var);
-            }
-            CodeExpr::PropagateError(_) => todo!(),
+                    let local_index =
+                        self.define_local(ctx, loc.clone(), local_name.clone(), item_type, false)?;
+                    local_indices.push(Some(local_index));
+                }
 
-            CodeExpr::FnCall(FnCallExpr { fn_name, args, loc }) => {
-                self.codegen_fn_call(ctx, instrs, &fn_name.repr, None, args, loc)?;
+                // items are on the stack in order so the last one is set first
+                for (local_index, item_type) in local_indices.iter().zip(item_types).rev() {
+                    let Some(local_index) = local_index else {
+                        for _ in 0..self.count_wasm_type_components(item_type) {
+                            instrs.push(WasmInstr::Drop);
+                        }
+                        continue;
+                    };
+
+                    self.codegen_local_set(instrs, item_type, *local_index);
+                }
             }
-            CodeExpr::MethodCall(MethodCallExpr {
-                lhs,
-                field_name,
-                args,
+            CodeExpr::Cast(CastExpr {
+                expr,
+                casted_to,
                 loc,
             }) => {
-                let lhs_type = self.get_expr_type(ctx, lhs)?;
-                let fn_name = get_fn_name_from_method(&lhs_type, &field_name.repr);
-                self.codegen_fn_call(ctx, instrs, &fn_name, Some(lhs), args, loc)?;
-            }
-            CodeExpr::MacroFnCall(_) => todo!(),
-            CodeExpr::MacroMethodCall(_) => todo!(),
+                let castee_type = self.get_expr_type(ctx, expr)?;
+                let casted_to = self.build_type_in_scope(&ctx.type_scope, casted_to)?;
 
-            CodeExpr::Dbg(_) => todo!(),
-            CodeExpr::Sizeof(_) => todo!(),
-            CodeExpr::GetDataSize(_) => todo!(),
+                self.codegen(ctx, instrs, expr)?;
 
-            CodeExpr::Return(ReturnExpr { expr, loc: _ }) => {
-                if let Some(return_expr) = expr {
-                    self.codegen(ctx, instrs, return_expr)?;
+                if let Some(conversion) = self.get_float_conversion_instr(&castee_type, &casted_to)
+                {
+                    instrs.push(conversion);
+                    return Ok(());
                 }
 
-                instrs.push(WasmInstr::Return);
-            }
-            CodeExpr::If(IfExpr {
-                cond,
-                then_block,
-                else_block,
-                loc: _,
-            }) => {
-                self.codegen(ctx, instrs, cond)?;
-
-                instrs.push(WasmInstr::BlockStart {
-                    block_kind: WasmBlockKind::If,
-                    block_type: WasmBlockType::NoOut,
-                });
-
-                ctx.enter_scope(LoScopeType::Block);
-                for expr in &then_block.exprs {
-                    self.codegen(ctx, instrs, &expr)?;
-                }
-                ctx.exit_scope();
-
-                match else_block {
-                    ElseBlock::None => {}
-                    ElseBlock::Else(code_block_expr) => {
-                        instrs.push(WasmInstr::Else);
-                        ctx.enter_scope(LoScopeType::Block);
-                        for expr in &code_block_expr.exprs {
-                            self.codegen(ctx, instrs, &expr)?;
-                        }
-                        ctx.exit_scope();
+                match (&castee_type, &casted_to) {
+                    (LoType::I8 | LoType::I16 | LoType::I32, LoType::I64 | LoType::U64) => {
+                        instrs.push(WasmInstr::I64ExtendI32s);
+                        return Ok(());
                     }
-                    ElseBlock::ElseIf(code_expr) => {
-                        instrs.push(WasmInstr::Else);
-                        self.codegen(ctx, instrs, &code_expr)?;
+                    (
+                        LoType::Bool | LoType::U8 | LoType::U16 | LoType::U32,
+                        LoType::I64 | LoType::U64,
+                    ) => {
+                        instrs.push(WasmInstr::I64ExtendI32u);
+                        return Ok(());
                     }
+                    (
+                        LoType::I64 | LoType::U64,
+                        LoType::U8
+                        | LoType::I8
+                        | LoType::U16
+                        | LoType::I16
+                        | LoType::U32
+                        | LoType::I32,
+                    ) => {
+                        instrs.push(WasmInstr::I32WrapI64);
+                        return Ok(());
+                    }
+                    _ => {}
                 }
 
-                instrs.push(WasmInstr::BlockEnd);
-            }
-            CodeExpr::Loop(LoopExpr { body, loc: _ }) => {
-                instrs.push(WasmInstr::BlockStart {
-                    block_kind: WasmBlockKind::Block,
-                    block_type: WasmBlockType::NoOut,
-                });
-                instrs.push(WasmInstr::BlockStart {
-                    block_kind: WasmBlockKind::Loop,
-                    block_type: WasmBlockType::NoOut,
-                });
-
-                ctx.enter_scope(LoScopeType::Loop);
-                for expr in &body.exprs {
-                    self.codegen(ctx, instrs, expr)?;
-                }
-                ctx.exit_scope();
-
-                // implicit continue
-                instrs.push(WasmInstr::Branch { label_index: 0 });
+                // everything else is reinterpreted as is, e.g. pointers, ints of the same width,
+                // c-style enums, function pointers (table indicies) and layout-compatible structs
+                let mut castee_wasm_types = Vec::new();
+                self.lower_type(&castee_type, &mut castee_wasm_types);
+                let mut casted_to_wasm_types = Vec::new();
+                self.lower_type(&casted_to, &mut casted_to_wasm_types);
 
-                instrs.push(WasmInstr::BlockEnd);
-                instrs.push(WasmInstr::BlockEnd);
-            }
-            CodeExpr::ForLoop(ForLoopExpr {
-                counter,
-                start,
-                end,
-                body,
-                loc,
-            }) => {
-                let counter_type = self.get_expr_type(ctx, start)?;
-                if self.get_expr_type(ctx, end)? != counter_type {
+                if castee_wasm_types != casted_to_wasm_types {
                     return Err(LoError {
-                        message: format!(
-                            "Invalid range end type: {}, expected: {counter_type}",
-                            self.get_expr_type(ctx, end)?
-                        ),
+                        message: format!("Cannot cast from {castee_type} to {casted_to}"),
                         loc: loc.clone(),
                     });
                 }
-
-                ctx.enter_scope(LoScopeType::ForLoop);
-
-                // define counter and set value to start
-                self.define_local(ctx, loc.clone(), counter.clone(), &counter_type, false)?;
-                let counter_var = self.var_from_ident(ctx, counter, loc)?;
-                self.codegen_var_set(ctx, instrs, &counter_var, start)?;
-
-                {
-                    instrs.push(WasmInstr::BlockStart {
-                        block_kind: WasmBlockKind::Block,
-                        block_type: WasmBlockType::NoOut,
-                    });
-
-                    {
-                        instrs.push(WasmInstr::BlockStart {
-                            block_kind: WasmBlockKind::Loop,
-                            block_type: WasmBlockType::NoOut,
+            }
+            CodeExpr::PrefixOp(PrefixOpExpr { op_tag, expr, loc }) => match op_tag {
+                PrefixOpTag::Not => {
+                    let expr_type = self.get_expr_type(ctx, expr)?;
+                    if expr_type != LoType::Bool {
+                        return Err(LoError {
+                            message: format!("Cannot apply `!` to value of type {}", expr_type),
+                            loc: loc.clone(),
                         });
+                    }
 
-                        // break if counter is equal to end
-                        self.codegen(ctx, instrs, end)?;
-                        self.codegen_var_get(instrs, &counter_var);
-                        let cmp_kind =
-                            self.get_binary_op_kind(&InfixOpTag::Equal, &counter_type, loc)?;
-                        instrs.push(WasmInstr::BinaryOp { kind: cmp_kind });
-                        instrs.push(WasmInstr::BranchIf { label_index: 1 });
+                    self.codegen(ctx, instrs, expr)?;
+                    instrs.push(WasmInstr::UnaryOp {
+                        kind: WasmUnaryOpKind::I32_EQZ,
+                    });
+                }
+                PrefixOpTag::BitNot => {
+                    let expr_type = self.get_expr_type(ctx, expr)?;
 
-                        {
-                            instrs.push(WasmInstr::BlockStart {
-                                block_kind: WasmBlockKind::Block,
-                                block_type: WasmBlockType::NoOut,
+                    // narrow unsigned values are kept zero-extended
+                    let (mask, kind) = match expr_type {
+                        LoType::U8 => (
+                            WasmInstr::I32Const { value: 0xFF },
+                            WasmBinaryOpKind::I32_XOR,
+                        ),
+                        LoType::U16 => (
+                            WasmInstr::I32Const { value: 0xFFFF },
+                            WasmBinaryOpKind::I32_XOR,
+                        ),
+                        LoType::I8 | LoType::I16 | LoType::I32 | LoType::U32 => {
+                            (WasmInstr::I32Const { value: -1 }, WasmBinaryOpKind::I32_XOR)
+                        }
+                        LoType::I64 | LoType::U64 => {
+                            (WasmInstr::I64Const { value: -1 }, WasmBinaryOpKind::I64_XOR)
+                        }
+                        _ => {
+                            return Err(LoError {
+                                message: format!("Cannot apply `~` to value of type {}", expr_type),
+                                loc: loc.clone(),
                             });
+                        }
+                    };
 
-                            for expr in &body.exprs {
-                                self.codegen(ctx, instrs, &expr)?;
-                            }
+                    self.codegen(ctx, instrs, expr)?;
+                    instrs.push(mask);
+                    instrs.push(WasmInstr::BinaryOp { kind });
+                }
+                PrefixOpTag::Dereference => {
+                    let expr_type = self.get_expr_type(ctx, expr)?;
+                    let (LoType::Pointer {
+                        pointee: pointee_type,
+                    }
+                    | LoType::SequencePointer {
+                        pointee: pointee_type,
+                    }) = expr_type
+                    else {
+                        return Err(LoError {
+                            message: format!("Cannot dereference expr of type {}", expr_type),
+                            loc: loc.clone(),
+                        });
+                    };
 
-                            instrs.push(WasmInstr::BlockEnd);
-                        }
+                    self.codegen(ctx, instrs, expr)?;
 
-                        // TODO: optimize
-                        // increment counter
-                        self.codegen_compound_assignment(
+                    if self.count_wasm_type_components(&pointee_type) == 1 {
+                        self.codegen_load_or_store(instrs, &pointee_type, 0, false);
+                    } else {
+                        let address_local_index = self.define_local(
                             ctx,
-                            instrs,
-                            Some(InfixOpTag::Add),
-                            loc,
-                            &CodeExpr::Ident(IdentExpr {
-                                repr: counter.clone(),
-                                parts: Vec::new(),
-                                loc: loc.clone(),
-                            }),
-                            &CodeExpr::IntLiteral(IntLiteralExpr {
-                                repr: String::from(""),
-                                value: 1,
-                                tag: Some(counter_type.to_string()),
-                                loc: loc.clone(),
-                            }),
+                            loc.clone(),
+                            format!("%{}", ctx.last_local_index),
+                            &LoType::U32,
+                            false,
                         )?;
+                        instrs.push(WasmInstr::LocalSet {
+                            local_index: address_local_index,
+                        });
 
-                        // implicit continue
-                        instrs.push(WasmInstr::Branch { label_index: 0 });
+                        self.codegen_load_from_local(instrs, &pointee_type, address_local_index, 0);
+                    }
+                }
+                PrefixOpTag::Positive | PrefixOpTag::Negative => {
+                    let is_negative = matches!(op_tag, PrefixOpTag::Negative);
 
-                        instrs.push(WasmInstr::BlockEnd);
+                    if let CodeExpr::FloatLiteral(literal) = expr.as_ref() {
+                        let literal_type = self.get_expr_type(ctx, expr)?;
+                        return self.codegen_float_literal(
+                            instrs,
+                            literal,
+                            is_negative,
+                            &literal_type,
+                        );
                     }
 
-                    instrs.push(WasmInstr::BlockEnd);
-                }
+                    if let Some(value) = self.get_const_int_literal_value(expr) {
+                        let value = if is_negative {
+                            -(value as i64)
+                        } else {
+                            value as i64
+                        };
 
-                ctx.exit_scope();
-            }
-            CodeExpr::Break(BreakExpr { loc }) => {
-                let mut label_index = 1; // 0 = loop, 1 = loop wrapper block
+                        match self.get_expr_type(ctx, expr)? {
+                            LoType::U64 | LoType::I64 => {
+                                instrs.push(WasmInstr::I64Const { value });
+                                return Ok(());
+                            }
+                            LoType::U8
+                            | LoType::I8
+                            | LoType::U16
+                            | LoType::I16
+                            | LoType::U32
+                            | LoType::I32 => {
+                                instrs.push(WasmInstr::I32Const {
+                                    value: value as i32,
+                                });
+                                return Ok(());
+                            }
+                            _ => {}
+                        }
+                    }
 
-                for scope in ctx.scopes.iter().rev() {
-                    match scope.scope_type {
-                        LoScopeType::Block => {
-                            label_index += 1;
+                    let expr_type = self.get_expr_type(ctx, expr)?;
+                    match expr_type {
+                        LoType::I8 | LoType::I16 | LoType::I32 => {
+                            if is_negative {
+                                instrs.push(WasmInstr::I32Const { value: 0 });
+                                self.codegen(ctx, instrs, expr)?;
+                                instrs.push(WasmInstr::BinaryOp {
+                                    kind: WasmBinaryOpKind::I32_SUB,
+                                });
+                            } else {
+                                self.codegen(ctx, instrs, expr)?;
+                            }
                         }
-                        LoScopeType::Function => {
-                            return Err(LoError {
-                                message: format!("Cannot break outside of a loop"),
-                                loc: loc.clone(),
-                            });
+                        LoType::I64 => {
+                            if is_negative {
+                                instrs.push(WasmInstr::I64Const { value: 0 });
+                                self.codegen(ctx, instrs, expr)?;
+                                instrs.push(WasmInstr::BinaryOp {
+                                    kind: WasmBinaryOpKind::I64_SUB,
+                                });
+                            } else {
+                                self.codegen(ctx, instrs, expr)?;
+                            }
                         }
-                        LoScopeType::Loop => break,
-                        LoScopeType::ForLoop => {
-                            label_index += 1;
-                            break;
+                        LoType::F32 => {
+                            self.codegen(ctx, instrs, expr)?;
+                            if is_negative {
+                                instrs.push(WasmInstr::UnaryOp {
+                                    kind: WasmUnaryOpKind::F32_NEG,
+                                });
+                            }
                         }
-                    }
-                }
-
-                instrs.push(WasmInstr::Branch { label_index });
-            }
-            CodeExpr::Continue(ContinueExpr { loc }) => {
-                let mut label_index = 0; // 0 = loop, 1 = loop wrapper block
-
-                for scope in ctx.scopes.iter().rev() {
-                    match scope.scope_type {
-                        LoScopeType::Block => {
-                            label_index += 1;
+                        LoType::F64 => {
+                            self.codegen(ctx, instrs, expr)?;
+                            if is_negative {
+                                instrs.push(WasmInstr::UnaryOp {
+                                    kind: WasmUnaryOpKind::F64_NEG,
+                                });
+                            }
                         }
-                        LoScopeType::Function => {
+                        _ => {
                             return Err(LoError {
-                                message: format!("Cannot continue outside of a loop"),
+                                message: format!(
+                                    "Cannot apply `{}` to value of type {}",
+                                    op_tag.to_str(),
+                                    expr_type
+                                ),
                                 loc: loc.clone(),
                             });
                         }
-                        LoScopeType::Loop => break,
-                        LoScopeType::ForLoop => break,
                     }
                 }
+            },
+            CodeExpr::InfixOp(InfixOpExpr {
+                op_tag,
+                op_loc,
+                lhs,
+                rhs,
+                loc: _,
+            }) => {
+                if let Some(base_op) = self.get_compound_assignment_base_op(op_tag) {
+                    return self.codegen_compound_assignment(
+                        ctx,
+                        instrs,
+                        Some(base_op),
+                        op_loc,
+                        lhs,
+                        rhs,
+                    );
+                }
 
-                instrs.push(WasmInstr::Branch { label_index });
+                let (lhs_type, rhs_type) = self.get_operand_types(ctx, lhs, rhs)?;
+
+                if lhs_type != rhs_type {
+                    return Err(LoError {
+                        message: format!(
+                            "Operands are not of the same type: lhs = {}, rhs = {}",
+                            lhs_type, rhs_type
+                        ),
+                        loc: op_loc.clone(),
+                    });
+                }
+
+                self.codegen_with_expected_type(ctx, instrs, lhs, Some(&lhs_type))?;
+                self.codegen_with_expected_type(ctx, instrs, rhs, Some(&rhs_type))?;
+
+                let kind = self.get_binary_op_kind(op_tag, &lhs_type, op_loc)?;
+                instrs.push(WasmInstr::BinaryOp { kind });
             }
-            CodeExpr::Defer(_) => todo!(),
-            CodeExpr::Catch(_) => todo!(),
-            CodeExpr::Paren(ParenExpr { expr, loc: _ }) => {
-                self.codegen(ctx, instrs, expr)?;
+
+            CodeExpr::Assign(AssignExpr {
+                op_loc,
+                lhs,
+                rhs,
+                loc: _,
+            }) => {
+                return self.codegen_compound_assignment(ctx, instrs, None, op_loc, lhs, rhs);
             }
-            CodeExpr::Unreachable(_) => {
-                instrs.push(WasmInstr::Unreachable);
+            CodeExpr::FieldAccess(FieldAccessExpr {
+                lhs,
+                field_name,
+                loc,
+            }) => {
+                let var = self.var_from_field_access_or_copy(ctx, instrs, lhs, field_name, loc)?;
+                self.codegen_var_get(instrs, &var);
             }
-        };
+            CodeExpr::Index(IndexExpr { lhs, index, loc }) => {
+                let var = self.var_from_index(ctx, instrs, lhs, index, loc)?;
+                self.codegen_var_get(instrs, &var);
+            }
+            CodeExpr::Slice(slice) => {
+                self.codegen_slice(ctx, instrs, slice)?;
+            }
+            CodeExpr::PropagateError(PropagateErrorExpr { expr, loc }) => {
+                let expr_type = self.get_expr_type(ctx, expr)?;
+                let LoType::Result { ok_type, err_type } = expr_type else {
+                    return Err(LoError {
+                        message: format!(
+                            "Trying to propagate an error from the expression of type: {expr_type}"
+                        ),
+                        loc: loc.clone(),
+                    });
+                };
 
-        Ok(())
-    }
+                self.assert_fn_can_throw(ctx, &err_type, loc)?;
+                let Some(LoType::Result {
+                    ok_type: fn_ok_type,
+                    err_type: _,
+                }) = ctx.fn_return_type.clone()
+                else {
+                    unreachable!()
+                };
 
-    fn codegen_fn_call(
-        &self,
-        ctx: &mut LoExprContext,
-        instrs: &mut Vec<WasmInstr>,
-        fn_name: &str,
-        receiver_arg: Option<&CodeExpr>,
-        args: &Vec<CodeExpr>,
-        loc: &LoLocation,
-    ) -> Result<(), LoError> {
-        let Some((lo_fn_info, wasm_fn_info)) = self.get_fn_info(fn_name) else {
-            return Err(LoError {
-                message: format!("Unknown function: {}", fn_name),
-                loc: loc.clone(),
-            });
-        };
+                self.codegen(ctx, instrs, expr)?;
 
-        let mut arg_types = Vec::new();
-        if let Some(receiver_arg) = receiver_arg {
-            arg_types.push(self.get_expr_type(ctx, receiver_arg)?);
-            self.codegen(ctx, instrs, receiver_arg)?;
-        }
-        for arg in args {
-            arg_types.push(self.get_expr_type(ctx, arg)?);
-            self.codegen(ctx, instrs, arg)?;
-        }
+                ctx.enter_scope(LoScopeType::Block);
 
-        if arg_types != lo_fn_info.fn_type.inputs {
-            return Err(LoError {
-                message: format!(
-                    "Invalid function arguments for function {}: [{}], expected [{}]",
-                    lo_fn_info.fn_name,
-                    ListDisplay(&arg_types),
-                    ListDisplay(&lo_fn_info.fn_type.inputs),
-                ),
-                loc: loc.clone(),
-            });
-        }
+                let (ok_var, err_var) =
+                    self.codegen_bind_result(ctx, instrs, None, &ok_type, &err_type, loc)?;
 
-        instrs.push(WasmInstr::Call {
-            fn_index: wasm_fn_info.wasm_fn_index,
-        });
+                // error value of zero means no error
+                self.codegen_var_get(instrs, &err_var);
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::If,
+                    block_type: self.get_block_type(&ok_type),
+                });
 
-        Ok(())
-    }
+                self.codegen_default_value(instrs, &fn_ok_type);
+                self.codegen_var_get(instrs, &err_var);
+                for scope in ctx.scopes.iter().rev() {
+                    self.codegen_deferred(instrs, scope);
+                }
+                instrs.push(WasmInstr::Return);
 
-    fn define_local(
-        &self,
-        ctx: &mut LoExprContext,
-        loc: LoLocation,
-        local_name: String,
-        local_type: &LoType,
-        is_fn_param: bool,
-    ) -> Result<u32, LoError> {
-        for local in ctx.current_scope().locals.iter() {
-            if local.local_name == local_name && local.defined_in_this_scope {
-                let LoLocal { definition_loc, .. } = &ctx.locals[local.lo_local_index];
+                instrs.push(WasmInstr::Else);
+                self.codegen_var_get(instrs, &ok_var);
+                instrs.push(WasmInstr::BlockEnd);
 
-                return Err(LoError {
-                    message: format!(
-                        "Cannot redefine local {}, previously defined at {}",
-                        local_name, definition_loc
-                    ),
-                    loc,
-                });
+                ctx.exit_scope();
             }
-        }
 
-        let local_index = ctx.last_local_index;
-        ctx.locals.push(LoLocal {
-            local_index,
-            local_type: local_type.clone(),
-            definition_loc: loc,
-            is_fn_param,
-        });
-        let lo_local_index = ctx.locals.len() - 1;
-        ctx.current_scope_mut().locals.push(LoScopedLocal {
-            local_name,
-            lo_local_index,
-            defined_in_this_scope: true,
-        });
-        ctx.last_local_index += self.count_wasm_type_components(local_type);
-
-        Ok(local_index)
-    }
+            CodeExpr::FnCall(FnCallExpr { fn_name, args, loc }) => {
+                if fn_name.repr == "Ok" || fn_name.repr == "Err" {
+                    return self.codegen_result_constructor(ctx, instrs, &fn_name.repr, args, loc);
+                }
 
-    fn codegen_compound_assignment(
-        &self,
-        ctx: &mut LoExprContext,
-        instrs: &mut Vec<WasmInstr>,
-        base_op: Option<InfixOpTag>,
-        op_loc: &LoLocation,
-        lhs: &CodeExpr,
-        rhs: &CodeExpr,
-    ) -> Result<(), LoError> {
-        let lhs_type = self.get_expr_type(ctx, lhs)?;
-        let rhs_type = self.get_expr_type(ctx, rhs)?;
+                if let Some((enum_def, variant_index)) = self.get_enum_variant(&fn_name.repr) {
+                    if args.len() > 1 {
+                        return Err(LoError {
+                            message: format!("Too many payload values for {}", fn_name.repr),
+                            loc: loc.clone(),
+                        });
+                    }
 
-        if lhs_type != rhs_type {
-            return Err(LoError {
-                message: format!(
-                    "Unexpected value for assignment: {}, expected {}",
-                    rhs_type, lhs_type
-                ),
-                loc: op_loc.clone(),
-            });
-        }
+                    return self.codegen_enum_value(
+                        ctx,
+                        instrs,
+                        enum_def,
+                        variant_index,
+                        args.first(),
+                        loc,
+                    );
+                }
 
-        if let CodeExpr::PrefixOp(PrefixOpExpr {
-            op_tag: PrefixOpTag::Dereference,
-            expr: addr_expr,
-            loc: _,
-        }) = lhs
-        {
-            let pointer_type = self.get_expr_type(ctx, addr_expr)?;
-            let LoType::Pointer {
-                pointee: pointee_type,
-            } = pointer_type
-            else {
-                return Err(LoError {
-                    message: format!("Cannot use {pointer_type} as an address, pointer expected"),
-                    loc: addr_expr.loc().clone(),
-                });
-            };
+                if let Some(enum_def) = self.get_enum_of_name_fn(&fn_name.repr) {
+                    return self.codegen_enum_name(ctx, instrs, enum_def, args, loc);
+                }
 
-            let component_count = self.count_wasm_type_components(&pointee_type);
-            if component_count == 1 {
-                self.codegen(ctx, instrs, addr_expr)?;
-            }
+                if let Some(callee) = self.var_from_callable_ident(ctx, fn_name) {
+                    return self.codegen_indirect_call(ctx, instrs, &callee, args, loc);
+                }
 
-            if let Some(base_op) = base_op {
-                self.codegen(ctx, instrs, lhs)?;
-                self.codegen(ctx, instrs, rhs)?;
+                if let Some((instr, input_types, _)) = self.get_memory_intrinsic(&fn_name.repr) {
+                    let call = LoCallSite {
+                        name: &fn_name.repr,
+                        receiver: None,
+                        args,
+                        loc,
+                    };
+                    return self.codegen_memory_intrinsic(ctx, instrs, &call, instr, &input_types);
+                }
 
-                let kind = self.get_binary_op_kind(&base_op, &lhs_type, op_loc)?;
-                instrs.push(WasmInstr::BinaryOp { kind });
-            } else {
-                self.codegen(ctx, instrs, rhs)?;
+                self.codegen_fn_call(ctx, instrs, &fn_name.repr, None, args, loc)?;
             }
+            CodeExpr::MethodCall(MethodCallExpr {
+                lhs,
+                field_name,
+                args,
+                loc,
+            }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let fn_name = self.get_fn_name_from_method(&lhs_type, &field_name.repr);
 
-            if component_count != 1 {
-                let tmp_local_index = self.define_local(
-                    ctx,
-                    rhs.loc().clone(),
-                    format!("%{}", ctx.last_local_index),
-                    &rhs_type,
-                    false,
-                )?;
-                self.codegen_local_set(instrs, &rhs_type, tmp_local_index);
+                if let None = self.get_fn_info(&fn_name) {
+                    if let Some(callee) = self.var_from_callable_field(ctx, lhs, field_name) {
+                        return self.codegen_indirect_call(ctx, instrs, &callee, args, loc);
+                    }
 
-                for i in 0..component_count {
-                    self.codegen(ctx, instrs, addr_expr)?;
-                    instrs.push(WasmInstr::LocalGet {
-                        local_index: tmp_local_index + i,
-                    });
+                    if let Some((instr, arg_count)) =
+                        self.get_bit_intrinsic(&lhs_type, &field_name.repr)
+                    {
+                        let call = LoCallSite {
+                            name: &fn_name,
+                            receiver: Some(lhs),
+                            args,
+                            loc,
+                        };
+                        return self.codegen_bit_intrinsic(
+                            ctx, instrs, &call, &lhs_type, instr, arg_count,
+                        );
+                    }
                 }
+
+                self.codegen_fn_call(ctx, instrs, &fn_name, Some(lhs), args, loc)?;
             }
+            CodeExpr::Closure(closure) => {
+                self.codegen_closure(ctx, instrs, closure, None)?;
+            }
+            CodeExpr::FnRef(FnRefExpr { fn_name, loc }) => {
+                let Some((_, wasm_fn_info)) = self.get_fn_info(&fn_name.repr) else {
+                    return Err(LoError {
+                        message: format!("Unknown function: {}", fn_name.repr),
+                        loc: loc.clone(),
+                    });
+                };
 
-            self.codegen_load_or_store(instrs, &pointee_type, 0, true);
+                let table_index = self.get_fn_table_index(wasm_fn_info.wasm_fn_index);
+                instrs.push(WasmInstr::I32Const {
+                    value: table_index as i32,
+                });
+            }
+            CodeExpr::MacroFnCall(MacroFnCallExpr {
+                fn_name,
+                type_args,
+                args,
+                loc,
+            }) => {
+                let call = LoCallSite {
+                    name: &fn_name.repr,
+                    receiver: None,
+                    args,
+                    loc,
+                };
+                self.codegen_macro_call(ctx, instrs, &call, type_args)?;
+            }
+            CodeExpr::MacroMethodCall(MacroMethodCallExpr {
+                lhs,
+                field_name,
+                type_args,
+                args,
+                loc,
+            }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let macro_name = self.get_fn_name_from_method(&lhs_type, &field_name.repr);
+                let call = LoCallSite {
+                    name: &macro_name,
+                    receiver: Some(lhs),
+                    args,
+                    loc,
+                };
+                self.codegen_macro_call(ctx, instrs, &call, type_args)?;
+            }
 
-            return Ok(());
-        }
+            CodeExpr::Dbg(dbg) => {
+                self.codegen(ctx, instrs, &self.get_dbg_string_literal(dbg))?;
+            }
+            CodeExpr::Sizeof(SizeofExpr { type_expr, loc: _ }) => {
+                let lo_type = self.build_type_in_scope(&ctx.type_scope, type_expr)?;
+                let layout = &mut LoTypeLayout::default();
+                self.get_type_layout(&lo_type, layout);
 
-        if let CodeExpr::Ident(IdentExpr {
-            repr,
-            parts: _,
-            loc,
-        }) = lhs
-        {
-            if let Some(local_index) = ctx.get_local(&repr).map(|l| l.local_index) {
-                if let Some(base_op) = base_op {
-                    self.codegen(ctx, instrs, lhs)?;
-                    self.codegen(ctx, instrs, rhs)?;
+                instrs.push(WasmInstr::I32Const {
+                    value: layout.byte_length as i32,
+                });
+            }
+            CodeExpr::GetDataSize(GetDataSizeExpr { loc }) => {
+                return Err(LoError {
+                    message: format!("@data_size can only be used as a global value"),
+                    loc: loc.clone(),
+                });
+            }
 
-                    let kind = self.get_binary_op_kind(&base_op, &lhs_type, op_loc)?;
-                    instrs.push(WasmInstr::BinaryOp { kind });
-                } else {
-                    self.codegen(ctx, instrs, rhs)?;
+            CodeExpr::Return(ReturnExpr { expr, loc }) => {
+                let fn_return_type = ctx.fn_return_type.clone().unwrap_or(LoType::Void);
+                match expr {
+                    Some(return_expr) => {
+                        let value_type = self.codegen_with_expected_type(
+                            ctx,
+                            instrs,
+                            return_expr,
+                            Some(&fn_return_type),
+                        )?;
+                        if !self.is_type_compatible(&value_type, &fn_return_type) {
+                            return Err(LoError {
+                                message: format!(
+                                    "Invalid return value type: {value_type}, expected: {fn_return_type}"
+                                ),
+                                loc: return_expr.loc().clone(),
+                            });
+                        }
+                    }
+                    None if fn_return_type != LoType::Void => {
+                        return Err(LoError {
+                            message: format!("Missing return value, expected: {fn_return_type}"),
+                            loc: loc.clone(),
+                        });
+                    }
+                    None => {}
                 }
 
-                for i in (0..self.count_wasm_type_components(&rhs_type)).rev() {
-                    instrs.push(WasmInstr::LocalSet {
-                        local_index: local_index + i,
-                    });
+                for scope in ctx.scopes.iter().rev() {
+                    self.codegen_deferred(instrs, scope);
                 }
 
-                return Ok(());
+                instrs.push(WasmInstr::Return);
             }
+            CodeExpr::If(IfExpr {
+                cond,
+                then_block,
+                else_block,
+                loc: _,
+            }) => {
+                self.codegen(ctx, instrs, cond)?;
 
-            if let Some(global) = self.get_global(&repr) {
-                if let Some(base_op) = base_op {
-                    self.codegen(ctx, instrs, lhs)?;
-                    self.codegen(ctx, instrs, rhs)?;
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::If,
+                    block_type: WasmBlockType::NoOut,
+                });
 
-                    let kind = self.get_binary_op_kind(&base_op, &lhs_type, op_loc)?;
-                    instrs.push(WasmInstr::BinaryOp { kind });
-                } else {
-                    self.codegen(ctx, instrs, rhs)?;
-                }
+                ctx.enter_scope(LoScopeType::Block);
+                self.codegen_code_block(ctx, instrs, &then_block.exprs);
+                self.codegen_deferred(instrs, ctx.current_scope());
+                ctx.exit_scope();
 
-                for i in 0..self.count_wasm_type_components(&rhs_type) {
-                    instrs.push(WasmInstr::GlobalSet {
-                        global_index: global.global_index + i,
-                    });
+                match else_block {
+                    ElseBlock::None => {}
+                    ElseBlock::Else(code_block_expr) => {
+                        instrs.push(WasmInstr::Else);
+                        ctx.enter_scope(LoScopeType::Block);
+                        self.codegen_code_block(ctx, instrs, &code_block_expr.exprs);
+                        self.codegen_deferred(instrs, ctx.current_scope());
+                        ctx.exit_scope();
+                    }
+                    ElseBlock::ElseIf(code_expr) => {
+                        instrs.push(WasmInstr::Else);
+                        self.codegen(ctx, instrs, &code_expr)?;
+                    }
                 }
 
-                return Ok(());
+                instrs.push(WasmInstr::BlockEnd);
             }
+            CodeExpr::Match(MatchExpr { expr, arms, loc }) => {
+                let expr_type = self.get_expr_type(ctx, expr)?;
+                let LoType::EnumInstance { enum_name } = &expr_type else {
+                    return Err(LoError {
+                        message: format!("Cannot match on value of type {expr_type}"),
+                        loc: expr.loc().clone(),
+                    });
+                };
+                let enum_def = self.get_enum_def(enum_name).unwrap();
+
+                let mut arm_variants = Vec::new();
+                let mut arm_indicies_by_variant = vec![None; enum_def.variants.len()];
+                let mut wildcard_arm_index = None;
+                for (arm, arm_index) in arms.iter().zip(0..) {
+                    if arm.variant_name.repr == "_" {
+                        if let Some(payload_bind) = &arm.payload_bind {
+                            return Err(LoError {
+                                message: format!("Cannot bind payload in wildcard match arm"),
+                                loc: payload_bind.loc.clone(),
+                            });
+                        }
 
-            return Err(LoError {
-                message: format!("Unknown variable: {repr}"),
-                loc: loc.clone(),
-            });
-        }
+                        if let Some(_) = wildcard_arm_index {
+                            return Err(LoError {
+                                message: format!("Duplicate wildcard match arm"),
+                                loc: arm.loc.clone(),
+                            });
+                        }
 
-        if let CodeExpr::FieldAccess(FieldAccessExpr {
-            lhs,
-            field_name,
-            loc: _,
-        }) = lhs
-        {
-            let var = self.var_from_field_access(ctx, lhs, field_name)?;
-            self.codegen_var_set(ctx, instrs, &var, rhs)?;
+                        wildcard_arm_index = Some(arm_index);
+                        arm_variants.push(None);
+                        continue;
+                    }
 
-            return Ok(());
-        }
+                    let Some((_, variant_index)) = self
+                        .get_enum_variant(&arm.variant_name.repr)
+                        .filter(|(arm_enum_def, _)| arm_enum_def.enum_name == *enum_name)
+                    else {
+                        return Err(LoError {
+                            message: format!(
+                                "Unknown variant {} of enum {enum_name}",
+                                arm.variant_name.repr
+                            ),
+                            loc: arm.variant_name.loc.clone(),
+                        });
+                    };
 
-        todo!();
-    }
+                    if let Some(_) = arm_indicies_by_variant[variant_index] {
+                        return Err(LoError {
+                            message: format!("Duplicate match arm for {}", arm.variant_name.repr),
+                            loc: arm.loc.clone(),
+                        });
+                    }
 
-    fn codegen_load_or_store(
-        &self,
-        instrs: &mut Vec<WasmInstr>,
-        pointee_type: &LoType,
-        offset: u32,
-        is_store: bool,
-    ) {
-        match pointee_type {
-            LoType::Never | LoType::Void => unreachable!(),
-            LoType::Bool | LoType::U8 => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::I32_8,
-                        align: 0,
-                        offset,
-                    })
-                } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::I32U8,
-                        align: 0,
-                        offset,
-                    })
-                }
-            }
-            LoType::I8 => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::I32_8,
-                        align: 0,
-                        offset,
-                    })
-                } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::I32I8,
-                        align: 0,
-                        offset,
-                    })
+                    let variant = &enum_def.variants[variant_index];
+                    if let Some(payload_bind) = &arm.payload_bind {
+                        if variant.payload_type == LoType::Void {
+                            return Err(LoError {
+                                message: format!(
+                                    "Enum variant {} has no payload to bind",
+                                    arm.variant_name.repr
+                                ),
+                                loc: payload_bind.loc.clone(),
+                            });
+                        }
+                    }
+
+                    arm_indicies_by_variant[variant_index] = Some(arm_index);
+                    arm_variants.push(Some(variant));
                 }
-            }
-            LoType::U16 => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::I32_16,
-                        align: 0,
-                        offset,
-                    })
-                } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::I32U16,
-                        align: 0,
-                        offset,
-                    })
+
+                let mut missing_variants = Vec::new();
+                for (variant, arm_index) in enum_def.variants.iter().zip(&arm_indicies_by_variant) {
+                    if let None = arm_index {
+                        missing_variants.push(format!("{enum_name}::{}", variant.variant_name));
+                    }
                 }
-            }
-            LoType::I16 => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::I32_16,
-                        align: 0,
-                        offset,
-                    })
-                } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::I32I16,
-                        align: 0,
-                        offset,
-                    })
+                if missing_variants.len() != 0 && wildcard_arm_index.is_none() {
+                    return Err(LoError {
+                        message: format!(
+                            "Non-exhaustive match, missing variants: {}",
+                            ListDisplay(&missing_variants)
+                        ),
+                        loc: loc.clone(),
+                    });
                 }
-            }
-            LoType::U32
-            | LoType::I32
-            | LoType::Pointer { pointee: _ }
-            | LoType::SequencePointer { pointee: _ } => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::I32,
-                        align: 0,
-                        offset,
-                    })
-                } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::I32,
-                        align: 0,
-                        offset,
-                    })
+
+                self.codegen(ctx, instrs, expr)?;
+                let value_local_index = self.define_local(
+                    ctx,
+                    loc.clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &expr_type,
+                    false,
+                )?;
+                self.codegen_local_set(instrs, &expr_type, value_local_index);
+
+                // outer block to exit the match + one block per arm, arm bodies follow block ends
+                let arms_count = arms.len() as u32;
+                for _ in 0..arms_count + 1 {
+                    instrs.push(WasmInstr::BlockStart {
+                        block_kind: WasmBlockKind::Block,
+                        block_type: WasmBlockType::NoOut,
+                    });
                 }
-            }
-            LoType::U64 | LoType::I64 => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::I64,
-                        align: 0,
-                        offset,
-                    })
+
+                if let Some(wildcard_arm_index) = wildcard_arm_index {
+                    self.codegen_match_dispatch(
+                        instrs,
+                        enum_def,
+                        &arm_indicies_by_variant,
+                        value_local_index,
+                        wildcard_arm_index,
+                    );
                 } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::I64,
-                        align: 0,
-                        offset,
-                    })
+                    // without a wildcard arm only invalid tags (e.g. from casts) reach the default,
+                    // it traps in an extra innermost block which shifts the arm labels by one
+                    instrs.push(WasmInstr::BlockStart {
+                        block_kind: WasmBlockKind::Block,
+                        block_type: WasmBlockType::NoOut,
+                    });
+
+                    let arm_labels_by_variant = arm_indicies_by_variant
+                        .iter()
+                        .map(|arm_index| arm_index.map(|arm_index| arm_index + 1))
+                        .collect();
+                    self.codegen_match_dispatch(
+                        instrs,
+                        enum_def,
+                        &arm_labels_by_variant,
+                        value_local_index,
+                        0,
+                    );
+
+                    instrs.push(WasmInstr::BlockEnd);
+                    instrs.push(WasmInstr::Unreachable);
                 }
-            }
-            LoType::F32 => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::F32,
-                        align: 0,
-                        offset,
-                    })
-                } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::F32,
-                        align: 0,
-                        offset,
-                    })
+
+                for ((arm, variant), arm_index) in arms.iter().zip(arm_variants).zip(0..) {
+                    instrs.push(WasmInstr::BlockEnd);
+
+                    let block_depth = arms_count - arm_index;
+                    ctx.enter_scope(LoScopeType::MatchArm { block_depth });
+
+                    if let (Some(payload_bind), Some(variant)) = (&arm.payload_bind, variant) {
+                        let payload_local_index = self.define_local(
+                            ctx,
+                            payload_bind.loc.clone(),
+                            payload_bind.repr.clone(),
+                            &variant.payload_type,
+                            false,
+                        )?;
+
+                        let payload_comp_count =
+                            self.count_wasm_type_components(&variant.payload_type);
+                        for i in 0..payload_comp_count {
+                            instrs.push(WasmInstr::LocalGet {
+                                local_index: value_local_index + variant.field_index + i,
+                            });
+                        }
+                        self.codegen_local_set(instrs, &variant.payload_type, payload_local_index);
+                    }
+
+                    self.codegen_code_block(ctx, instrs, &arm.body.exprs);
+                    self.codegen_deferred(instrs, ctx.current_scope());
+                    ctx.exit_scope();
+
+                    instrs.push(WasmInstr::Branch {
+                        label_index: block_depth - 1,
+                    });
                 }
+
+                instrs.push(WasmInstr::BlockEnd);
             }
-            LoType::F64 => {
-                if is_store {
-                    instrs.push(WasmInstr::Store {
-                        kind: WasmStoreKind::F64,
-                        align: 0,
-                        offset,
-                    })
-                } else {
-                    instrs.push(WasmInstr::Load {
-                        kind: WasmLoadKind::F64,
-                        align: 0,
-                        offset,
-                    })
+            CodeExpr::Loop(LoopExpr {
+                label,
+                body,
+                loc: _,
+            }) => {
+                let value_type = self.get_loop_value_type(ctx, label, body)?;
+
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::Block,
+                    block_type: self.get_block_type(&value_type),
+                });
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::Loop,
+                    block_type: WasmBlockType::NoOut,
+                });
+
+                ctx.enter_scope(LoScopeType::Loop {
+                    label: label.clone(),
+                    value_type: value_type.clone(),
+                });
+                self.codegen_code_block(ctx, instrs, &body.exprs);
+                self.codegen_deferred(instrs, ctx.current_scope());
+                ctx.exit_scope();
+
+                // implicit continue
+                instrs.push(WasmInstr::Branch { label_index: 0 });
+
+                instrs.push(WasmInstr::BlockEnd);
+
+                // the value is only produced by `break`, the loop never falls through
+                if value_type != LoType::Void {
+                    instrs.push(WasmInstr::Unreachable);
                 }
+
+                instrs.push(WasmInstr::BlockEnd);
             }
-            LoType::StructInstance { struct_name } => {
-                let struct_def = self.get_struct_def(struct_name).unwrap();
+            CodeExpr::ForLoop(ForLoopExpr {
+                label,
+                item_name,
+                iterable,
+                body,
+                loc,
+            }) => {
+                ctx.enter_scope(LoScopeType::ForLoop {
+                    label: label.clone(),
+                });
 
-                for struct_field in struct_def.fields.iter().rev() {
-                    self.codegen_load_or_store(
-                        instrs,
-                        &struct_field.field_type,
-                        offset + struct_field.byte_offset,
-                        is_store,
-                    );
+                match iterable {
+                    ForLoopIterable::Range(range) => {
+                        self.codegen_range_loop(ctx, instrs, item_name, range, body, loc)?
+                    }
+                    ForLoopIterable::Items(items) => {
+                        self.codegen_slice_loop(ctx, instrs, item_name, items, body, loc)?
+                    }
                 }
-            }
-            LoType::Result {
-                ok_type: _,
-                err_type: _,
-            } => todo!(),
-        }
-    }
 
-    fn get_expr_type(&self, ctx: &LoExprContext, expr: &CodeExpr) -> Result<LoType, LoError> {
-        match expr {
-            CodeExpr::BoolLiteral(_) => Ok(LoType::Bool),
-            CodeExpr::CharLiteral(_) => Ok(LoType::U8),
-            CodeExpr::IntLiteral(IntLiteralExpr {
-                repr: _,
-                value: _,
-                tag,
+                ctx.exit_scope();
+            }
+            CodeExpr::WhileLoop(WhileLoopExpr {
+                label,
+                cond,
+                body,
                 loc: _,
-            }) => match tag.as_deref() {
-                Some("u8") => Ok(LoType::U8),
-                Some("i8") => Ok(LoType::I8),
-                Some("u16") => Ok(LoType::U16),
-                Some("i16") => Ok(LoType::I16),
-                Some("u32") => Ok(LoType::U32),
-                Some("i32") => Ok(LoType::I32),
-                Some("f32") => Ok(LoType::F32),
-                Some("u64") => Ok(LoType::U64),
-                Some("i64") => Ok(LoType::I64),
-                Some("f64") => Ok(LoType::F64),
-                Some(_) => todo!(),
-                None => Ok(LoType::U32),
-            },
-            CodeExpr::StringLiteral(_) => todo!(),
-            CodeExpr::StructLiteral(StructLiteralExpr {
-                struct_name,
-                fields: _,
-                loc,
             }) => {
-                let Some(_) = self.get_struct_def(&struct_name.repr) else {
+                let cond_type = self.get_expr_type(ctx, cond)?;
+                if cond_type != LoType::Bool {
                     return Err(LoError {
-                        message: format!("Unknown struct: {}", struct_name.repr),
-                        loc: loc.clone(),
+                        message: format!(
+                            "Invalid while condition type: {cond_type}, expected: {}",
+                            LoType::Bool
+                        ),
+                        loc: cond.loc().clone(),
                     });
+                }
+
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::Block,
+                    block_type: WasmBlockType::NoOut,
+                });
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::Loop,
+                    block_type: WasmBlockType::NoOut,
+                });
+
+                // break if condition is false
+                self.codegen(ctx, instrs, cond)?;
+                instrs.push(WasmInstr::UnaryOp {
+                    kind: WasmUnaryOpKind::I32_EQZ,
+                });
+                instrs.push(WasmInstr::BranchIf { label_index: 1 });
+
+                ctx.enter_scope(LoScopeType::WhileLoop {
+                    label: label.clone(),
+                });
+                self.codegen_code_block(ctx, instrs, &body.exprs);
+                self.codegen_deferred(instrs, ctx.current_scope());
+                ctx.exit_scope();
+
+                // implicit continue
+                instrs.push(WasmInstr::Branch { label_index: 0 });
+
+                instrs.push(WasmInstr::BlockEnd);
+                instrs.push(WasmInstr::BlockEnd);
+            }
+            CodeExpr::Break(BreakExpr { label, value, loc }) => {
+                let (scope_index, label_index) =
+                    self.get_loop_branch_target(ctx, label, true, loc)?;
+
+                let loop_value_type = match &ctx.scopes[scope_index].scope_type {
+                    LoScopeType::Loop {
+                        label: _,
+                        value_type,
+                    } => Some(value_type.clone()),
+                    _ => None,
+                };
+                match (value, loop_value_type) {
+                    (Some(value), None) => {
+                        let loop_kind = match &ctx.scopes[scope_index].scope_type {
+                            LoScopeType::WhileLoop { label: _ } => "while",
+                            _ => "for",
+                        };
+                        return Err(LoError {
+                            message: format!("Cannot break with a value out of a {loop_kind} loop"),
+                            loc: value.loc().clone(),
+                        });
+                    }
+                    (Some(value), Some(loop_value_type)) => {
+                        let value_type =
+                            self.get_expr_type_with_expected(ctx, value, Some(&loop_value_type))?;
+                        if !self.is_type_compatible(&value_type, &loop_value_type) {
+                            return Err(LoError {
+                                message: format!(
+                                    "Invalid break value type: {value_type}, expected: {loop_value_type}"
+                                ),
+                                loc: value.loc().clone(),
+                            });
+                        }
+
+                        self.codegen_with_expected_type(
+                            ctx,
+                            instrs,
+                            value,
+                            Some(&loop_value_type),
+                        )?;
+                    }
+                    (None, Some(loop_value_type)) if loop_value_type != LoType::Void => {
+                        return Err(LoError {
+                            message: format!("Missing break value, expected: {loop_value_type}"),
+                            loc: loc.clone(),
+                        });
+                    }
+                    (None, _) => {}
+                }
+
+                for scope in ctx.scopes[scope_index..].iter().rev() {
+                    self.codegen_deferred(instrs, scope);
+                }
+
+                instrs.push(WasmInstr::Branch { label_index });
+            }
+            CodeExpr::Continue(ContinueExpr { label, loc }) => {
+                let (scope_index, label_index) =
+                    self.get_loop_branch_target(ctx, label, false, loc)?;
+
+                for scope in ctx.scopes[scope_index..].iter().rev() {
+                    self.codegen_deferred(instrs, scope);
+                }
+
+                instrs.push(WasmInstr::Branch { label_index });
+            }
+            CodeExpr::Defer(DeferExpr { expr, loc: _ }) => {
+                let mut deferred = Vec::new();
+                self.codegen(ctx, &mut deferred, expr)?;
+
+                let deferred_type = self.get_expr_type(ctx, expr)?;
+                for _ in 0..self.count_wasm_type_components(&deferred_type) {
+                    deferred.push(WasmInstr::Drop);
+                }
+
+                ctx.current_scope_mut().deferred.push(deferred);
+            }
+            CodeExpr::Catch(CatchExpr {
+                lhs,
+                error_bind,
+                catch_body,
+                loc,
+            }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let LoType::Result { ok_type, err_type } = lhs_type else {
+                    return Err(LoError {
+                        message: format!(
+                            "Trying to catch an error from the expression of type: {lhs_type}"
+                        ),
+                        loc: loc.clone(),
+                    });
+                };
+
+                self.codegen(ctx, instrs, lhs)?;
+
+                ctx.enter_scope(LoScopeType::Block);
+
+                let (ok_var, err_var) = self.codegen_bind_result(
+                    ctx,
+                    instrs,
+                    Some(error_bind),
+                    &ok_type,
+                    &err_type,
+                    loc,
+                )?;
+
+                // error value of zero means no error
+                self.codegen_var_get(instrs, &err_var);
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::If,
+                    block_type: self.get_block_type(&ok_type),
+                });
+
+                for (expr, expr_index) in catch_body.exprs.iter().zip(1..) {
+                    if expr_index == catch_body.exprs.len() && *ok_type != LoType::Void {
+                        let body_type = self.get_expr_type(ctx, expr)?;
+                        if body_type != *ok_type && body_type != LoType::Never {
+                            return Err(LoError {
+                                message: format!(
+                                    "Invalid catch body type: {body_type}, expected: {ok_type}"
+                                ),
+                                loc: expr.loc().clone(),
+                            });
+                        }
+                    }
+
+                    self.codegen(ctx, instrs, expr)?;
+                }
+                self.codegen_deferred(instrs, ctx.current_scope());
+
+                instrs.push(WasmInstr::Else);
+                self.codegen_var_get(instrs, &ok_var);
+                instrs.push(WasmInstr::BlockEnd);
+
+                ctx.exit_scope();
+            }
+            CodeExpr::Paren(ParenExpr { expr, loc: _ }) => {
+                self.codegen(ctx, instrs, expr)?;
+            }
+            CodeExpr::Unreachable(_) => {
+                instrs.push(WasmInstr::Unreachable);
+            }
+        };
+
+        Ok(())
+    }
+
+    fn codegen_range_loop(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        counter: &String,
+        range: &RangeExpr,
+        body: &CodeBlockExpr,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let RangeExpr {
+            start,
+            end,
+            is_inclusive,
+            step,
+        } = range;
+
+        let (counter_type, end_type) = self.get_operand_types(ctx, start, end)?;
+        if end_type != counter_type {
+            return Err(LoError {
+                message: format!("Invalid range end type: {end_type}, expected: {counter_type}"),
+                loc: loc.clone(),
+            });
+        }
+
+        // distance to the end is compared unsigned so it works for the whole range of any type
+        let distance_type = match counter_type {
+            LoType::I8 | LoType::U8 | LoType::I16 | LoType::U16 | LoType::I32 | LoType::U32 => {
+                LoType::U32
+            }
+            LoType::I64 | LoType::U64 => LoType::U64,
+            _ => {
+                return Err(LoError {
+                    message: format!("Invalid counter type: {counter_type}"),
+                    loc: loc.clone(),
+                });
+            }
+        };
+        let (step, is_descending) = self.get_range_step(step, &counter_type, &distance_type)?;
+
+        // define counter and set value to start
+        self.codegen_with_expected_type(ctx, instrs, start, Some(&counter_type))?;
+        let counter_local_index =
+            self.define_local(ctx, loc.clone(), counter.clone(), &counter_type, false)?;
+        instrs.push(WasmInstr::LocalSet {
+            local_index: counter_local_index,
+        });
+
+        // end is evaluated once
+        self.codegen_with_expected_type(ctx, instrs, end, Some(&counter_type))?;
+        let end_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &counter_type,
+            false,
+        )?;
+        instrs.push(WasmInstr::LocalSet {
+            local_index: end_local_index,
+        });
+
+        let (past_end_op, distance_lhs, distance_rhs) = match (is_descending, is_inclusive) {
+            (false, false) => (
+                InfixOpTag::GreaterEqual,
+                end_local_index,
+                counter_local_index,
+            ),
+            (false, true) => (InfixOpTag::Greater, end_local_index, counter_local_index),
+            (true, false) => (InfixOpTag::LessEqual, counter_local_index, end_local_index),
+            (true, true) => (InfixOpTag::Less, counter_local_index, end_local_index),
+        };
+        // the last item is reached when the distance to the end is less than a step
+        let last_item_op = if *is_inclusive {
+            InfixOpTag::Less
+        } else {
+            InfixOpTag::LessEqual
+        };
+        let step_instr = if distance_type == LoType::U64 {
+            WasmInstr::I64Const { value: step as i64 }
+        } else {
+            WasmInstr::I32Const {
+                value: step as u32 as i32,
+            }
+        };
+
+        {
+            instrs.push(WasmInstr::BlockStart {
+                block_kind: WasmBlockKind::Block,
+                block_type: WasmBlockType::NoOut,
+            });
+
+            // skip empty ranges
+            instrs.push(WasmInstr::LocalGet {
+                local_index: counter_local_index,
+            });
+            instrs.push(WasmInstr::LocalGet {
+                local_index: end_local_index,
+            });
+            let cmp_kind = self.get_binary_op_kind(&past_end_op, &counter_type, loc)?;
+            instrs.push(WasmInstr::BinaryOp { kind: cmp_kind });
+            instrs.push(WasmInstr::BranchIf { label_index: 0 });
+
+            {
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::Loop,
+                    block_type: WasmBlockType::NoOut,
+                });
+
+                {
+                    instrs.push(WasmInstr::BlockStart {
+                        block_kind: WasmBlockKind::Block,
+                        block_type: WasmBlockType::NoOut,
+                    });
+
+                    self.codegen_code_block(ctx, instrs, &body.exprs);
+                    self.codegen_deferred(instrs, ctx.current_scope());
+
+                    instrs.push(WasmInstr::BlockEnd);
+                }
+
+                // break on the last item, so the counter never steps past the end and overflows
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: distance_lhs,
+                });
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: distance_rhs,
+                });
+                let sub_kind = self.get_binary_op_kind(&InfixOpTag::Sub, &counter_type, loc)?;
+                instrs.push(WasmInstr::BinaryOp { kind: sub_kind });
+                instrs.push(step_instr.clone());
+                let cmp_kind = self.get_binary_op_kind(&last_item_op, &distance_type, loc)?;
+                instrs.push(WasmInstr::BinaryOp { kind: cmp_kind });
+                instrs.push(WasmInstr::BranchIf { label_index: 1 });
+
+                // step counter
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: counter_local_index,
+                });
+                instrs.push(step_instr);
+                let step_op = if is_descending {
+                    InfixOpTag::Sub
+                } else {
+                    InfixOpTag::Add
+                };
+                let step_kind = self.get_binary_op_kind(&step_op, &counter_type, loc)?;
+                instrs.push(WasmInstr::BinaryOp { kind: step_kind });
+                instrs.push(WasmInstr::LocalSet {
+                    local_index: counter_local_index,
+                });
+
+                // implicit continue
+                instrs.push(WasmInstr::Branch { label_index: 0 });
+
+                instrs.push(WasmInstr::BlockEnd);
+            }
+
+            instrs.push(WasmInstr::BlockEnd);
+        }
+
+        Ok(())
+    }
+
+    // returns the absolute value of the step and whether the range is descending
+    fn get_range_step(
+        &self,
+        step: &Option<Box<CodeExpr>>,
+        counter_type: &LoType,
+        distance_type: &LoType,
+    ) -> Result<(u64, bool), LoError> {
+        let Some(step) = step else {
+            return Ok((1, false));
+        };
+
+        let (value, is_descending) = match step.as_ref() {
+            CodeExpr::IntLiteral(IntLiteralExpr { value, .. }) => (*value, false),
+            CodeExpr::PrefixOp(PrefixOpExpr {
+                op_tag: PrefixOpTag::Negative,
+                expr,
+                loc: _,
+            }) => match expr.as_ref() {
+                CodeExpr::IntLiteral(IntLiteralExpr { value, .. }) => (*value, true),
+                _ => {
+                    return Err(LoError {
+                        message: format!("Range step must be an integer literal"),
+                        loc: step.loc().clone(),
+                    });
+                }
+            },
+            _ => {
+                return Err(LoError {
+                    message: format!("Range step must be an integer literal"),
+                    loc: step.loc().clone(),
+                });
+            }
+        };
+
+        if value == 0 {
+            return Err(LoError {
+                message: format!("Range step cannot be zero"),
+                loc: step.loc().clone(),
+            });
+        }
+
+        if *distance_type == LoType::U32 && value > u32::MAX as u64 {
+            return Err(LoError {
+                message: format!("Range step is out of range for {counter_type}"),
+                loc: step.loc().clone(),
+            });
+        }
+
+        Ok((value, is_descending))
+    }
+
+    fn codegen_slice_loop(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        item_name: &String,
+        items: &CodeExpr,
+        body: &CodeBlockExpr,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let items_type = self.get_expr_type(ctx, items)?;
+        let LoType::Slice { item_type } = &items_type else {
+            return Err(LoError {
+                message: format!(
+                    "Cannot iterate over value of type {items_type}, expected a slice"
+                ),
+                loc: items.loc().clone(),
+            });
+        };
+
+        self.codegen(ctx, instrs, items)?;
+        let slice_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &items_type,
+            false,
+        )?;
+        self.codegen_local_set(instrs, &items_type, slice_local_index);
+
+        let index_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        instrs.push(WasmInstr::I32Const { value: 0 });
+        instrs.push(WasmInstr::LocalSet {
+            local_index: index_local_index,
+        });
+
+        let item_address_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        let item_local_index =
+            self.define_local(ctx, loc.clone(), item_name.clone(), item_type, false)?;
+
+        {
+            instrs.push(WasmInstr::BlockStart {
+                block_kind: WasmBlockKind::Block,
+                block_type: WasmBlockType::NoOut,
+            });
+
+            {
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::Loop,
+                    block_type: WasmBlockType::NoOut,
+                });
+
+                // break if index is past the slice length
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: index_local_index,
+                });
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: slice_local_index + 1,
+                });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_GE_U,
+                });
+                instrs.push(WasmInstr::BranchIf { label_index: 1 });
+
+                // copy the current item
+                self.codegen_item_address(instrs, slice_local_index, index_local_index, item_type);
+                instrs.push(WasmInstr::LocalSet {
+                    local_index: item_address_local_index,
+                });
+                self.codegen_var_get(
+                    instrs,
+                    &VariableInfo::Stored {
+                        address_local_index: item_address_local_index,
+                        field_offset: 0,
+                        value_type: item_type.as_ref().clone(),
+                    },
+                );
+                self.codegen_local_set(instrs, item_type, item_local_index);
+
+                {
+                    instrs.push(WasmInstr::BlockStart {
+                        block_kind: WasmBlockKind::Block,
+                        block_type: WasmBlockType::NoOut,
+                    });
+
+                    self.codegen_code_block(ctx, instrs, &body.exprs);
+                    self.codegen_deferred(instrs, ctx.current_scope());
+
+                    instrs.push(WasmInstr::BlockEnd);
+                }
+
+                // increment index
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: index_local_index,
+                });
+                instrs.push(WasmInstr::I32Const { value: 1 });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_ADD,
+                });
+                instrs.push(WasmInstr::LocalSet {
+                    local_index: index_local_index,
+                });
+
+                // implicit continue
+                instrs.push(WasmInstr::Branch { label_index: 0 });
+
+                instrs.push(WasmInstr::BlockEnd);
+            }
+
+            instrs.push(WasmInstr::BlockEnd);
+        }
+
+        Ok(())
+    }
+
+    // returns the index of the targeted loop's scope and the label index of its exit
+    // for `break` or of its next iteration for `continue`
+    fn get_loop_branch_target(
+        &self,
+        ctx: &LoExprContext,
+        label: &Option<String>,
+        is_break: bool,
+        loc: &LoLocation,
+    ) -> Result<(usize, u32), LoError> {
+        let mut label_index = 0;
+
+        for (scope_index, scope) in ctx.scopes.iter().enumerate().rev() {
+            match &scope.scope_type {
+                LoScopeType::Function => break,
+                LoScopeType::Block => {
+                    label_index += 1;
+                }
+                LoScopeType::MatchArm { block_depth } => {
+                    label_index += block_depth;
+                }
+                LoScopeType::Loop {
+                    label: loop_label,
+                    value_type: _,
+                } => {
+                    // 0 = loop, 1 = loop wrapper block
+                    if label.is_none() || label == loop_label {
+                        if is_break {
+                            label_index += 1;
+                        }
+                        return Ok((scope_index, label_index));
+                    }
+                    label_index += 2;
+                }
+                LoScopeType::WhileLoop { label: loop_label } => {
+                    // 0 = loop, 1 = loop wrapper block
+                    if label.is_none() || label == loop_label {
+                        if is_break {
+                            label_index += 1;
+                        }
+                        return Ok((scope_index, label_index));
+                    }
+                    label_index += 2;
+                }
+                LoScopeType::ForLoop { label: loop_label } => {
+                    // 0 = body block, 1 = loop, 2 = loop wrapper block
+                    if label.is_none() || label == loop_label {
+                        if is_break {
+                            label_index += 2;
+                        }
+                        return Ok((scope_index, label_index));
+                    }
+                    label_index += 3;
+                }
+            }
+        }
+
+        if let Some(label) = label {
+            return Err(LoError {
+                message: format!("Unknown loop label '{label}"),
+                loc: loc.clone(),
+            });
+        }
+
+        Err(LoError {
+            message: if is_break {
+                format!("Cannot break outside of a loop")
+            } else {
+                format!("Cannot continue outside of a loop")
+            },
+            loc: loc.clone(),
+        })
+    }
+
+    // a loop evaluates to the value of the first `break value` that exits it,
+    // the body is scanned with its locals defined so the value can refer to them
+    fn get_loop_value_type(
+        &self,
+        ctx: &LoExprContext,
+        label: &Option<String>,
+        body: &CodeBlockExpr,
+    ) -> Result<LoType, LoError> {
+        let scan_ctx = &mut ctx.clone();
+        scan_ctx.enter_scope(LoScopeType::Block);
+
+        let value_type = self.find_break_value_type(scan_ctx, &body.exprs, label, true)?;
+        Ok(value_type.unwrap_or(LoType::Void))
+    }
+
+    fn find_break_value_type(
+        &self,
+        ctx: &mut LoExprContext,
+        exprs: &[CodeExpr],
+        label: &Option<String>,
+        is_innermost_loop: bool,
+    ) -> Result<Option<LoType>, LoError> {
+        for expr in exprs {
+            let value_type = match expr {
+                CodeExpr::Break(BreakExpr {
+                    label: break_label,
+                    value: Some(value),
+                    loc: _,
+                }) => {
+                    let targets_loop = match break_label {
+                        Some(_) => break_label == label,
+                        None => is_innermost_loop,
+                    };
+                    if !targets_loop {
+                        continue;
+                    }
+
+                    Some(self.get_expr_type(ctx, value)?)
+                }
+                CodeExpr::Let(LetExpr {
+                    local_name,
+                    local_type,
+                    value,
+                    loc,
+                }) => {
+                    let value_type = self.find_break_value_type(
+                        ctx,
+                        core::slice::from_ref(value.as_ref()),
+                        label,
+                        is_innermost_loop,
+                    )?;
+
+                    if local_name != "_" {
+                        let local_type = match local_type {
+                            Some(local_type) => {
+                                self.build_type_in_scope(&ctx.type_scope, local_type)?
+                            }
+                            None => self.get_expr_type(ctx, value)?,
+                        };
+                        self.define_local(
+                            ctx,
+                            loc.clone(),
+                            local_name.clone(),
+                            &local_type,
+                            false,
+                        )?;
+                    }
+
+                    value_type
+                }
+                CodeExpr::If(IfExpr {
+                    cond: _,
+                    then_block,
+                    else_block,
+                    loc: _,
+                }) => {
+                    ctx.enter_scope(LoScopeType::Block);
+                    let mut value_type = self.find_break_value_type(
+                        ctx,
+                        &then_block.exprs,
+                        label,
+                        is_innermost_loop,
+                    )?;
+                    ctx.exit_scope();
+
+                    if value_type.is_none() {
+                        value_type = match else_block {
+                            ElseBlock::None => None,
+                            ElseBlock::Else(else_block) => {
+                                ctx.enter_scope(LoScopeType::Block);
+                                let value_type = self.find_break_value_type(
+                                    ctx,
+                                    &else_block.exprs,
+                                    label,
+                                    is_innermost_loop,
+                                )?;
+                                ctx.exit_scope();
+                                value_type
+                            }
+                            ElseBlock::ElseIf(else_if) => self.find_break_value_type(
+                                ctx,
+                                core::slice::from_ref(else_if.as_ref()),
+                                label,
+                                is_innermost_loop,
+                            )?,
+                        };
+                    }
+
+                    value_type
+                }
+                CodeExpr::Match(MatchExpr { expr, arms, loc: _ }) => {
+                    let expr_type = self.get_expr_type(ctx, expr)?;
+
+                    let mut value_type = None;
+                    for arm in arms {
+                        ctx.enter_scope(LoScopeType::Block);
+                        if let (Some(payload_bind), LoType::EnumInstance { enum_name }) =
+                            (&arm.payload_bind, &expr_type)
+                        {
+                            if let Some((enum_def, variant_index)) = self
+                                .get_enum_variant(&arm.variant_name.repr)
+                                .filter(|(arm_enum_def, _)| arm_enum_def.enum_name == *enum_name)
+                            {
+                                self.define_local(
+                                    ctx,
+                                    payload_bind.loc.clone(),
+                                    payload_bind.repr.clone(),
+                                    &enum_def.variants[variant_index].payload_type,
+                                    false,
+                                )?;
+                            }
+                        }
+                        value_type = self.find_break_value_type(
+                            ctx,
+                            &arm.body.exprs,
+                            label,
+                            is_innermost_loop,
+                        )?;
+                        ctx.exit_scope();
+
+                        if value_type.is_some() {
+                            break;
+                        }
+                    }
+
+                    value_type
+                }
+                CodeExpr::Catch(CatchExpr {
+                    lhs,
+                    error_bind,
+                    catch_body,
+                    loc,
+                }) => {
+                    ctx.enter_scope(LoScopeType::Block);
+                    if let LoType::Result {
+                        ok_type: _,
+                        err_type,
+                    } = self.get_expr_type(ctx, lhs)?
+                    {
+                        self.define_local(ctx, loc.clone(), error_bind.clone(), &err_type, false)?;
+                    }
+                    let value_type = self.find_break_value_type(
+                        ctx,
+                        &catch_body.exprs,
+                        label,
+                        is_innermost_loop,
+                    )?;
+                    ctx.exit_scope();
+
+                    value_type
+                }
+                CodeExpr::Loop(LoopExpr {
+                    label: _,
+                    body,
+                    loc: _,
+                }) => {
+                    ctx.enter_scope(LoScopeType::Block);
+                    let value_type = self.find_break_value_type(ctx, &body.exprs, label, false)?;
+                    ctx.exit_scope();
+
+                    value_type
+                }
+                CodeExpr::ForLoop(ForLoopExpr {
+                    label: _,
+                    item_name,
+                    iterable,
+                    body,
+                    loc,
+                }) => {
+                    ctx.enter_scope(LoScopeType::Block);
+                    let item_type = match iterable {
+                        ForLoopIterable::Range(RangeExpr { start, end, .. }) => {
+                            Some(self.get_operand_types(ctx, start, end)?.0)
+                        }
+                        ForLoopIterable::Items(items) => match self.get_expr_type(ctx, items)? {
+                            LoType::Slice { item_type } => Some(*item_type),
+                            _ => None,
+                        },
+                    };
+                    if let Some(item_type) = item_type {
+                        self.define_local(ctx, loc.clone(), item_name.clone(), &item_type, false)?;
+                    }
+                    let value_type = self.find_break_value_type(ctx, &body.exprs, label, false)?;
+                    ctx.exit_scope();
+
+                    value_type
+                }
+                CodeExpr::WhileLoop(WhileLoopExpr {
+                    label: _,
+                    cond: _,
+                    body,
+                    loc: _,
+                }) => {
+                    ctx.enter_scope(LoScopeType::Block);
+                    let value_type = self.find_break_value_type(ctx, &body.exprs, label, false)?;
+                    ctx.exit_scope();
+
+                    value_type
+                }
+                _ => None,
+            };
+
+            if value_type.is_some() {
+                return Ok(value_type);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn codegen_fn_call(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        fn_name: &str,
+        receiver_arg: Option<&CodeExpr>,
+        args: &Vec<CodeExpr>,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let (fn_type, wasm_fn_index) = match self.get_fn_info(fn_name) {
+            Some((lo_fn_info, wasm_fn_info)) => {
+                (lo_fn_info.fn_type.clone(), wasm_fn_info.wasm_fn_index)
+            }
+            None => {
+                let Some(instance) = self.get_generic_method_instance(fn_name, loc)? else {
+                    return Err(LoError {
+                        message: format!("Unknown function: {}", fn_name),
+                        loc: loc.clone(),
+                    });
+                };
+
+                (
+                    instance.fn_type,
+                    self.late_fns_start + instance.late_fn_index,
+                )
+            }
+        };
+
+        let mut arg_types = Vec::new();
+        if let Some(receiver_arg) = receiver_arg {
+            arg_types.push(self.get_expr_type(ctx, receiver_arg)?);
+            self.codegen(ctx, instrs, receiver_arg)?;
+        }
+        for arg in args {
+            let expected_type = fn_type.inputs.get(arg_types.len());
+            arg_types.push(self.codegen_with_expected_type(ctx, instrs, arg, expected_type)?);
+        }
+
+        if !self.are_types_compatible(&arg_types, &fn_type.inputs) {
+            return Err(LoError {
+                message: format!(
+                    "Invalid function arguments for function {}: [{}], expected [{}]",
+                    fn_name,
+                    ListDisplay(&arg_types),
+                    ListDisplay(&fn_type.inputs),
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        instrs.push(WasmInstr::Call {
+            fn_index: wasm_fn_index,
+        });
+
+        Ok(())
+    }
+
+    fn codegen_indirect_call(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        callee: &VariableInfo,
+        args: &Vec<CodeExpr>,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let callee_type = callee.get_type_ref();
+        let (inputs, output) = callee_type.get_call_signature().unwrap();
+
+        // closure's (table index, env) pair is split around the args
+        let mut closure_local_index = None;
+        if let LoType::Closure { .. } = callee_type {
+            self.codegen_var_get(instrs, callee);
+            let local_index = self.define_local(
+                ctx,
+                loc.clone(),
+                format!("%{}", ctx.last_local_index),
+                callee_type,
+                false,
+            )?;
+            self.codegen_local_set(instrs, callee_type, local_index);
+            closure_local_index = Some(local_index);
+        }
+
+        let mut arg_types = Vec::new();
+        for (arg, arg_index) in args.iter().zip(0..) {
+            arg_types.push(self.codegen_with_expected_type(
+                ctx,
+                instrs,
+                arg,
+                inputs.get(arg_index),
+            )?);
+        }
+
+        if !self.are_types_compatible(&arg_types, inputs) {
+            let callee_kind = match callee_type {
+                LoType::Closure { .. } => "closure",
+                _ => "function pointer",
+            };
+            return Err(LoError {
+                message: format!(
+                    "Invalid function arguments for {callee_kind} {callee_type}: [{}], expected [{}]",
+                    ListDisplay(&arg_types),
+                    ListDisplay(inputs),
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        let mut wasm_fn_type = self.lower_fn_type(inputs, output);
+        if let Some(local_index) = closure_local_index {
+            instrs.push(WasmInstr::LocalGet {
+                local_index: local_index + 1,
+            });
+            instrs.push(WasmInstr::LocalGet { local_index });
+            wasm_fn_type.inputs.push(WasmType::I32);
+        } else {
+            self.codegen_var_get(instrs, callee);
+        }
+
+        let type_index = self.insert_fn_type(wasm_fn_type);
+        instrs.push(WasmInstr::CallIndirect {
+            type_index,
+            table_index: 0,
+        });
+
+        Ok(())
+    }
+
+    // untagged int literals and untyped closure params are inferred from the expected type
+    fn codegen_with_expected_type(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        expr: &CodeExpr,
+        expected_type: Option<&LoType>,
+    ) -> Result<LoType, LoError> {
+        if let Some((literal, is_negative, literal_type)) =
+            self.get_contextual_int_literal(expr, expected_type)
+        {
+            self.codegen_int_literal(instrs, literal, is_negative, &literal_type)?;
+            return Ok(literal_type);
+        }
+
+        if let Some((literal, is_negative, literal_type)) =
+            self.get_contextual_float_literal(expr, expected_type)
+        {
+            self.codegen_float_literal(instrs, literal, is_negative, &literal_type)?;
+            return Ok(literal_type);
+        }
+
+        if let CodeExpr::Closure(closure) = expr {
+            return self.codegen_closure(ctx, instrs, closure, expected_type);
+        }
+
+        if let CodeExpr::TupleLiteral(literal) = expr {
+            return self.codegen_tuple_literal(ctx, instrs, literal, expected_type);
+        }
+
+        let expr_type = self.get_expr_type(ctx, expr)?;
+        self.codegen(ctx, instrs, expr)?;
+
+        Ok(expr_type)
+    }
+
+    fn get_expr_type_with_expected(
+        &self,
+        ctx: &LoExprContext,
+        expr: &CodeExpr,
+        expected_type: Option<&LoType>,
+    ) -> Result<LoType, LoError> {
+        if let Some((_, _, literal_type)) = self.get_contextual_int_literal(expr, expected_type) {
+            return Ok(literal_type);
+        }
+
+        if let Some((_, _, literal_type)) = self.get_contextual_float_literal(expr, expected_type) {
+            return Ok(literal_type);
+        }
+
+        if let CodeExpr::Closure(closure) = expr {
+            let (_, closure_type) = self.build_closure_ctx(ctx, closure, expected_type)?;
+            return Ok(closure_type);
+        }
+
+        if let CodeExpr::TupleLiteral(literal) = expr {
+            return self.get_tuple_literal_type(ctx, literal, expected_type);
+        }
+
+        self.get_expr_type(ctx, expr)
+    }
+
+    // items of a tuple literal are typed by the matching items of the expected tuple
+    fn codegen_tuple_literal(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        literal: &TupleLiteralExpr,
+        expected_type: Option<&LoType>,
+    ) -> Result<LoType, LoError> {
+        let expected_item_types = self.get_expected_tuple_item_types(literal, expected_type);
+
+        let mut item_types = Vec::new();
+        for (item, index) in literal.items.iter().zip(0..) {
+            let expected_item_type = expected_item_types.map(|types| &types[index]);
+            item_types.push(self.codegen_with_expected_type(
+                ctx,
+                instrs,
+                item,
+                expected_item_type,
+            )?);
+        }
+
+        Ok(LoType::Tuple { item_types })
+    }
+
+    fn get_tuple_literal_type(
+        &self,
+        ctx: &LoExprContext,
+        literal: &TupleLiteralExpr,
+        expected_type: Option<&LoType>,
+    ) -> Result<LoType, LoError> {
+        let expected_item_types = self.get_expected_tuple_item_types(literal, expected_type);
+
+        let mut item_types = Vec::new();
+        for (item, index) in literal.items.iter().zip(0..) {
+            let expected_item_type = expected_item_types.map(|types| &types[index]);
+            item_types.push(self.get_expr_type_with_expected(ctx, item, expected_item_type)?);
+        }
+
+        Ok(LoType::Tuple { item_types })
+    }
+
+    fn get_expected_tuple_item_types<'a>(
+        &self,
+        literal: &TupleLiteralExpr,
+        expected_type: Option<&'a LoType>,
+    ) -> Option<&'a Vec<LoType>> {
+        match expected_type {
+            Some(LoType::Tuple { item_types }) if item_types.len() == literal.items.len() => {
+                Some(item_types)
+            }
+            _ => None,
+        }
+    }
+
+    // an untagged literal operand takes the type of the other operand
+    fn get_operand_types(
+        &self,
+        ctx: &LoExprContext,
+        lhs: &CodeExpr,
+        rhs: &CodeExpr,
+    ) -> Result<(LoType, LoType), LoError> {
+        let lhs_is_literal = self.is_untagged_literal(lhs);
+        let rhs_is_literal = self.is_untagged_literal(rhs);
+
+        if lhs_is_literal && !rhs_is_literal {
+            let rhs_type = self.get_expr_type(ctx, rhs)?;
+            let lhs_type = self.get_expr_type_with_expected(ctx, lhs, Some(&rhs_type))?;
+            return Ok((lhs_type, rhs_type));
+        }
+
+        let lhs_type = self.get_expr_type(ctx, lhs)?;
+        let rhs_type = self.get_expr_type_with_expected(ctx, rhs, Some(&lhs_type))?;
+        Ok((lhs_type, rhs_type))
+    }
+
+    fn is_untagged_literal(&self, expr: &CodeExpr) -> bool {
+        match expr {
+            CodeExpr::IntLiteral(IntLiteralExpr { tag: None, .. })
+            | CodeExpr::FloatLiteral(FloatLiteralExpr { tag: None, .. }) => true,
+            CodeExpr::PrefixOp(PrefixOpExpr {
+                op_tag: PrefixOpTag::Negative,
+                expr,
+                loc: _,
+            }) => matches!(
+                expr.as_ref(),
+                CodeExpr::IntLiteral(IntLiteralExpr { tag: None, .. })
+                    | CodeExpr::FloatLiteral(FloatLiteralExpr { tag: None, .. })
+            ),
+            _ => false,
+        }
+    }
+
+    fn get_contextual_int_literal<'a>(
+        &self,
+        expr: &'a CodeExpr,
+        expected_type: Option<&LoType>,
+    ) -> Option<(&'a IntLiteralExpr, bool, LoType)> {
+        let expected_type = expected_type?;
+
+        match expr {
+            CodeExpr::IntLiteral(literal @ IntLiteralExpr { tag: None, .. }) => match expected_type
+            {
+                LoType::U8
+                | LoType::I8
+                | LoType::U16
+                | LoType::I16
+                | LoType::U32
+                | LoType::I32
+                | LoType::U64
+                | LoType::I64 => Some((literal, false, expected_type.clone())),
+                _ => None,
+            },
+            CodeExpr::PrefixOp(PrefixOpExpr {
+                op_tag: PrefixOpTag::Negative,
+                expr,
+                loc: _,
+            }) => {
+                let CodeExpr::IntLiteral(literal @ IntLiteralExpr { tag: None, .. }) =
+                    expr.as_ref()
+                else {
+                    return None;
+                };
+
+                match expected_type {
+                    LoType::I8 | LoType::I16 | LoType::I32 | LoType::I64 => {
+                        Some((literal, true, expected_type.clone()))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn get_contextual_float_literal<'a>(
+        &self,
+        expr: &'a CodeExpr,
+        expected_type: Option<&LoType>,
+    ) -> Option<(&'a FloatLiteralExpr, bool, LoType)> {
+        let expected_type @ (LoType::F32 | LoType::F64) = expected_type? else {
+            return None;
+        };
+
+        match expr {
+            CodeExpr::FloatLiteral(literal @ FloatLiteralExpr { tag: None, .. }) => {
+                Some((literal, false, expected_type.clone()))
+            }
+            CodeExpr::PrefixOp(PrefixOpExpr {
+                op_tag: PrefixOpTag::Negative,
+                expr,
+                loc: _,
+            }) => {
+                let CodeExpr::FloatLiteral(literal @ FloatLiteralExpr { tag: None, .. }) =
+                    expr.as_ref()
+                else {
+                    return None;
+                };
+
+                Some((literal, true, expected_type.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    fn codegen_int_literal(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        literal: &IntLiteralExpr,
+        is_negative: bool,
+        literal_type: &LoType,
+    ) -> Result<(), LoError> {
+        self.check_int_literal_range(literal, is_negative, literal_type)?;
+
+        let value = if is_negative {
+            (literal.value as i64).wrapping_neg()
+        } else {
+            literal.value as i64
+        };
+
+        match literal_type {
+            LoType::U64 | LoType::I64 => instrs.push(WasmInstr::I64Const { value }),
+            LoType::F32 => instrs.push(WasmInstr::F32Const {
+                value: value as f32,
+            }),
+            LoType::F64 => instrs.push(WasmInstr::F64Const {
+                value: value as f64,
+            }),
+            _ => instrs.push(WasmInstr::I32Const {
+                value: value as i32,
+            }),
+        }
+
+        Ok(())
+    }
+
+    fn check_int_literal_range(
+        &self,
+        literal: &IntLiteralExpr,
+        is_negative: bool,
+        literal_type: &LoType,
+    ) -> Result<(), LoError> {
+        let (max_negative, max_positive) = match literal_type {
+            LoType::U8 => (0, u8::MAX as u64),
+            LoType::I8 => (1 << 7, i8::MAX as u64),
+            LoType::U16 => (0, u16::MAX as u64),
+            LoType::I16 => (1 << 15, i16::MAX as u64),
+            LoType::U32 => (0, u32::MAX as u64),
+            LoType::I32 => (1 << 31, i32::MAX as u64),
+            LoType::U64 => (0, u64::MAX),
+            LoType::I64 => (1 << 63, i64::MAX as u64),
+            _ => return Ok(()),
+        };
+
+        let max_value = if is_negative {
+            max_negative
+        } else {
+            max_positive
+        };
+        if literal.value > max_value {
+            return Err(LoError {
+                message: format!(
+                    "Integer literal {}{} is out of range for {literal_type}",
+                    if is_negative { "-" } else { "" },
+                    literal.repr
+                ),
+                loc: literal.loc.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn codegen_float_literal(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        literal: &FloatLiteralExpr,
+        is_negative: bool,
+        literal_type: &LoType,
+    ) -> Result<(), LoError> {
+        self.check_float_literal_range(literal, literal_type)?;
+
+        let value = if is_negative {
+            -literal.value
+        } else {
+            literal.value
+        };
+
+        match literal_type {
+            LoType::F32 => instrs.push(WasmInstr::F32Const {
+                value: value as f32,
+            }),
+            _ => instrs.push(WasmInstr::F64Const { value }),
+        }
+
+        Ok(())
+    }
+
+    fn check_float_literal_range(
+        &self,
+        literal: &FloatLiteralExpr,
+        literal_type: &LoType,
+    ) -> Result<(), LoError> {
+        let is_out_of_range = match literal_type {
+            LoType::F32 => (literal.value as f32).is_infinite(),
+            _ => literal.value.is_infinite(),
+        };
+
+        if is_out_of_range {
+            return Err(LoError {
+                message: format!(
+                    "Float literal {} is out of range for {literal_type}",
+                    literal.repr
+                ),
+                loc: literal.loc.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // float to int casts saturate instead of trapping on NaN and out of range values
+    fn get_float_conversion_instr(
+        &self,
+        castee_type: &LoType,
+        casted_to: &LoType,
+    ) -> Option<WasmInstr> {
+        let trunc_sat = |kind| Some(WasmInstr::TruncSat { kind });
+
+        let kind = match (castee_type, casted_to) {
+            (LoType::I8 | LoType::I16 | LoType::I32, LoType::F32) => {
+                WasmUnaryOpKind::F32_CONVERT_I32_S
+            }
+            (LoType::U8 | LoType::U16 | LoType::U32, LoType::F32) => {
+                WasmUnaryOpKind::F32_CONVERT_I32_U
+            }
+            (LoType::I64, LoType::F32) => WasmUnaryOpKind::F32_CONVERT_I64_S,
+            (LoType::U64, LoType::F32) => WasmUnaryOpKind::F32_CONVERT_I64_U,
+            (LoType::F64, LoType::F32) => WasmUnaryOpKind::F32_DEMOTE_F64,
+            (LoType::I8 | LoType::I16 | LoType::I32, LoType::F64) => {
+                WasmUnaryOpKind::F64_CONVERT_I32_S
+            }
+            (LoType::U8 | LoType::U16 | LoType::U32, LoType::F64) => {
+                WasmUnaryOpKind::F64_CONVERT_I32_U
+            }
+            (LoType::I64, LoType::F64) => WasmUnaryOpKind::F64_CONVERT_I64_S,
+            (LoType::U64, LoType::F64) => WasmUnaryOpKind::F64_CONVERT_I64_U,
+            (LoType::F32, LoType::F64) => WasmUnaryOpKind::F64_PROMOTE_F32,
+            (LoType::F32, LoType::I32) => return trunc_sat(WasmTruncSatKind::I32_TRUNC_SAT_F32_S),
+            (LoType::F32, LoType::U32) => return trunc_sat(WasmTruncSatKind::I32_TRUNC_SAT_F32_U),
+            (LoType::F64, LoType::I32) => return trunc_sat(WasmTruncSatKind::I32_TRUNC_SAT_F64_S),
+            (LoType::F64, LoType::U32) => return trunc_sat(WasmTruncSatKind::I32_TRUNC_SAT_F64_U),
+            (LoType::F32, LoType::I64) => return trunc_sat(WasmTruncSatKind::I64_TRUNC_SAT_F32_S),
+            (LoType::F32, LoType::U64) => return trunc_sat(WasmTruncSatKind::I64_TRUNC_SAT_F32_U),
+            (LoType::F64, LoType::I64) => return trunc_sat(WasmTruncSatKind::I64_TRUNC_SAT_F64_S),
+            (LoType::F64, LoType::U64) => return trunc_sat(WasmTruncSatKind::I64_TRUNC_SAT_F64_U),
+            _ => return None,
+        };
+
+        Some(WasmInstr::UnaryOp { kind })
+    }
+
+    // closures are lifted into functions taking the env pointer as the last param
+    fn codegen_closure(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        closure: &ClosureExpr,
+        expected_type: Option<&LoType>,
+    ) -> Result<LoType, LoError> {
+        let (mut closure_ctx, closure_type) =
+            self.build_closure_ctx(ctx, closure, expected_type)?;
+
+        let mut wasm_expr = WasmExpr { instrs: Vec::new() };
+        match &closure.body {
+            ClosureBody::Expr(expr) => {
+                let output = closure_ctx.fn_return_type.clone();
+                self.codegen_with_expected_type(
+                    &mut closure_ctx,
+                    &mut wasm_expr.instrs,
+                    expr,
+                    output.as_ref(),
+                )?;
+            }
+            ClosureBody::Block(block) => {
+                self.codegen_code_block(&mut closure_ctx, &mut wasm_expr.instrs, &block.exprs);
+                self.codegen_deferred(&mut wasm_expr.instrs, closure_ctx.current_scope());
+            }
+        }
+
+        let (inputs, output) = closure_type.get_call_signature().unwrap();
+        let mut wasm_fn_type = self.lower_fn_type(inputs, output);
+        wasm_fn_type.inputs.push(WasmType::I32);
+        let type_index = self.insert_fn_type(wasm_fn_type);
+
+        let mut late_fns = self.late_fns.borrow_mut();
+        let wasm_fn_index = self.late_fns_start + late_fns.len() as u32;
+        late_fns.push(LoLateFn {
+            fn_name: format!("closure@{}", closure.loc),
+            type_index,
+            wasm_fn: WasmFn {
+                locals: self.lower_locals(&closure_ctx),
+                expr: wasm_expr,
+            },
+        });
+        drop(late_fns);
+
+        let table_index = self.get_fn_table_index(wasm_fn_index);
+        instrs.push(WasmInstr::I32Const {
+            value: table_index as i32,
+        });
+
+        let closure_env = closure_ctx.closure_env.unwrap();
+        let captures = closure_env.captures.into_inner();
+        if captures.len() == 0 {
+            instrs.push(WasmInstr::I32Const { value: 0 });
+            return Ok(closure_type);
+        }
+
+        self.codegen_closure_env(ctx, instrs, &captures, &closure.loc)?;
+
+        Ok(closure_type)
+    }
+
+    fn build_closure_ctx(
+        &self,
+        ctx: &LoExprContext,
+        closure: &ClosureExpr,
+        expected_type: Option<&LoType>,
+    ) -> Result<(LoExprContext, LoType), LoError> {
+        let mut expected_signature = None;
+        if let Some(LoType::Closure { inputs, output }) = expected_type {
+            if inputs.len() == closure.params.len() {
+                expected_signature = Some((inputs, output));
+            }
+        }
+
+        let mut closure_ctx = LoExprContext::default();
+        closure_ctx.type_scope = ctx.type_scope.clone();
+        closure_ctx.enter_scope(LoScopeType::Function);
+
+        let mut inputs = Vec::new();
+        for (param, param_index) in closure.params.iter().zip(0..) {
+            let param_type = match (&param.param_type, expected_signature) {
+                (Some(param_type), _) => self.build_type_in_scope(&ctx.type_scope, param_type)?,
+                (None, Some((expected_inputs, _))) => {
+                    let expected_inputs: &Vec<LoType> = expected_inputs;
+                    expected_inputs[param_index].clone()
+                }
+                (None, None) => {
+                    return Err(LoError {
+                        message: format!(
+                            "Cannot infer type of closure param {}, please annotate it",
+                            param.param_name
+                        ),
+                        loc: param.loc.clone(),
+                    });
+                }
+            };
+
+            self.define_local(
+                &mut closure_ctx,
+                param.loc.clone(),
+                param.param_name.clone(),
+                &param_type,
+                true,
+            )?;
+            inputs.push(param_type);
+        }
+
+        let env_local_index = self.define_local(
+            &mut closure_ctx,
+            closure.loc.clone(),
+            String::from("%env"),
+            &LoType::U32,
+            true,
+        )?;
+        closure_ctx.closure_env = Some(LoClosureEnv {
+            enclosing_ctx: Box::new(ctx.clone()),
+            env_local_index,
+            captures: RefCell::new(Vec::new()),
+        });
+
+        let output = match (&closure.return_type, &closure.body) {
+            (Some(return_type), _) => self.build_type_in_scope(&ctx.type_scope, return_type)?,
+            (None, ClosureBody::Expr(expr)) => {
+                let expected_output = expected_signature.map(|(_, output)| output.as_ref());
+                self.get_expr_type_with_expected(&closure_ctx, expr, expected_output)?
+            }
+            (None, ClosureBody::Block(_)) => match expected_signature {
+                Some((_, expected_output)) => expected_output.as_ref().clone(),
+                None => LoType::Void,
+            },
+        };
+        closure_ctx.fn_return_type = Some(output.clone());
+
+        let closure_type = LoType::Closure {
+            inputs,
+            output: Box::new(output),
+        };
+
+        Ok((closure_ctx, closure_type))
+    }
+
+    fn codegen_closure_env(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        captures: &Vec<LoCapture>,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let alloc_fn_name = "closure::alloc_env";
+        let Some((alloc_fn_info, alloc_wasm_fn_info)) = self.get_fn_info(alloc_fn_name) else {
+            return Err(LoError {
+                message: format!(
+                    "Closure captures locals but {alloc_fn_name} is not defined, \
+                    expected fn(u32): &void to allocate its environment"
+                ),
+                loc: loc.clone(),
+            });
+        };
+        if alloc_fn_info.fn_type.inputs != [LoType::U32]
+            || self.count_wasm_type_components(&alloc_fn_info.fn_type.output) != 1
+        {
+            return Err(LoError {
+                message: format!(
+                    "Invalid {alloc_fn_name} signature: fn({}): {}, expected fn(u32): &void",
+                    ListDisplay(&alloc_fn_info.fn_type.inputs),
+                    alloc_fn_info.fn_type.output
+                ),
+                loc: alloc_fn_info.definition_loc.clone(),
+            });
+        }
+
+        let last_capture = captures.last().unwrap();
+        let layout = &mut LoTypeLayout::default();
+        self.get_type_layout(&last_capture.local_type, layout);
+        let env_size = last_capture.env_offset + layout.byte_length;
+
+        instrs.push(WasmInstr::I32Const {
+            value: env_size as i32,
+        });
+        instrs.push(WasmInstr::Call {
+            fn_index: alloc_wasm_fn_info.wasm_fn_index,
+        });
+        let env_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        instrs.push(WasmInstr::LocalSet {
+            local_index: env_local_index,
+        });
+
+        for capture in captures {
+            let env_var = VariableInfo::Stored {
+                address_local_index: env_local_index,
+                field_offset: capture.env_offset,
+                value_type: capture.local_type.clone(),
+            };
+            let captured_value = CodeExpr::Ident(IdentExpr {
+                repr: capture.local_name.clone(),
+                parts: vec![capture.local_name.clone()],
+                loc: loc.clone(),
+            });
+            self.codegen_var_set(ctx, instrs, &env_var, &captured_value)?;
+        }
+
+        instrs.push(WasmInstr::LocalGet {
+            local_index: env_local_index,
+        });
+
+        Ok(())
+    }
+
+    fn codegen_deferred(&self, instrs: &mut Vec<WasmInstr>, scope: &LoScope) {
+        for deferred in scope.deferred.iter().rev() {
+            instrs.extend_from_slice(deferred);
+        }
+    }
+
+    fn codegen_macro_call(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        call: &LoCallSite,
+        type_args: &Vec<TypeExpr>,
+    ) -> Result<(), LoError> {
+        let &LoCallSite {
+            name: macro_name,
+            receiver: receiver_arg,
+            args,
+            loc,
+        } = call;
+        let macro_def = self.get_macro_def_or_err(macro_name, loc)?;
+        let receiver_type = match receiver_arg {
+            Some(receiver_arg) => Some(self.get_expr_type(ctx, receiver_arg)?),
+            None => None,
+        };
+        let type_scope =
+            self.build_macro_type_scope(ctx, macro_def, receiver_type.as_ref(), type_args, loc)?;
+        let return_type = self.get_macro_return_type(&type_scope, macro_def)?;
+
+        let mut all_args = Vec::new();
+        if let Some(receiver_arg) = receiver_arg {
+            all_args.push(receiver_arg);
+        }
+        for arg in args {
+            all_args.push(arg);
+        }
+
+        let mut param_types = Vec::new();
+        for macro_param in &macro_def.macro_params {
+            param_types.push(self.get_macro_param_type(&type_scope, macro_def, macro_param)?);
+        }
+
+        let mut arg_types = Vec::new();
+        for arg in &all_args {
+            arg_types.push(self.get_expr_type(ctx, arg)?);
+        }
+
+        if !self.are_types_compatible(&arg_types, &param_types) {
+            return Err(LoError {
+                message: format!(
+                    "Invalid macro arguments for macro {}: [{}], expected [{}], macro defined at {}",
+                    macro_name,
+                    ListDisplay(&arg_types),
+                    ListDisplay(&param_types),
+                    macro_def.loc,
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        // args are evaluated in the caller's scope before any of the params are bound
+        for arg in &all_args {
+            self.codegen(ctx, instrs, arg)?;
+        }
+
+        ctx.enter_scope(LoScopeType::Block);
+        let caller_type_scope = core::mem::replace(&mut ctx.type_scope, type_scope);
+
+        let mut param_locals = Vec::new();
+        for (macro_param, param_type) in macro_def.macro_params.iter().zip(param_types) {
+            let local_index = self.define_local(
+                ctx,
+                macro_param.loc.clone(),
+                macro_param.param_name.clone(),
+                &param_type,
+                false,
+            )?;
+            param_locals.push((local_index, param_type));
+        }
+        for (local_index, param_type) in param_locals.iter().rev() {
+            self.codegen_local_set(instrs, param_type, *local_index);
+        }
+
+        instrs.push(WasmInstr::BlockStart {
+            block_kind: WasmBlockKind::Block,
+            block_type: self.get_block_type(&return_type),
+        });
+
+        let body_result = self.codegen_macro_body(ctx, instrs, macro_def, &return_type);
+
+        // macro defers run at the end of the caller's scope, e.g. to free `tmp_alloc!` memory
+        let deferred = core::mem::take(&mut ctx.current_scope_mut().deferred);
+        ctx.type_scope = caller_type_scope;
+        ctx.exit_scope();
+        ctx.current_scope_mut().deferred.extend(deferred);
+
+        if let Err(err) = body_result {
+            return Err(LoError {
+                message: format!("{}, in macro {macro_name} expanded at {loc}", err.message),
+                loc: err.loc,
+            });
+        }
+
+        instrs.push(WasmInstr::BlockEnd);
+
+        Ok(())
+    }
+
+    fn codegen_macro_body(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        macro_def: &MacroDefExpr,
+        return_type: &LoType,
+    ) -> Result<(), LoError> {
+        // trailing defers don't produce the macro's value
+        let value_expr_index = macro_def
+            .body
+            .exprs
+            .iter()
+            .rposition(|expr| !matches!(expr, CodeExpr::Defer(_)));
+
+        for (expr, expr_index) in macro_def.body.exprs.iter().zip(0..) {
+            if Some(expr_index) == value_expr_index && *return_type != LoType::Void {
+                let body_type = self.get_expr_type(ctx, expr)?;
+                if body_type != *return_type && body_type != LoType::Never {
+                    return Err(LoError {
+                        message: format!(
+                            "Invalid macro body type: {body_type}, expected: {return_type}"
+                        ),
+                        loc: expr.loc().clone(),
+                    });
+                }
+            }
+
+            self.codegen(ctx, instrs, expr)?;
+        }
+
+        Ok(())
+    }
+
+    fn build_macro_type_scope(
+        &self,
+        ctx: &LoExprContext,
+        macro_def: &MacroDefExpr,
+        receiver_type: Option<&LoType>,
+        type_args: &Vec<TypeExpr>,
+        loc: &LoLocation,
+    ) -> Result<Vec<LoTypeDef>, LoError> {
+        let mut type_arg_values = Vec::new();
+        for type_arg in type_args {
+            type_arg_values.push(self.build_type_in_scope(&ctx.type_scope, type_arg)?);
+        }
+
+        // methods of generic structs take type args from the receiver if none are provided
+        if let Some(receiver_type) = receiver_type {
+            if type_args.len() == 0 && macro_def.macro_type_params.len() != 0 {
+                type_arg_values = self.get_struct_type_args(receiver_type);
+            }
+        }
+
+        if type_arg_values.len() != macro_def.macro_type_params.len() {
+            return Err(LoError {
+                message: format!(
+                    "Invalid number of type params, expected {}, got {}",
+                    macro_def.macro_type_params.len(),
+                    type_arg_values.len()
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        let mut type_scope = Vec::new();
+        for (type_param, type_arg) in macro_def.macro_type_params.iter().zip(type_arg_values) {
+            self.check_trait_bounds(type_param, &type_arg, loc)?;
+
+            type_scope.push(LoTypeDef {
+                name: type_param.name.clone(),
+                value: type_arg,
+                loc: macro_def.loc.clone(),
+            });
+        }
+
+        Ok(type_scope)
+    }
+
+    fn get_macro_return_type(
+        &self,
+        type_scope: &Vec<LoTypeDef>,
+        macro_def: &MacroDefExpr,
+    ) -> Result<LoType, LoError> {
+        match &macro_def.return_type {
+            Some(return_type) => self.build_type_in_scope(type_scope, return_type),
+            None => Ok(LoType::Void),
+        }
+    }
+
+    fn get_macro_param_type(
+        &self,
+        type_scope: &Vec<LoTypeDef>,
+        macro_def: &MacroDefExpr,
+        macro_param: &FnParam,
+    ) -> Result<LoType, LoError> {
+        match &macro_param.param_type {
+            FnParamType::Self_ | FnParamType::SelfRef => {
+                let name_parts = &macro_def.macro_name.parts;
+                if name_parts.len() == 1 {
+                    return Err(LoError {
+                        message: format!("Cannot use self param in non-method macro"),
+                        loc: macro_param.loc.clone(),
+                    });
+                }
+
+                let self_type_name = name_parts[..name_parts.len() - 1].join("::");
+                let self_type =
+                    if let Some(generic_def) = self.get_generic_struct_def(&self_type_name) {
+                        // `Self` of a generic struct's macro is instantiated with the macro's type args
+                        let mut type_args = Vec::new();
+                        for type_def in type_scope {
+                            type_args.push(type_def.value.clone());
+                        }
+
+                        let struct_def =
+                            self.get_struct_instance(generic_def, type_args, &macro_def.loc)?;
+                        LoType::StructInstance {
+                            struct_name: struct_def.struct_name.clone(),
+                        }
+                    } else {
+                        self.get_type_or_err(&self_type_name, &macro_def.loc)?
+                    };
+
+                if let FnParamType::Self_ = macro_param.param_type {
+                    return Ok(self_type);
+                }
+
+                return Ok(LoType::Pointer {
+                    pointee: Box::new(self_type),
+                });
+            }
+            FnParamType::Type { expr } => self.build_type_in_scope(type_scope, &expr),
+        }
+    }
+
+    fn codegen_enum_value(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        enum_def: &LoEnumDef,
+        variant_index: usize,
+        payload: Option<&CodeExpr>,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let variant = &enum_def.variants[variant_index];
+
+        match payload {
+            Some(payload) => {
+                if variant.payload_type == LoType::Void {
+                    return Err(LoError {
+                        message: format!(
+                            "Enum variant {}::{} has no payload",
+                            enum_def.enum_name, variant.variant_name
+                        ),
+                        loc: loc.clone(),
+                    });
+                }
+
+                let payload_type = self.get_expr_type(ctx, payload)?;
+                if !self.is_type_compatible(&payload_type, &variant.payload_type) {
+                    return Err(LoError {
+                        message: format!(
+                            "Invalid payload type for {}::{}, expected: {}, got: {}",
+                            enum_def.enum_name,
+                            variant.variant_name,
+                            variant.payload_type,
+                            payload_type
+                        ),
+                        loc: payload.loc().clone(),
+                    });
+                }
+            }
+            None => {
+                if variant.payload_type != LoType::Void {
+                    return Err(LoError {
+                        message: format!(
+                            "Enum variant {}::{} requires a payload of type {}",
+                            enum_def.enum_name, variant.variant_name, variant.payload_type
+                        ),
+                        loc: loc.clone(),
+                    });
+                }
+            }
+        }
+
+        instrs.push(WasmInstr::I32Const {
+            value: variant.tag as i32,
+        });
+
+        // payloads of other variants are zeroed
+        for (other_variant, other_variant_index) in enum_def.variants.iter().zip(0..) {
+            if other_variant_index != variant_index {
+                self.codegen_default_value(instrs, &other_variant.payload_type);
+                continue;
+            }
+
+            if let Some(payload) = payload {
+                self.codegen(ctx, instrs, payload)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn codegen_bit_intrinsic(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        call: &LoCallSite,
+        receiver_type: &LoType,
+        instr: WasmInstr,
+        arg_count: usize,
+    ) -> Result<(), LoError> {
+        if let Some(receiver) = call.receiver {
+            self.codegen(ctx, instrs, receiver)?;
+        }
+
+        let mut arg_types = vec![receiver_type.clone()];
+        for arg in call.args {
+            arg_types.push(self.codegen_with_expected_type(
+                ctx,
+                instrs,
+                arg,
+                Some(receiver_type),
+            )?);
+        }
+
+        let expected_types = vec![receiver_type.clone(); arg_count + 1];
+        if arg_types != expected_types {
+            return Err(LoError {
+                message: format!(
+                    "Invalid function arguments for function {}: [{}], expected [{}]",
+                    call.name,
+                    ListDisplay(&arg_types),
+                    ListDisplay(&expected_types),
+                ),
+                loc: call.loc.clone(),
+            });
+        }
+
+        instrs.push(instr);
+
+        Ok(())
+    }
+
+    fn codegen_memory_intrinsic(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        call: &LoCallSite,
+        instr: WasmInstr,
+        input_types: &Vec<LoType>,
+    ) -> Result<(), LoError> {
+        let mut arg_types = Vec::new();
+        for arg in call.args {
+            arg_types.push(self.codegen_with_expected_type(
+                ctx,
+                instrs,
+                arg,
+                Some(&LoType::U32),
+            )?);
+        }
+
+        if arg_types != *input_types {
+            return Err(LoError {
+                message: format!(
+                    "Invalid function arguments for function {}: [{}], expected [{}]",
+                    call.name,
+                    ListDisplay(&arg_types),
+                    ListDisplay(input_types),
+                ),
+                loc: call.loc.clone(),
+            });
+        }
+
+        instrs.push(instr);
+
+        Ok(())
+    }
+
+    // branches to the arm of the variant whose tag is in the first component of the value,
+    // dense tags use a jump table and sparse tags are compared one by one
+    fn codegen_match_dispatch(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        enum_def: &LoEnumDef,
+        arm_indicies_by_variant: &Vec<Option<u32>>,
+        value_local_index: u32,
+        default_label_index: u32,
+    ) {
+        let min_tag = enum_def.variants.iter().map(|v| v.tag).min().unwrap_or(0);
+        let max_tag = enum_def.variants.iter().map(|v| v.tag).max().unwrap_or(0);
+
+        let tag_span = (max_tag - min_tag) as usize + 1;
+        if tag_span > enum_def.variants.len() * 2 {
+            for (variant, arm_index) in enum_def.variants.iter().zip(arm_indicies_by_variant) {
+                let Some(arm_index) = arm_index else {
+                    continue;
+                };
+
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: value_local_index,
+                });
+                instrs.push(WasmInstr::I32Const {
+                    value: variant.tag as i32,
+                });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_EQ,
+                });
+                instrs.push(WasmInstr::BranchIf {
+                    label_index: *arm_index,
+                });
+            }
+
+            instrs.push(WasmInstr::Branch {
+                label_index: default_label_index,
+            });
+            return;
+        }
+
+        let mut label_idx = vec![default_label_index; tag_span];
+        for (variant, arm_index) in enum_def.variants.iter().zip(arm_indicies_by_variant) {
+            label_idx[(variant.tag - min_tag) as usize] = arm_index.unwrap_or(default_label_index);
+        }
+
+        // tags below the smallest one wrap around and hit the default
+        instrs.push(WasmInstr::LocalGet {
+            local_index: value_local_index,
+        });
+        if min_tag != 0 {
+            instrs.push(WasmInstr::I32Const {
+                value: min_tag as i32,
+            });
+            instrs.push(WasmInstr::BinaryOp {
+                kind: WasmBinaryOpKind::I32_SUB,
+            });
+        }
+        instrs.push(WasmInstr::BranchIndirect {
+            label_idx,
+            default_label_index,
+        });
+    }
+
+    fn codegen_enum_name(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        enum_def: &LoEnumDef,
+        args: &Vec<CodeExpr>,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let enum_type = LoType::EnumInstance {
+            enum_name: enum_def.enum_name.clone(),
+        };
+
+        let mut arg_types = Vec::new();
+        for arg in args {
+            arg_types.push(self.get_expr_type(ctx, arg)?);
+        }
+        if arg_types != vec![enum_type.clone()] {
+            return Err(LoError {
+                message: format!(
+                    "Invalid function arguments for function {}::name: [{}], expected [{}]",
+                    enum_def.enum_name,
+                    ListDisplay(&arg_types),
+                    enum_type
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        if self.memory.is_none() {
+            return Err(LoError {
+                message: format!(
+                    "Cannot use {}::name with no memory defined",
+                    enum_def.enum_name
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        let table_ptr = self.get_or_append_enum_name_table(enum_def);
+
+        self.codegen(ctx, instrs, &args[0])?;
+        let value_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &enum_type,
+            false,
+        )?;
+        self.codegen_local_set(instrs, &enum_type, value_local_index);
+
+        // the table is searched for the entry with the value's tag,
+        // an empty name at the end of the table is used for unknown tags
+        let last_entry_ptr = table_ptr + enum_def.variants.len() as u32 * 12;
+
+        let address_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        instrs.push(WasmInstr::I32Const {
+            value: table_ptr as i32,
+        });
+        instrs.push(WasmInstr::LocalSet {
+            local_index: address_local_index,
+        });
+
+        {
+            instrs.push(WasmInstr::BlockStart {
+                block_kind: WasmBlockKind::Block,
+                block_type: WasmBlockType::NoOut,
+            });
+
+            {
+                instrs.push(WasmInstr::BlockStart {
+                    block_kind: WasmBlockKind::Loop,
+                    block_type: WasmBlockType::NoOut,
+                });
+
+                // stop at the last entry
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: address_local_index,
+                });
+                instrs.push(WasmInstr::I32Const {
+                    value: last_entry_ptr as i32,
+                });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_EQ,
+                });
+                instrs.push(WasmInstr::BranchIf { label_index: 1 });
+
+                // stop at the entry with a matching tag
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: address_local_index,
+                });
+                instrs.push(WasmInstr::Load {
+                    kind: WasmLoadKind::I32,
+                    align: 0,
+                    offset: 0,
+                });
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: value_local_index,
+                });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_EQ,
+                });
+                instrs.push(WasmInstr::BranchIf { label_index: 1 });
+
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: address_local_index,
+                });
+                instrs.push(WasmInstr::I32Const { value: 12 });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_ADD,
+                });
+                instrs.push(WasmInstr::LocalSet {
+                    local_index: address_local_index,
+                });
+
+                instrs.push(WasmInstr::Branch { label_index: 0 });
+
+                instrs.push(WasmInstr::BlockEnd);
+            }
+
+            instrs.push(WasmInstr::BlockEnd);
+        }
+
+        let name_type = LoType::Slice {
+            item_type: Box::new(LoType::U8),
+        };
+        self.codegen_load_from_local(instrs, &name_type, address_local_index, 4);
+
+        Ok(())
+    }
+
+    // each table entry is a variant's tag followed by its name as `[]u8`
+    fn get_or_append_enum_name_table(&self, enum_def: &LoEnumDef) -> u32 {
+        if let Some(table_ptr) = self.enum_name_tables.borrow().get(&enum_def.enum_name) {
+            return *table_ptr;
+        }
+
+        let mut table = Vec::new();
+        for variant in &enum_def.variants {
+            let name = &variant.variant_name;
+            let name_ptr = self.get_or_append_string(name.clone());
+            table.extend_from_slice(&(variant.tag as u32).to_le_bytes());
+            table.extend_from_slice(&name_ptr.to_le_bytes());
+            table.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        }
+
+        // the entry for unknown tags
+        let empty_name_ptr = self.get_or_append_string(String::new());
+        table.extend_from_slice(&0u32.to_le_bytes());
+        table.extend_from_slice(&empty_name_ptr.to_le_bytes());
+        table.extend_from_slice(&0u32.to_le_bytes());
+
+        let table_ptr = self.append_data(table);
+        self.enum_name_tables
+            .borrow_mut()
+            .insert(enum_def.enum_name.clone(), table_ptr);
+
+        table_ptr
+    }
+
+    fn codegen_result_constructor(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        constructor_name: &str,
+        args: &Vec<CodeExpr>,
+        loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let Some(LoType::Result { ok_type, err_type }) = ctx.fn_return_type.clone() else {
+            return Err(LoError {
+                message: format!("Cannot infer Result type from function's return type"),
+                loc: loc.clone(),
+            });
+        };
+
+        let is_ok = constructor_name == "Ok";
+        let value_type = if is_ok { &ok_type } else { &err_type };
+
+        let expected_args_count = if **value_type == LoType::Void { 0 } else { 1 };
+        if args.len() != expected_args_count {
+            return Err(LoError {
+                message: format!(
+                    "Invalid number of arguments for {constructor_name}, expected {expected_args_count}, got {}",
+                    args.len()
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        if !is_ok {
+            self.codegen_default_value(instrs, &ok_type);
+        }
+
+        if let Some(value) = args.first() {
+            let actual_type = self.get_expr_type(ctx, value)?;
+            if actual_type != **value_type {
+                return Err(LoError {
+                    message: format!(
+                        "Invalid {constructor_name} type: {actual_type}, expected: {value_type}"
+                    ),
+                    loc: value.loc().clone(),
+                });
+            }
+
+            self.codegen(ctx, instrs, value)?;
+        }
+
+        if is_ok {
+            self.codegen_default_value(instrs, &err_type);
+        }
+
+        Ok(())
+    }
+
+    fn codegen_bind_result(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        error_bind: Option<&String>,
+        ok_type: &LoType,
+        err_type: &LoType,
+        loc: &LoLocation,
+    ) -> Result<(VariableInfo, VariableInfo), LoError> {
+        let err_local_name = match error_bind {
+            Some(error_bind) if error_bind != "_" => error_bind.clone(),
+            _ => format!("%{}", ctx.last_local_index), // make sure it's not accessible
+        };
+        let err_local_index =
+            self.define_local(ctx, loc.clone(), err_local_name, err_type, false)?;
+        self.codegen_local_set(instrs, err_type, err_local_index);
+
+        let ok_local_name = format!("%{}", ctx.last_local_index);
+        let ok_local_index = self.define_local(ctx, loc.clone(), ok_local_name, ok_type, false)?;
+        self.codegen_local_set(instrs, ok_type, ok_local_index);
+
+        Ok((
+            VariableInfo::Local {
+                local_index: ok_local_index,
+                local_type: ok_type.clone(),
+            },
+            VariableInfo::Local {
+                local_index: err_local_index,
+                local_type: err_type.clone(),
+            },
+        ))
+    }
+
+    fn assert_fn_can_throw(
+        &self,
+        ctx: &LoExprContext,
+        error_type: &LoType,
+        throw_loc: &LoLocation,
+    ) -> Result<(), LoError> {
+        let fn_output = ctx.fn_return_type.clone().unwrap_or(LoType::Void);
+        let LoType::Result { err_type, .. } = &fn_output else {
+            return Err(LoError {
+                message: format!("Cannot throw {error_type}, function can only return {fn_output}"),
+                loc: throw_loc.clone(),
+            });
+        };
+        if *error_type != **err_type {
+            return Err(LoError {
+                message: format!("Invalid throw type, expected {err_type}, got {error_type}"),
+                loc: throw_loc.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn codegen_default_value(&self, instrs: &mut Vec<WasmInstr>, value_type: &LoType) {
+        let mut wasm_types = Vec::new();
+        self.lower_type(value_type, &mut wasm_types);
+
+        for wasm_type in wasm_types {
+            match wasm_type {
+                WasmType::I32 => instrs.push(WasmInstr::I32Const { value: 0 }),
+                WasmType::I64 => instrs.push(WasmInstr::I64Const { value: 0 }),
+                WasmType::F32 => instrs.push(WasmInstr::F32Const { value: 0.0 }),
+                WasmType::F64 => instrs.push(WasmInstr::F64Const { value: 0.0 }),
+                WasmType::FuncRef => unreachable!(),
+            }
+        }
+    }
+
+    fn define_local(
+        &self,
+        ctx: &mut LoExprContext,
+        loc: LoLocation,
+        local_name: String,
+        local_type: &LoType,
+        is_fn_param: bool,
+    ) -> Result<u32, LoError> {
+        for local in ctx.current_scope().locals.iter() {
+            if local.local_name == local_name && local.defined_in_this_scope {
+                let LoLocal { definition_loc, .. } = &ctx.locals[local.lo_local_index];
+
+                return Err(LoError {
+                    message: format!(
+                        "Cannot redefine local {}, previously defined at {}",
+                        local_name, definition_loc
+                    ),
+                    loc,
+                });
+            }
+        }
+
+        let local_index = ctx.last_local_index;
+        ctx.locals.push(LoLocal {
+            local_index,
+            local_type: local_type.clone(),
+            definition_loc: loc,
+            is_fn_param,
+        });
+        let lo_local_index = ctx.locals.len() - 1;
+        ctx.current_scope_mut().locals.push(LoScopedLocal {
+            local_name,
+            lo_local_index,
+            defined_in_this_scope: true,
+        });
+        ctx.last_local_index += self.count_wasm_type_components(local_type);
+
+        Ok(local_index)
+    }
+
+    fn codegen_compound_assignment(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        base_op: Option<InfixOpTag>,
+        op_loc: &LoLocation,
+        lhs: &CodeExpr,
+        rhs: &CodeExpr,
+    ) -> Result<(), LoError> {
+        let lhs_type = self.get_expr_type(ctx, lhs)?;
+        let rhs_type = self.get_expr_type_with_expected(ctx, rhs, Some(&lhs_type))?;
+
+        if !self.is_type_compatible(&rhs_type, &lhs_type) {
+            return Err(LoError {
+                message: format!(
+                    "Unexpected value for assignment: {}, expected {}",
+                    rhs_type, lhs_type
+                ),
+                loc: op_loc.clone(),
+            });
+        }
+
+        if let CodeExpr::PrefixOp(PrefixOpExpr {
+            op_tag: PrefixOpTag::Dereference,
+            expr: addr_expr,
+            loc: _,
+        }) = lhs
+        {
+            let pointer_type = self.get_expr_type(ctx, addr_expr)?;
+            let (LoType::Pointer {
+                pointee: pointee_type,
+            }
+            | LoType::SequencePointer {
+                pointee: pointee_type,
+            }) = pointer_type
+            else {
+                return Err(LoError {
+                    message: format!("Cannot use {pointer_type} as an address, pointer expected"),
+                    loc: addr_expr.loc().clone(),
+                });
+            };
+
+            let component_count = self.count_wasm_type_components(&pointee_type);
+            if component_count == 1 {
+                self.codegen(ctx, instrs, addr_expr)?;
+            }
+
+            if let Some(base_op) = base_op {
+                self.codegen(ctx, instrs, lhs)?;
+                self.codegen_with_expected_type(ctx, instrs, rhs, Some(&lhs_type))?;
+
+                let kind = self.get_binary_op_kind(&base_op, &lhs_type, op_loc)?;
+                instrs.push(WasmInstr::BinaryOp { kind });
+            } else {
+                self.codegen_with_expected_type(ctx, instrs, rhs, Some(&lhs_type))?;
+            }
+
+            if component_count != 1 {
+                let tmp_local_index = self.define_local(
+                    ctx,
+                    rhs.loc().clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &rhs_type,
+                    false,
+                )?;
+                self.codegen_local_set(instrs, &rhs_type, tmp_local_index);
+
+                let address_local_index = self.define_local(
+                    ctx,
+                    addr_expr.loc().clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &LoType::U32,
+                    false,
+                )?;
+                self.codegen(ctx, instrs, addr_expr)?;
+                instrs.push(WasmInstr::LocalSet {
+                    local_index: address_local_index,
+                });
+
+                self.codegen_store_from_local(
+                    instrs,
+                    &pointee_type,
+                    address_local_index,
+                    tmp_local_index,
+                    0,
+                );
+            } else {
+                self.codegen_load_or_store(instrs, &pointee_type, 0, true);
+            }
+
+            return Ok(());
+        }
+
+        if let CodeExpr::Ident(IdentExpr {
+            repr,
+            parts: _,
+            loc,
+        }) = lhs
+        {
+            if let Some(local_index) = ctx.get_local(&repr).map(|l| l.local_index) {
+                if let Some(base_op) = base_op {
+                    self.codegen(ctx, instrs, lhs)?;
+                    self.codegen_with_expected_type(ctx, instrs, rhs, Some(&lhs_type))?;
+
+                    let kind = self.get_binary_op_kind(&base_op, &lhs_type, op_loc)?;
+                    instrs.push(WasmInstr::BinaryOp { kind });
+                } else {
+                    self.codegen_with_expected_type(ctx, instrs, rhs, Some(&lhs_type))?;
+                }
+
+                for i in (0..self.count_wasm_type_components(&rhs_type)).rev() {
+                    instrs.push(WasmInstr::LocalSet {
+                        local_index: local_index + i,
+                    });
+                }
+
+                return Ok(());
+            }
+
+            if let Some(var) = self.var_from_capture(ctx, &repr) {
+                return self.codegen_var_update(ctx, instrs, &var, base_op, op_loc, rhs);
+            }
+
+            if let Some(global) = self.get_global(&repr) {
+                if let Some(base_op) = base_op {
+                    self.codegen(ctx, instrs, lhs)?;
+                    self.codegen_with_expected_type(ctx, instrs, rhs, Some(&lhs_type))?;
+
+                    let kind = self.get_binary_op_kind(&base_op, &lhs_type, op_loc)?;
+                    instrs.push(WasmInstr::BinaryOp { kind });
+                } else {
+                    self.codegen_with_expected_type(ctx, instrs, rhs, Some(&lhs_type))?;
+                }
+
+                for i in 0..self.count_wasm_type_components(&rhs_type) {
+                    instrs.push(WasmInstr::GlobalSet {
+                        global_index: global.global_index + i,
+                    });
+                }
+
+                return Ok(());
+            }
+
+            return Err(LoError {
+                message: format!("Unknown variable: {repr}"),
+                loc: loc.clone(),
+            });
+        }
+
+        if let CodeExpr::FieldAccess(FieldAccessExpr {
+            lhs,
+            field_name,
+            loc,
+        }) = lhs
+        {
+            let var = self.var_from_field_assignment_target(ctx, instrs, lhs, field_name, loc)?;
+            return self.codegen_var_update(ctx, instrs, &var, base_op, op_loc, rhs);
+        }
+
+        if let CodeExpr::Index(IndexExpr { lhs, index, loc }) = lhs {
+            let var = self.var_from_index(ctx, instrs, lhs, index, loc)?;
+            return self.codegen_var_update(ctx, instrs, &var, base_op, op_loc, rhs);
+        }
+
+        return Err(LoError {
+            message: format!("Invalid assignment target"),
+            loc: lhs.loc().clone(),
+        });
+    }
+
+    fn codegen_var_update(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        var: &VariableInfo,
+        base_op: Option<InfixOpTag>,
+        op_loc: &LoLocation,
+        rhs: &CodeExpr,
+    ) -> Result<(), LoError> {
+        let Some(base_op) = base_op else {
+            return self.codegen_var_set(ctx, instrs, var, rhs);
+        };
+        let lhs_type = var.get_type_ref();
+
+        if let VariableInfo::Stored {
+            address_local_index,
+            field_offset: _,
+            value_type: _,
+        } = var
+        {
+            instrs.push(WasmInstr::LocalGet {
+                local_index: *address_local_index,
+            });
+        }
+
+        self.codegen_var_get(instrs, var);
+        self.codegen_with_expected_type(ctx, instrs, rhs, Some(lhs_type))?;
+
+        let kind = self.get_binary_op_kind(&base_op, lhs_type, op_loc)?;
+        instrs.push(WasmInstr::BinaryOp { kind });
+
+        match var {
+            VariableInfo::Local {
+                local_index,
+                local_type,
+            } => self.codegen_local_set(instrs, local_type, *local_index),
+            VariableInfo::Stored {
+                address_local_index: _,
+                field_offset,
+                value_type,
+            } => self.codegen_load_or_store(instrs, value_type, *field_offset, true),
+            VariableInfo::LocalItem {
+                item_local_indices: _,
+                index_local_index: _,
+                value_type,
+            } => {
+                let tmp_local_index = self.define_local(
+                    ctx,
+                    op_loc.clone(),
+                    format!("%{}", ctx.last_local_index),
+                    value_type,
+                    false,
+                )?;
+                self.codegen_local_set(instrs, value_type, tmp_local_index);
+
+                self.codegen_local_item_set(instrs, var, tmp_local_index);
+            }
+            VariableInfo::Global { .. } => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    fn codegen_load_or_store(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        pointee_type: &LoType,
+        offset: u32,
+        is_store: bool,
+    ) {
+        match pointee_type {
+            LoType::Never | LoType::Void => {}
+            LoType::Bool | LoType::U8 => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::I32_8,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::I32U8,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::I8 => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::I32_8,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::I32I8,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::U16 => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::I32_16,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::I32U16,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::I16 => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::I32_16,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::I32I16,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::U32
+            | LoType::I32
+            | LoType::Pointer { pointee: _ }
+            | LoType::SequencePointer { pointee: _ }
+            | LoType::Fn {
+                inputs: _,
+                output: _,
+            } => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::I32,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::I32,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::U64 | LoType::I64 => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::I64,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::I64,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::F32 => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::F32,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::F32,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::F64 => {
+                if is_store {
+                    instrs.push(WasmInstr::Store {
+                        kind: WasmStoreKind::F64,
+                        align: 0,
+                        offset,
+                    })
+                } else {
+                    instrs.push(WasmInstr::Load {
+                        kind: WasmLoadKind::F64,
+                        align: 0,
+                        offset,
+                    })
+                }
+            }
+            LoType::StructInstance { struct_name } => {
+                let struct_def = self.get_struct_def(struct_name).unwrap();
+
+                for struct_field in struct_def.fields.iter().rev() {
+                    self.codegen_load_or_store(
+                        instrs,
+                        &struct_field.field_type,
+                        offset + struct_field.byte_offset,
+                        is_store,
+                    );
+                }
+            }
+            // enums with payloads are multi-component and go through
+            // `codegen_load_from_local` / `codegen_store_from_local`
+            LoType::EnumInstance { enum_name } => {
+                let enum_def = self.get_enum_def(enum_name).unwrap();
+
+                self.codegen_load_or_store(instrs, &enum_def.tag_type, offset, is_store);
+            }
+            LoType::Array { item_type, length } => {
+                let item_layout = &mut LoTypeLayout::default();
+                self.get_type_layout(item_type, item_layout);
+
+                for item_index in (0..*length).rev() {
+                    self.codegen_load_or_store(
+                        instrs,
+                        item_type,
+                        offset + item_index * item_layout.byte_length,
+                        is_store,
+                    );
+                }
+            }
+            LoType::Slice { item_type: _ }
+            | LoType::Closure {
+                inputs: _,
+                output: _,
+            } => {
+                self.codegen_load_or_store(instrs, &LoType::U32, offset + 4, is_store);
+                self.codegen_load_or_store(instrs, &LoType::U32, offset, is_store);
+            }
+            LoType::Result { ok_type, err_type } => {
+                let ok_layout = &mut LoTypeLayout::default();
+                self.get_type_layout(ok_type, ok_layout);
+
+                self.codegen_load_or_store(
+                    instrs,
+                    err_type,
+                    offset + ok_layout.byte_length,
+                    is_store,
+                );
+                self.codegen_load_or_store(instrs, ok_type, offset, is_store);
+            }
+            LoType::Tuple { item_types } => {
+                let mut item_offsets = Vec::new();
+                let layout = &mut LoTypeLayout::default();
+                for item_type in item_types {
+                    item_offsets.push(layout.byte_length);
+                    self.get_type_layout(item_type, layout);
+                }
+
+                for (item_type, item_offset) in item_types.iter().zip(item_offsets).rev() {
+                    self.codegen_load_or_store(instrs, item_type, offset + item_offset, is_store);
+                }
+            }
+        }
+    }
+
+    fn codegen_code_block(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        exprs: &Vec<CodeExpr>,
+    ) {
+        for expr in exprs {
+            if let Err(err) = self.codegen(ctx, instrs, expr) {
+                self.errors.borrow_mut().report(err);
+            }
+        }
+    }
+
+    fn get_expr_type(&self, ctx: &LoExprContext, expr: &CodeExpr) -> Result<LoType, LoError> {
+        match expr {
+            CodeExpr::BoolLiteral(_) => Ok(LoType::Bool),
+            CodeExpr::CharLiteral(_) => Ok(LoType::U8),
+            CodeExpr::IntLiteral(IntLiteralExpr {
+                repr: _,
+                value: _,
+                tag,
+                loc,
+            }) => match tag.as_deref() {
+                Some("u8") => Ok(LoType::U8),
+                Some("i8") => Ok(LoType::I8),
+                Some("u16") => Ok(LoType::U16),
+                Some("i16") => Ok(LoType::I16),
+                Some("u32") => Ok(LoType::U32),
+                Some("i32") => Ok(LoType::I32),
+                Some("f32") => Ok(LoType::F32),
+                Some("u64") => Ok(LoType::U64),
+                Some("i64") => Ok(LoType::I64),
+                Some("f64") => Ok(LoType::F64),
+                Some(unknown_tag) => Err(LoError {
+                    message: format!("Unknown int literal tag: {}", unknown_tag),
+                    loc: loc.clone(),
+                }),
+                None => Ok(LoType::U32),
+            },
+            CodeExpr::FloatLiteral(FloatLiteralExpr {
+                repr: _,
+                value: _,
+                tag,
+                loc,
+            }) => match tag.as_deref() {
+                Some("f32") => Ok(LoType::F32),
+                Some("f64") | None => Ok(LoType::F64),
+                Some(unknown_tag) => Err(LoError {
+                    message: format!("Unknown float literal tag: {}", unknown_tag),
+                    loc: loc.clone(),
+                }),
+            },
+            CodeExpr::StringLiteral(StringLiteralExpr {
+                repr: _,
+                value: _,
+                zero_terminated,
+                loc: _,
+            }) => {
+                if *zero_terminated {
+                    return Ok(LoType::SequencePointer {
+                        pointee: Box::new(LoType::U8),
+                    });
+                }
+
+                Ok(LoType::Slice {
+                    item_type: Box::new(LoType::U8),
+                })
+            }
+            CodeExpr::StructLiteral(StructLiteralExpr {
+                struct_name,
+                type_args,
+                fields: _,
+                loc,
+            }) => {
+                let struct_def =
+                    self.get_struct_literal_def(&ctx.type_scope, struct_name, type_args, loc)?;
+
+                return Ok(LoType::StructInstance {
+                    struct_name: struct_def.struct_name.clone(),
+                });
+            }
+            CodeExpr::TupleLiteral(literal) => self.get_tuple_literal_type(ctx, literal, None),
+            CodeExpr::ArrayLiteral(ArrayLiteralExpr {
+                item_type,
+                items,
+                loc: _,
+            }) => {
+                let item_type = self.build_type_in_scope(&ctx.type_scope, item_type)?;
+
+                return Ok(LoType::Pointer {
+                    pointee: Box::new(LoType::Array {
+                        item_type: Box::new(item_type),
+                        length: items.len() as u32,
+                    }),
+                });
+            }
+            CodeExpr::Ident(IdentExpr {
+                repr,
+                parts: _,
+                loc,
+            }) => {
+                if let Some((enum_def, _)) = self.get_enum_variant(repr) {
+                    return Ok(LoType::EnumInstance {
+                        enum_name: enum_def.enum_name.clone(),
+                    });
+                }
+
+                if let Some(const_def) = self.get_const_def(repr) {
+                    let const_ctx = &mut LoExprContext::default();
+                    return self.get_expr_type(const_ctx, &const_def.const_value);
+                }
+
+                let var = self.var_from_ident(ctx, &repr, loc)?;
+                Ok(var.get_type())
+            }
+            CodeExpr::InfixOp(InfixOpExpr {
+                op_tag,
+                op_loc: _,
+                lhs,
+                rhs,
+                loc: _,
+            }) => match op_tag {
+                InfixOpTag::Equal
+                | InfixOpTag::NotEqual
+                | InfixOpTag::Less
+                | InfixOpTag::Greater
+                | InfixOpTag::LessEqual
+                | InfixOpTag::GreaterEqual
+                | InfixOpTag::And
+                | InfixOpTag::Or => Ok(LoType::Bool),
+
+                InfixOpTag::Add
+                | InfixOpTag::Sub
+                | InfixOpTag::Mul
+                | InfixOpTag::Div
+                | InfixOpTag::Mod
+                | InfixOpTag::BitAnd
+                | InfixOpTag::BitOr
+                | InfixOpTag::BitXor
+                | InfixOpTag::ShiftLeft
+                | InfixOpTag::ShiftRight => Ok(self.get_operand_types(ctx, lhs, rhs)?.0),
+
+                InfixOpTag::AddAssign
+                | InfixOpTag::SubAssign
+                | InfixOpTag::MulAssign
+                | InfixOpTag::DivAssign
+                | InfixOpTag::ModAssign
+                | InfixOpTag::BitAndAssign
+                | InfixOpTag::BitOrAssign
+                | InfixOpTag::BitXorAssign
+                | InfixOpTag::ShiftLeftAssign
+                | InfixOpTag::ShiftRightAssign => Ok(LoType::Void),
+
+                // have their own CodeExpr variants
+                InfixOpTag::Cast
+                | InfixOpTag::Assign
+                | InfixOpTag::FieldAccess
+                | InfixOpTag::Index
+                | InfixOpTag::Catch
+                | InfixOpTag::ErrorPropagation => unreachable!(),
+            },
+            CodeExpr::PrefixOp(PrefixOpExpr { op_tag, expr, loc }) => match op_tag {
+                PrefixOpTag::Not => Ok(LoType::Bool),
+                PrefixOpTag::BitNot => self.get_expr_type(ctx, expr),
+                PrefixOpTag::Dereference => {
+                    let expr_type = self.get_expr_type(ctx, expr)?;
+                    let (LoType::Pointer { pointee } | LoType::SequencePointer { pointee }) =
+                        expr_type
+                    else {
+                        return Err(LoError {
+                            message: format!("Cannot dereference expr of type {}", expr_type),
+                            loc: loc.clone(),
+                        });
+                    };
+                    Ok(*pointee)
+                }
+                PrefixOpTag::Positive | PrefixOpTag::Negative => {
+                    // integer literals become signed, as in v1
+                    if let CodeExpr::IntLiteral(IntLiteralExpr {
+                        repr: _,
+                        value: _,
+                        tag,
+                        loc: _,
+                    }) = expr.as_ref()
+                    {
+                        return match tag.as_deref() {
+                            Some("u32") | Some("i32") | None => Ok(LoType::I32),
+                            _ => Ok(LoType::I64),
+                        };
+                    }
+
+                    self.get_expr_type(ctx, expr)
+                }
+            },
+            CodeExpr::Cast(CastExpr {
+                expr: _,
+                casted_to,
+                loc: _,
+            }) => self.build_type_in_scope(&ctx.type_scope, casted_to),
+            CodeExpr::FieldAccess(FieldAccessExpr {
+                lhs,
+                field_name,
+                loc: _,
+            }) => {
+                if self.is_temporary_value(ctx, lhs) {
+                    // only the field type is needed so the local index doesn't matter
+                    let base = VariableInfo::Local {
+                        local_index: 0,
+                        local_type: self.get_expr_type(ctx, lhs)?,
+                    };
+                    let var = self.var_from_field(base, field_name)?;
+                    return Ok(var.get_type());
+                }
+
+                let var = self.var_from_field_access(ctx, lhs, field_name)?;
+                Ok(var.get_type())
+            }
+            CodeExpr::Index(IndexExpr { lhs, index: _, loc }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let Some((item_type, _)) = self.get_indexed_item_type(&lhs_type) else {
+                    return Err(LoError {
+                        message: format!("Cannot index value of type {lhs_type}"),
+                        loc: loc.clone(),
+                    });
+                };
+
+                Ok(item_type.clone())
+            }
+            CodeExpr::Slice(SliceExpr {
+                lhs,
+                start: _,
+                end,
+                loc,
+            }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let item_type = self.get_sliced_item_type(&lhs_type, end.is_some(), loc)?;
+
+                Ok(LoType::Slice {
+                    item_type: Box::new(item_type),
+                })
+            }
+            CodeExpr::FnCall(FnCallExpr {
+                fn_name,
+                args: _,
+                loc,
+            }) => {
+                if fn_name.repr == "Ok" || fn_name.repr == "Err" {
+                    let Some(LoType::Result { .. }) = &ctx.fn_return_type else {
+                        return Err(LoError {
+                            message: format!(
+                                "Cannot infer Result type from function's return type"
+                            ),
+                            loc: loc.clone(),
+                        });
+                    };
+
+                    return Ok(ctx.fn_return_type.clone().unwrap());
+                }
+
+                if let Some((enum_def, _)) = self.get_enum_variant(&fn_name.repr) {
+                    return Ok(LoType::EnumInstance {
+                        enum_name: enum_def.enum_name.clone(),
+                    });
+                }
+
+                if let Some(_) = self.get_enum_of_name_fn(&fn_name.repr) {
+                    return Ok(LoType::Slice {
+                        item_type: Box::new(LoType::U8),
+                    });
+                }
+
+                if let Some(callee) = self.var_from_callable_ident(ctx, fn_name) {
+                    let (_, output) = callee.get_type_ref().get_call_signature().unwrap();
+                    return Ok(output.clone());
+                }
+
+                if let Some((_, _, output)) = self.get_memory_intrinsic(&fn_name.repr) {
+                    return Ok(output);
+                }
+
+                let Some((fn_info, _)) = self.get_fn_info(&fn_name.repr) else {
+                    return Err(LoError {
+                        message: format!("Unknown function: {}", fn_name.repr),
+                        loc: loc.clone(),
+                    });
+                };
+
+                Ok(fn_info.fn_type.output.clone())
+            }
+            CodeExpr::MethodCall(MethodCallExpr {
+                lhs,
+                field_name,
+                args: _,
+                loc,
+            }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let fn_name = self.get_fn_name_from_method(&lhs_type, &field_name.repr);
+
+                let Some((fn_info, _)) = self.get_fn_info(&fn_name) else {
+                    if let Some(callee) = self.var_from_callable_field(ctx, lhs, field_name) {
+                        let (_, output) = callee.get_type_ref().get_call_signature().unwrap();
+                        return Ok(output.clone());
+                    }
+
+                    if let Some(_) = self.get_bit_intrinsic(&lhs_type, &field_name.repr) {
+                        return Ok(lhs_type);
+                    }
+
+                    if let Some(instance) = self.get_generic_method_instance(&fn_name, loc)? {
+                        return Ok(instance.fn_type.output);
+                    }
+
+                    return Err(LoError {
+                        message: format!("Unknown function: {}", fn_name),
+                        loc: loc.clone(),
+                    });
+                };
+
+                Ok(fn_info.fn_type.output.clone())
+            }
+            CodeExpr::MacroFnCall(MacroFnCallExpr {
+                fn_name,
+                type_args,
+                args: _,
+                loc,
+            }) => {
+                let macro_def = self.get_macro_def_or_err(&fn_name.repr, loc)?;
+                let type_scope =
+                    self.build_macro_type_scope(ctx, macro_def, None, type_args, loc)?;
+                self.get_macro_return_type(&type_scope, macro_def)
+            }
+            CodeExpr::MacroMethodCall(MacroMethodCallExpr {
+                lhs,
+                field_name,
+                type_args,
+                args: _,
+                loc,
+            }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let macro_name = self.get_fn_name_from_method(&lhs_type, &field_name.repr);
+
+                let macro_def = self.get_macro_def_or_err(&macro_name, loc)?;
+                let type_scope =
+                    self.build_macro_type_scope(ctx, macro_def, Some(&lhs_type), type_args, loc)?;
+                self.get_macro_return_type(&type_scope, macro_def)
+            }
+            CodeExpr::Catch(CatchExpr {
+                lhs,
+                error_bind: _,
+                catch_body: _,
+                loc,
+            }) => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+                let LoType::Result {
+                    ok_type,
+                    err_type: _,
+                } = lhs_type
+                else {
+                    return Err(LoError {
+                        message: format!(
+                            "Trying to catch an error from the expression of type: {lhs_type}"
+                        ),
+                        loc: loc.clone(),
+                    });
+                };
+
+                Ok(*ok_type)
+            }
+            CodeExpr::PropagateError(PropagateErrorExpr { expr, loc }) => {
+                let expr_type = self.get_expr_type(ctx, expr)?;
+                let LoType::Result {
+                    ok_type,
+                    err_type: _,
+                } = expr_type
+                else {
+                    return Err(LoError {
+                        message: format!(
+                            "Trying to propagate an error from the expression of type: {expr_type}"
+                        ),
+                        loc: loc.clone(),
+                    });
+                };
+
+                Ok(*ok_type)
+            }
+            CodeExpr::Dbg(dbg) => self.get_expr_type(ctx, &self.get_dbg_string_literal(dbg)),
+            CodeExpr::Sizeof(_) => Ok(LoType::U32),
+            CodeExpr::GetDataSize(_) => Ok(LoType::U32),
+            CodeExpr::Let(_) | CodeExpr::LetTuple(_) => Ok(LoType::Void),
+            CodeExpr::Closure(closure) => {
+                let (_, closure_type) = self.build_closure_ctx(ctx, closure, None)?;
+                Ok(closure_type)
+            }
+            CodeExpr::FnRef(FnRefExpr { fn_name, loc }) => {
+                // not using get_fn_info as wasm fn indicies are not yet resolved for globals
+                let Some(fn_info) = self
+                    .lo_functions
+                    .iter()
+                    .find(|fn_info| fn_info.fn_name == fn_name.repr)
+                else {
+                    return Err(LoError {
+                        message: format!("Unknown function: {}", fn_name.repr),
+                        loc: loc.clone(),
+                    });
+                };
+
+                Ok(LoType::Fn {
+                    inputs: fn_info.fn_type.inputs.clone(),
+                    output: Box::new(fn_info.fn_type.output.clone()),
+                })
+            }
+            CodeExpr::Assign(_) => Ok(LoType::Void),
+            CodeExpr::Defer(_) => Ok(LoType::Void),
+            CodeExpr::If(_) => Ok(LoType::Void),
+            CodeExpr::Match(_) => Ok(LoType::Void),
+            CodeExpr::Loop(LoopExpr {
+                label,
+                body,
+                loc: _,
+            }) => self.get_loop_value_type(ctx, label, body),
+            CodeExpr::ForLoop(_) => Ok(LoType::Void),
+            CodeExpr::WhileLoop(_) => Ok(LoType::Void),
+            CodeExpr::Break(_) => Ok(LoType::Never),
+            CodeExpr::Continue(_) => Ok(LoType::Never),
+            CodeExpr::Return(_) => Ok(LoType::Never),
+            CodeExpr::Unreachable(_) => Ok(LoType::Never),
+            CodeExpr::Paren(ParenExpr { expr, loc: _ }) => self.get_expr_type(ctx, expr),
+        }
+    }
+
+    fn var_from_ident(
+        &self,
+        ctx: &LoExprContext,
+        ident: &str,
+        loc: &LoLocation,
+    ) -> Result<VariableInfo, LoError> {
+        if let Some(local) = ctx.get_local(ident) {
+            return Ok(VariableInfo::Local {
+                local_index: local.local_index,
+                local_type: local.local_type.clone(),
+            });
+        };
+
+        if let Some(var) = self.var_from_capture(ctx, ident) {
+            return Ok(var);
+        }
+
+        if let Some(global) = self.get_global(ident) {
+            return Ok(VariableInfo::Global {
+                global_index: global.global_index,
+                global_type: global.global_type.clone(),
+            });
+        }
+
+        return Err(LoError {
+            message: format!("Unknown variable: {ident}"),
+            loc: loc.clone(),
+        });
+    }
+
+    // variables holding function pointers or closures shadow functions of the same name
+    fn var_from_callable_ident(
+        &self,
+        ctx: &LoExprContext,
+        ident: &IdentExpr,
+    ) -> Option<VariableInfo> {
+        let var = self.var_from_ident(ctx, &ident.repr, &ident.loc).ok()?;
+        var.get_type_ref().get_call_signature()?;
+
+        Some(var)
+    }
+
+    fn var_from_callable_field(
+        &self,
+        ctx: &LoExprContext,
+        lhs: &CodeExpr,
+        field_name: &IdentExpr,
+    ) -> Option<VariableInfo> {
+        let var = self.var_from_field_access(ctx, lhs, field_name).ok()?;
+        var.get_type_ref().get_call_signature()?;
+
+        Some(var)
+    }
+
+    fn var_from_capture(&self, ctx: &LoExprContext, local_name: &str) -> Option<VariableInfo> {
+        let closure_env = ctx.closure_env.as_ref()?;
+        let mut captures = closure_env.captures.borrow_mut();
+
+        let capture_index = match captures.iter().position(|c| c.local_name == local_name) {
+            Some(capture_index) => capture_index,
+            None => {
+                let local_type =
+                    self.get_capturable_local_type(&closure_env.enclosing_ctx, local_name)?;
+
+                let mut env_offset = 0;
+                if let Some(last_capture) = captures.last() {
+                    let layout = &mut LoTypeLayout::default();
+                    self.get_type_layout(&last_capture.local_type, layout);
+                    env_offset = last_capture.env_offset + layout.byte_length;
+                }
+
+                captures.push(LoCapture {
+                    local_name: String::from(local_name),
+                    local_type,
+                    env_offset,
+                });
+                captures.len() - 1
+            }
+        };
+
+        let capture = &captures[capture_index];
+        Some(VariableInfo::Stored {
+            address_local_index: closure_env.env_local_index,
+            field_offset: capture.env_offset,
+            value_type: capture.local_type.clone(),
+        })
+    }
+
+    // nested closures capture through every enclosing closure
+    fn get_capturable_local_type(&self, ctx: &LoExprContext, local_name: &str) -> Option<LoType> {
+        if let Some(local) = ctx.get_local(local_name) {
+            return Some(local.local_type.clone());
+        }
+
+        let closure_env = ctx.closure_env.as_ref()?;
+        for capture in closure_env.captures.borrow().iter() {
+            if capture.local_name == local_name {
+                return Some(capture.local_type.clone());
+            }
+        }
+
+        self.get_capturable_local_type(&closure_env.enclosing_ctx, local_name)
+    }
+
+    fn var_from_field_access(
+        &self,
+        ctx: &LoExprContext,
+        lhs: &CodeExpr,
+        field_name: &IdentExpr,
+    ) -> Result<VariableInfo, LoError> {
+        let base = match lhs {
+            CodeExpr::Ident(ident) => match ctx.get_local(&ident.repr) {
+                Some(local) => VariableInfo::Local {
+                    local_index: local.local_index,
+                    local_type: local.local_type.clone(),
+                },
+                None => {
+                    let Some(capture) = self.var_from_capture(ctx, &ident.repr) else {
+                        return Err(LoError {
+                            message: format!("Unknown local {}", ident.repr),
+                            loc: lhs.loc().clone(),
+                        });
+                    };
+                    capture
+                }
+            },
+            // nested fields resolve to a slice of the outer variable
+            CodeExpr::FieldAccess(FieldAccessExpr {
+                lhs,
+                field_name,
+                loc: _,
+            }) => self.var_from_field_access(ctx, lhs, field_name)?,
+            _ => {
+                return Err(LoError {
+                    message: format!(
+                        "Cannot access struct field '{}' lhs is not a struct local",
+                        field_name.repr
+                    ),
+                    loc: lhs.loc().clone(),
+                });
+            }
+        };
+
+        self.var_from_field(base, field_name)
+    }
+
+    // values that are not rooted in a local, like `make_point().x` or `GLOBAL_PTR.x`
+    fn is_temporary_value(&self, ctx: &LoExprContext, expr: &CodeExpr) -> bool {
+        match expr {
+            CodeExpr::Ident(ident) => self.get_capturable_local_type(ctx, &ident.repr).is_none(),
+            CodeExpr::FieldAccess(FieldAccessExpr {
+                lhs,
+                field_name: _,
+                loc: _,
+            }) => self.is_temporary_value(ctx, lhs),
+            _ => true,
+        }
+    }
+
+    // fields of temporary values are accessed through a local copy of the root value,
+    // so writes through a pointer still reach memory
+    fn var_from_field_access_or_copy(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        lhs: &CodeExpr,
+        field_name: &IdentExpr,
+        loc: &LoLocation,
+    ) -> Result<VariableInfo, LoError> {
+        if !self.is_temporary_value(ctx, lhs) {
+            return self.var_from_field_access(ctx, lhs, field_name);
+        }
+
+        let base = match lhs {
+            CodeExpr::FieldAccess(FieldAccessExpr {
+                lhs,
+                field_name,
+                loc,
+            }) => self.var_from_field_access_or_copy(ctx, instrs, lhs, field_name, loc)?,
+            // items are picked in place, through a pointer or from the array local itself
+            CodeExpr::Index(IndexExpr { lhs, index, loc }) => {
+                self.var_from_index(ctx, instrs, lhs, index, loc)?
+            }
+            _ => {
+                let lhs_type = self.get_expr_type(ctx, lhs)?;
+
+                self.codegen(ctx, instrs, lhs)?;
+                let local_index = self.define_local(
+                    ctx,
+                    loc.clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &lhs_type,
+                    false,
+                )?;
+                self.codegen_local_set(instrs, &lhs_type, local_index);
+
+                VariableInfo::Local {
+                    local_index,
+                    local_type: lhs_type,
+                }
+            }
+        };
+
+        self.var_from_field(base, field_name)
+    }
+
+    // fields of temporary values can only be assigned when they are reached through a pointer,
+    // otherwise the write would go to a copy and be lost
+    fn var_from_field_assignment_target(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        lhs: &CodeExpr,
+        field_name: &IdentExpr,
+        loc: &LoLocation,
+    ) -> Result<VariableInfo, LoError> {
+        let var = self.var_from_field_access_or_copy(ctx, instrs, lhs, field_name, loc)?;
+        if let VariableInfo::Stored { .. } = var {
+            return Ok(var);
+        }
+
+        if !self.is_local_place(ctx, lhs) {
+            return Err(LoError {
+                message: format!(
+                    "Cannot assign to field '{}' of a temporary value",
+                    field_name.repr
+                ),
+                loc: loc.clone(),
+            });
+        }
+
+        Ok(var)
+    }
+
+    // locals and their fields and items, which are updated in place
+    fn is_local_place(&self, ctx: &LoExprContext, expr: &CodeExpr) -> bool {
+        match expr {
+            CodeExpr::Ident(ident) => self.get_capturable_local_type(ctx, &ident.repr).is_some(),
+            CodeExpr::FieldAccess(FieldAccessExpr {
+                lhs,
+                field_name: _,
+                loc: _,
+            }) => self.is_local_place(ctx, lhs),
+            CodeExpr::Index(IndexExpr {
+                lhs,
+                index: _,
+                loc: _,
+            }) => self.is_local_place(ctx, lhs),
+            _ => false,
+        }
+    }
+
+    fn var_from_field(
+        &self,
+        base: VariableInfo,
+        field_name: &IdentExpr,
+    ) -> Result<VariableInfo, LoError> {
+        match base {
+            VariableInfo::Local {
+                local_index,
+                local_type,
+            } => {
+                if let LoType::Pointer { pointee } = &local_type {
+                    if let Some((_, byte_offset, field_type)) =
+                        self.get_field(pointee, field_name)?
+                    {
+                        return Ok(VariableInfo::Stored {
+                            address_local_index: local_index,
+                            field_offset: byte_offset,
+                            value_type: field_type,
+                        });
+                    }
+                }
+
+                let Some((field_index, _, field_type)) = self.get_field(&local_type, field_name)?
+                else {
+                    return Err(LoError {
+                        message: format!(
+                            "Cannot get field '{}' on non struct: {local_type}",
+                            field_name.repr
+                        ),
+                        loc: field_name.loc.clone(),
+                    });
+                };
+
+                Ok(VariableInfo::Local {
+                    local_index: local_index + field_index,
+                    local_type: field_type,
+                })
+            }
+            VariableInfo::Stored {
+                address_local_index,
+                field_offset,
+                value_type,
+            } => {
+                let Some((_, byte_offset, field_type)) = self.get_field(&value_type, field_name)?
+                else {
+                    return Err(LoError {
+                        message: format!(
+                            "Cannot get field '{}' on stored {value_type}, only structs, slices and tuples are supported",
+                            field_name.repr
+                        ),
+                        loc: field_name.loc.clone(),
+                    });
+                };
+
+                Ok(VariableInfo::Stored {
+                    address_local_index,
+                    field_offset: field_offset + byte_offset,
+                    value_type: field_type,
+                })
+            }
+            VariableInfo::LocalItem {
+                item_local_indices,
+                index_local_index,
+                value_type,
+            } => {
+                let Some((field_index, _, field_type)) = self.get_field(&value_type, field_name)?
+                else {
+                    return Err(LoError {
+                        message: format!(
+                            "Cannot get field '{}' on non struct: {value_type}",
+                            field_name.repr
+                        ),
+                        loc: field_name.loc.clone(),
+                    });
+                };
+
+                Ok(VariableInfo::LocalItem {
+                    item_local_indices: item_local_indices
+                        .iter()
+                        .map(|item_local_index| item_local_index + field_index)
+                        .collect(),
+                    index_local_index,
+                    value_type: field_type,
+                })
+            }
+            other => Err(LoError {
+                message: format!(
+                    "Cannot get field '{}' on {}",
+                    field_name.repr,
+                    other.get_type()
+                ),
+                loc: field_name.loc.clone(),
+            }),
+        }
+    }
+
+    // returns (component index, byte offset, field type) or None if `value_type` has no fields
+    fn get_field(
+        &self,
+        value_type: &LoType,
+        field_name: &IdentExpr,
+    ) -> Result<Option<(u32, u32, LoType)>, LoError> {
+        match value_type {
+            LoType::StructInstance { struct_name } => {
+                let struct_def = self.get_struct_def(&struct_name).unwrap();
+                let Some(field) = struct_def
+                    .fields
+                    .iter()
+                    .find(|f| &f.field_name == &field_name.repr)
+                else {
+                    return Err(LoError {
+                        message: format!(
+                            "Unknown field {} in struct {struct_name}",
+                            field_name.repr
+                        ),
+                        loc: field_name.loc.clone(),
+                    });
+                };
+
+                Ok(Some((
+                    field.field_index,
+                    field.byte_offset,
+                    field.field_type.clone(),
+                )))
+            }
+            LoType::Slice { item_type } => {
+                let (field_index, field_type) = self.get_slice_field(item_type, field_name)?;
+                Ok(Some((field_index, field_index * 4, field_type)))
+            }
+            LoType::Tuple { item_types } => Ok(Some(self.get_tuple_item(item_types, field_name)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn get_slice_field(
+        &self,
+        item_type: &LoType,
+        field_name: &IdentExpr,
+    ) -> Result<(u32, LoType), LoError> {
+        match field_name.repr.as_str() {
+            "ptr" => Ok((
+                0,
+                LoType::SequencePointer {
+                    pointee: Box::new(item_type.clone()),
+                },
+            )),
+            "len" => Ok((1, LoType::U32)),
+            _ => Err(LoError {
+                message: format!("Unknown field {} in slice []{item_type}", field_name.repr),
+                loc: field_name.loc.clone(),
+            }),
+        }
+    }
+
+    // returns (component index, byte offset, item type) of `tuple.N`
+    fn get_tuple_item(
+        &self,
+        item_types: &Vec<LoType>,
+        field_name: &IdentExpr,
+    ) -> Result<(u32, u32, LoType), LoError> {
+        let item_index = match field_name.repr.parse::<usize>() {
+            Ok(item_index) if item_index < item_types.len() => item_index,
+            _ => {
+                return Err(LoError {
+                    message: format!(
+                        "Unknown item {} in tuple {}",
+                        field_name.repr,
+                        LoType::Tuple {
+                            item_types: item_types.clone()
+                        }
+                    ),
+                    loc: field_name.loc.clone(),
+                });
+            }
+        };
+
+        let layout = &mut LoTypeLayout::default();
+        for item_type in &item_types[..item_index] {
+            self.get_type_layout(item_type, layout);
+        }
+
+        Ok((
+            layout.primities_count,
+            layout.byte_length,
+            item_types[item_index].clone(),
+        ))
+    }
+
+    fn var_from_index(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        lhs: &CodeExpr,
+        index: &CodeExpr,
+        loc: &LoLocation,
+    ) -> Result<VariableInfo, LoError> {
+        let lhs_type = self.get_expr_type(ctx, lhs)?;
+        let Some((item_type, length)) = self.get_indexed_item_type(&lhs_type) else {
+            return Err(LoError {
+                message: format!("Cannot index value of type {lhs_type}"),
+                loc: loc.clone(),
+            });
+        };
+        let item_type = item_type.clone();
+
+        let index_type = self.get_expr_type(ctx, index)?;
+        if index_type != LoType::U32 {
+            return Err(LoError {
+                message: format!("Invalid index type: {index_type}, expected: u32"),
+                loc: index.loc().clone(),
+            });
+        }
+
+        let Some(length) = length else {
+            return self.var_from_slice_index(ctx, instrs, lhs, item_type, index, loc);
+        };
+
+        let array_var = match &lhs_type {
+            LoType::Pointer { pointee } => {
+                self.codegen(ctx, instrs, lhs)?;
+                let address_local_index = self.define_local(
+                    ctx,
+                    loc.clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &LoType::U32,
+                    false,
+                )?;
+                instrs.push(WasmInstr::LocalSet {
+                    local_index: address_local_index,
+                });
+
+                VariableInfo::Stored {
+                    address_local_index,
+                    field_offset: 0,
+                    value_type: pointee.as_ref().clone(),
+                }
+            }
+            _ => match lhs {
+                CodeExpr::Ident(IdentExpr {
+                    repr,
+                    parts: _,
+                    loc,
+                }) => self.var_from_ident(ctx, repr, loc)?,
+                CodeExpr::FieldAccess(FieldAccessExpr {
+                    lhs,
+                    field_name,
+                    loc: _,
+                }) => self.var_from_field_access(ctx, lhs, field_name)?,
+                CodeExpr::Index(IndexExpr { lhs, index, loc }) => {
+                    self.var_from_index(ctx, instrs, lhs, index, loc)?
+                }
+                // temporary arrays are indexed through a local copy
+                _ => {
+                    self.codegen(ctx, instrs, lhs)?;
+                    let local_index = self.define_local(
+                        ctx,
+                        loc.clone(),
+                        format!("%{}", ctx.last_local_index),
+                        &lhs_type,
+                        false,
+                    )?;
+                    self.codegen_local_set(instrs, &lhs_type, local_index);
+
+                    VariableInfo::Local {
+                        local_index,
+                        local_type: lhs_type.clone(),
+                    }
+                }
+            },
+        };
+
+        let item_layout = &mut LoTypeLayout::default();
+        self.get_type_layout(&item_type, item_layout);
+
+        if let Some(item_index) = self.get_const_int_literal_value(index) {
+            if item_index >= length {
+                return Err(LoError {
+                    message: format!("Index {item_index} is out of bounds for {lhs_type}"),
+                    loc: index.loc().clone(),
+                });
+            }
+
+            return Ok(match array_var {
+                VariableInfo::Local {
+                    local_index,
+                    local_type: _,
+                } => VariableInfo::Local {
+                    local_index: local_index + item_index * item_layout.primities_count,
+                    local_type: item_type,
+                },
+                VariableInfo::Stored {
+                    address_local_index,
+                    field_offset,
+                    value_type: _,
+                } => VariableInfo::Stored {
+                    address_local_index,
+                    field_offset: field_offset + item_index * item_layout.byte_length,
+                    value_type: item_type,
+                },
+                VariableInfo::LocalItem {
+                    item_local_indices,
+                    index_local_index,
+                    value_type: _,
+                } => VariableInfo::LocalItem {
+                    item_local_indices: item_local_indices
+                        .iter()
+                        .map(|local_index| local_index + item_index * item_layout.primities_count)
+                        .collect(),
+                    index_local_index,
+                    value_type: item_type,
+                },
+                VariableInfo::Global { .. } => unreachable!(),
+            });
+        }
+
+        self.codegen(ctx, instrs, index)?;
+        let index_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        instrs.push(WasmInstr::LocalSet {
+            local_index: index_local_index,
+        });
+
+        self.codegen_bounds_check(
+            instrs,
+            index_local_index,
+            WasmInstr::I32Const {
+                value: length as i32,
+            },
+            WasmBinaryOpKind::I32_GE_U,
+        );
+
+        let (address_local_index, field_offset) = match array_var {
+            VariableInfo::Stored {
+                address_local_index,
+                field_offset,
+                value_type: _,
+            } => (address_local_index, field_offset),
+            VariableInfo::Local {
+                local_index,
+                local_type: _,
+            } => {
+                return Ok(VariableInfo::LocalItem {
+                    item_local_indices: (0..length)
+                        .map(|item_index| local_index + item_index * item_layout.primities_count)
+                        .collect(),
+                    index_local_index,
+                    value_type: item_type,
+                });
+            }
+            // items of already picked items are flattened, `outer * length + index` picks one
+            VariableInfo::LocalItem {
+                item_local_indices: outer_local_indices,
+                index_local_index: outer_index_local_index,
+                value_type: _,
+            } => {
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: outer_index_local_index,
+                });
+                instrs.push(WasmInstr::I32Const {
+                    value: length as i32,
+                });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_MUL,
+                });
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: index_local_index,
+                });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_ADD,
+                });
+                let flat_index_local_index = self.define_local(
+                    ctx,
+                    loc.clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &LoType::U32,
+                    false,
+                )?;
+                instrs.push(WasmInstr::LocalSet {
+                    local_index: flat_index_local_index,
+                });
+
+                let mut item_local_indices = Vec::new();
+                for outer_local_index in outer_local_indices {
+                    for item_index in 0..length {
+                        item_local_indices
+                            .push(outer_local_index + item_index * item_layout.primities_count);
+                    }
+                }
+
+                return Ok(VariableInfo::LocalItem {
+                    item_local_indices,
+                    index_local_index: flat_index_local_index,
+                    value_type: item_type,
+                });
+            }
+            VariableInfo::Global { .. } => unreachable!(),
+        };
+
+        self.codegen_item_address(instrs, address_local_index, index_local_index, &item_type);
+        let item_address_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        instrs.push(WasmInstr::LocalSet {
+            local_index: item_address_local_index,
+        });
+
+        Ok(VariableInfo::Stored {
+            address_local_index: item_address_local_index,
+            field_offset,
+            value_type: item_type,
+        })
+    }
+
+    fn var_from_slice_index(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        lhs: &CodeExpr,
+        item_type: LoType,
+        index: &CodeExpr,
+        loc: &LoLocation,
+    ) -> Result<VariableInfo, LoError> {
+        let slice_type = &LoType::Slice {
+            item_type: Box::new(item_type.clone()),
+        };
+
+        self.codegen(ctx, instrs, lhs)?;
+        let slice_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            slice_type,
+            false,
+        )?;
+        self.codegen_local_set(instrs, slice_type, slice_local_index);
+
+        self.codegen(ctx, instrs, index)?;
+        let index_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        instrs.push(WasmInstr::LocalSet {
+            local_index: index_local_index,
+        });
+
+        self.codegen_bounds_check(
+            instrs,
+            index_local_index,
+            WasmInstr::LocalGet {
+                local_index: slice_local_index + 1,
+            },
+            WasmBinaryOpKind::I32_GE_U,
+        );
+
+        self.codegen_item_address(instrs, slice_local_index, index_local_index, &item_type);
+        let item_address_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        instrs.push(WasmInstr::LocalSet {
+            local_index: item_address_local_index,
+        });
+
+        Ok(VariableInfo::Stored {
+            address_local_index: item_address_local_index,
+            field_offset: 0,
+            value_type: item_type,
+        })
+    }
+
+    fn codegen_slice(
+        &self,
+        ctx: &mut LoExprContext,
+        instrs: &mut Vec<WasmInstr>,
+        SliceExpr {
+            lhs,
+            start,
+            end,
+            loc,
+        }: &SliceExpr,
+    ) -> Result<(), LoError> {
+        let lhs_type = self.get_expr_type(ctx, lhs)?;
+        let item_type = self.get_sliced_item_type(&lhs_type, end.is_some(), loc)?;
+
+        for bound in [start, end] {
+            let Some(bound) = bound else {
+                continue;
+            };
+
+            let bound_type = self.get_expr_type(ctx, bound)?;
+            if bound_type != LoType::U32 {
+                return Err(LoError {
+                    message: format!("Invalid slice bound type: {bound_type}, expected: u32"),
+                    loc: bound.loc().clone(),
+                });
+            }
+        }
+
+        self.codegen(ctx, instrs, lhs)?;
+        let ptr_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+
+        // sequence pointers have no length to check against
+        let mut len_local_index = None;
+        match &lhs_type {
+            LoType::Slice { item_type: _ } => {
+                let local_index = self.define_local(
+                    ctx,
+                    loc.clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &LoType::U32,
+                    false,
+                )?;
+                instrs.push(WasmInstr::LocalSet { local_index });
+                len_local_index = Some(local_index);
+            }
+            LoType::Pointer { pointee } => {
+                let LoType::Array {
+                    item_type: _,
+                    length,
+                } = pointee.as_ref()
+                else {
+                    unreachable!()
                 };
 
-                return Ok(LoType::StructInstance {
-                    struct_name: struct_name.repr.clone(),
+                let local_index = self.define_local(
+                    ctx,
+                    loc.clone(),
+                    format!("%{}", ctx.last_local_index),
+                    &LoType::U32,
+                    false,
+                )?;
+                instrs.push(WasmInstr::I32Const {
+                    value: *length as i32,
                 });
+                instrs.push(WasmInstr::LocalSet { local_index });
+                len_local_index = Some(local_index);
             }
-            CodeExpr::ArrayLiteral(_) => todo!(),
-            CodeExpr::Ident(IdentExpr {
-                repr,
-                parts: _,
-                loc,
-            }) => {
-                let var = self.var_from_ident(ctx, &repr, loc)?;
-                Ok(var.get_type())
-            }
-            CodeExpr::InfixOp(InfixOpExpr {
-                op_tag,
-                op_loc: _,
-                lhs,
-                rhs: _,
-                loc: _,
-            }) => match op_tag {
-                InfixOpTag::Equal
-                | InfixOpTag::NotEqual
-                | InfixOpTag::Less
-                | InfixOpTag::Greater
-                | InfixOpTag::LessEqual
-                | InfixOpTag::GreaterEqual
-                | InfixOpTag::And
-                | InfixOpTag::Or => Ok(LoType::Bool),
+            _ => {}
+        }
+        instrs.push(WasmInstr::LocalSet {
+            local_index: ptr_local_index,
+        });
 
-                InfixOpTag::Add
-                | InfixOpTag::Sub
-                | InfixOpTag::Mul
-                | InfixOpTag::Div
-                | InfixOpTag::Mod
-                | InfixOpTag::BitAnd
-                | InfixOpTag::BitOr
-                | InfixOpTag::ShiftLeft
-                | InfixOpTag::ShiftRight => Ok(self.get_expr_type(ctx, lhs)?),
+        let start_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        match start {
+            Some(start) => self.codegen(ctx, instrs, start)?,
+            None => instrs.push(WasmInstr::I32Const { value: 0 }),
+        }
+        instrs.push(WasmInstr::LocalSet {
+            local_index: start_local_index,
+        });
 
-                InfixOpTag::AddAssign
-                | InfixOpTag::SubAssign
-                | InfixOpTag::MulAssign
-                | InfixOpTag::DivAssign
-                | InfixOpTag::ModAssign
-                | InfixOpTag::BitAndAssign
-                | InfixOpTag::BitOrAssign
-                | InfixOpTag::ShiftLeftAssign
-                | InfixOpTag::ShiftRightAssign => Ok(LoType::Void),
+        let end_local_index = self.define_local(
+            ctx,
+            loc.clone(),
+            format!("%{}", ctx.last_local_index),
+            &LoType::U32,
+            false,
+        )?;
+        match (end, len_local_index) {
+            (Some(end), _) => self.codegen(ctx, instrs, end)?,
+            (None, Some(len_local_index)) => instrs.push(WasmInstr::LocalGet {
+                local_index: len_local_index,
+            }),
+            (None, None) => unreachable!(),
+        }
+        instrs.push(WasmInstr::LocalSet {
+            local_index: end_local_index,
+        });
 
-                // have their own CodeExpr variants
-                InfixOpTag::Cast
-                | InfixOpTag::Assign
-                | InfixOpTag::FieldAccess
-                | InfixOpTag::Catch
-                | InfixOpTag::ErrorPropagation => unreachable!(),
+        self.codegen_bounds_check(
+            instrs,
+            start_local_index,
+            WasmInstr::LocalGet {
+                local_index: end_local_index,
             },
-            CodeExpr::PrefixOp(PrefixOpExpr { op_tag, expr, loc }) => match op_tag {
-                PrefixOpTag::Not => Ok(LoType::Bool),
-                PrefixOpTag::Dereference => {
-                    let expr_type = self.get_expr_type(ctx, expr)?;
-                    let LoType::Pointer { pointee } = expr_type else {
-                        return Err(LoError {
-                            message: format!("Cannot dereference expr of type {}", expr_type),
-                            loc: loc.clone(),
-                        });
-                    };
-                    Ok(*pointee)
+            WasmBinaryOpKind::I32_GT_U,
+        );
+        if let Some(len_local_index) = len_local_index {
+            self.codegen_bounds_check(
+                instrs,
+                end_local_index,
+                WasmInstr::LocalGet {
+                    local_index: len_local_index,
+                },
+                WasmBinaryOpKind::I32_GT_U,
+            );
+        }
+
+        self.codegen_item_address(instrs, ptr_local_index, start_local_index, &item_type);
+        instrs.push(WasmInstr::LocalGet {
+            local_index: end_local_index,
+        });
+        instrs.push(WasmInstr::LocalGet {
+            local_index: start_local_index,
+        });
+        instrs.push(WasmInstr::BinaryOp {
+            kind: WasmBinaryOpKind::I32_SUB,
+        });
+
+        Ok(())
+    }
+
+    // traps when `value <op> limit` holds, unless bounds checks are disabled
+    fn codegen_bounds_check(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        value_local_index: u32,
+        limit: WasmInstr,
+        op_kind: WasmBinaryOpKind,
+    ) {
+        if self.skip_bounds_checks {
+            return;
+        }
+
+        instrs.push(WasmInstr::LocalGet {
+            local_index: value_local_index,
+        });
+        instrs.push(limit);
+        instrs.push(WasmInstr::BinaryOp { kind: op_kind });
+        instrs.push(WasmInstr::BlockStart {
+            block_kind: WasmBlockKind::If,
+            block_type: WasmBlockType::NoOut,
+        });
+        instrs.push(WasmInstr::Unreachable);
+        instrs.push(WasmInstr::BlockEnd);
+    }
+
+    fn codegen_item_address(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        base_local_index: u32,
+        index_local_index: u32,
+        item_type: &LoType,
+    ) {
+        let item_layout = &mut LoTypeLayout::default();
+        self.get_type_layout(item_type, item_layout);
+
+        instrs.push(WasmInstr::LocalGet {
+            local_index: base_local_index,
+        });
+        instrs.push(WasmInstr::LocalGet {
+            local_index: index_local_index,
+        });
+        instrs.push(WasmInstr::I32Const {
+            value: item_layout.byte_length as i32,
+        });
+        instrs.push(WasmInstr::BinaryOp {
+            kind: WasmBinaryOpKind::I32_MUL,
+        });
+        instrs.push(WasmInstr::BinaryOp {
+            kind: WasmBinaryOpKind::I32_ADD,
+        });
+    }
+
+    // arrays are indexed either by value or through a pointer, slices have no static length
+    fn get_indexed_item_type<'a>(&self, lhs_type: &'a LoType) -> Option<(&'a LoType, Option<u32>)> {
+        if let LoType::Slice { item_type } = lhs_type {
+            return Some((item_type, None));
+        }
+
+        let array_type = match lhs_type {
+            LoType::Pointer { pointee } => pointee.as_ref(),
+            other => other,
+        };
+
+        let LoType::Array { item_type, length } = array_type else {
+            return None;
+        };
+
+        Some((item_type, Some(*length)))
+    }
+
+    fn get_sliced_item_type(
+        &self,
+        lhs_type: &LoType,
+        has_end: bool,
+        loc: &LoLocation,
+    ) -> Result<LoType, LoError> {
+        match lhs_type {
+            LoType::Slice { item_type } => return Ok(item_type.as_ref().clone()),
+            LoType::Pointer { pointee } => {
+                if let LoType::Array {
+                    item_type,
+                    length: _,
+                } = pointee.as_ref()
+                {
+                    return Ok(item_type.as_ref().clone());
                 }
-                PrefixOpTag::Positive => self.get_expr_type(ctx, expr),
-                PrefixOpTag::Negative => self.get_expr_type(ctx, expr),
-            },
-            CodeExpr::Cast(CastExpr {
-                expr: _,
-                casted_to,
-                loc: _,
-            }) => self.build_type(casted_to),
-            CodeExpr::FieldAccess(FieldAccessExpr {
-                lhs,
-                field_name,
-                loc: _,
-            }) => {
-                let var = self.var_from_field_access(ctx, lhs, field_name)?;
-                Ok(var.get_type())
             }
-            CodeExpr::FnCall(FnCallExpr {
-                fn_name,
-                args: _,
-                loc,
-            }) => {
-                let Some((fn_info, _)) = self.get_fn_info(&fn_name.repr) else {
+            LoType::SequencePointer { pointee } => {
+                if !has_end {
                     return Err(LoError {
-                        message: format!("Unknown function: {}", fn_name.repr),
+                        message: format!("Cannot slice {lhs_type} without an end index"),
                         loc: loc.clone(),
                     });
-                };
+                }
 
-                Ok(fn_info.fn_type.output.clone())
+                return Ok(pointee.as_ref().clone());
             }
-            CodeExpr::MethodCall(MethodCallExpr {
-                lhs,
-                field_name,
-                args: _,
-                loc,
-            }) => {
-                let lhs_type = self.get_expr_type(ctx, lhs)?;
-                let fn_name = get_fn_name_from_method(&lhs_type, &field_name.repr);
+            _ => {}
+        }
 
-                let Some((fn_info, _)) = self.get_fn_info(&fn_name) else {
-                    return Err(LoError {
-                        message: format!("Unknown function: {}", fn_name),
-                        loc: loc.clone(),
+        Err(LoError {
+            message: format!("Cannot slice value of type {lhs_type}"),
+            loc: loc.clone(),
+        })
+    }
+
+    fn codegen_var_get(&self, instrs: &mut Vec<WasmInstr>, var: &VariableInfo) {
+        match var {
+            VariableInfo::Local {
+                local_index,
+                local_type,
+            } => {
+                for i in 0..self.count_wasm_type_components(local_type) {
+                    instrs.push(WasmInstr::LocalGet {
+                        local_index: local_index + i,
                     });
-                };
+                }
+            }
+            VariableInfo::Global {
+                global_index,
+                global_type,
+            } => {
+                for i in 0..self.count_wasm_type_components(global_type) {
+                    instrs.push(WasmInstr::GlobalGet {
+                        global_index: global_index + i,
+                    });
+                }
+            }
+            VariableInfo::Stored {
+                address_local_index,
+                field_offset,
+                value_type,
+            } => {
+                self.codegen_load_from_local(
+                    instrs,
+                    value_type,
+                    *address_local_index,
+                    *field_offset,
+                );
+            }
+            VariableInfo::LocalItem {
+                item_local_indices,
+                index_local_index,
+                value_type,
+            } => {
+                // reading an empty array always fails the bounds check
+                if item_local_indices.is_empty() {
+                    instrs.push(WasmInstr::Unreachable);
+                    return;
+                }
 
-                Ok(fn_info.fn_type.output.clone())
+                // every item is pushed, then `select`s fold them from the last one down
+                for component in 0..self.count_wasm_type_components(value_type) {
+                    for item_local_index in item_local_indices {
+                        instrs.push(WasmInstr::LocalGet {
+                            local_index: item_local_index + component,
+                        });
+                    }
+
+                    for item_index in (0..item_local_indices.len() - 1).rev() {
+                        instrs.push(WasmInstr::LocalGet {
+                            local_index: *index_local_index,
+                        });
+                        instrs.push(WasmInstr::I32Const {
+                            value: item_index as i32,
+                        });
+                        instrs.push(WasmInstr::BinaryOp {
+                            kind: WasmBinaryOpKind::I32_EQ,
+                        });
+                        instrs.push(WasmInstr::Select);
+                    }
+                }
             }
-            CodeExpr::MacroFnCall(_) => todo!(),
-            CodeExpr::MacroMethodCall(_) => todo!(),
-            CodeExpr::Catch(_) => todo!(),
-            CodeExpr::Dbg(_) => todo!(),
-            CodeExpr::Sizeof(_) => Ok(LoType::U32),
-            CodeExpr::GetDataSize(_) => Ok(LoType::U32),
-            CodeExpr::Let(_) => Ok(LoType::Void),
-            CodeExpr::Assign(_) => Ok(LoType::Void),
-            CodeExpr::Defer(_) => Ok(LoType::Void),
-            CodeExpr::If(_) => Ok(LoType::Void),
-            CodeExpr::Loop(_) => Ok(LoType::Void),
-            CodeExpr::ForLoop(_) => Ok(LoType::Void),
-            CodeExpr::Break(_) => Ok(LoType::Never),
-            CodeExpr::Continue(_) => Ok(LoType::Never),
-            CodeExpr::Return(_) => Ok(LoType::Never),
-            CodeExpr::PropagateError(_) => Ok(LoType::Never),
-            CodeExpr::Unreachable(_) => Ok(LoType::Never),
-            CodeExpr::Paren(ParenExpr { expr, loc: _ }) => self.get_expr_type(ctx, expr),
         }
     }
 
-    fn var_from_ident(
+    // every item keeps its value unless its index matches, then it takes the new one
+    fn codegen_local_item_set(
         &self,
-        ctx: &LoExprContext,
-        ident: &str,
-        loc: &LoLocation,
-    ) -> Result<VariableInfo, LoError> {
-        if let Some(local) = ctx.get_local(ident) {
-            return Ok(VariableInfo::Local {
-                local_index: local.local_index,
-                local_type: local.local_type.clone(),
-            });
+        instrs: &mut Vec<WasmInstr>,
+        var: &VariableInfo,
+        value_local_index: u32,
+    ) {
+        let VariableInfo::LocalItem {
+            item_local_indices,
+            index_local_index,
+            value_type,
+        } = var
+        else {
+            unreachable!()
         };
 
-        if let Some(global) = self.get_global(ident) {
-            return Ok(VariableInfo::Global {
-                global_index: global.global_index,
-                global_type: global.global_type.clone(),
-            });
-        }
+        for (item_local_index, item_index) in item_local_indices.iter().zip(0..) {
+            for component in 0..self.count_wasm_type_components(value_type) {
+                let local_index = item_local_index + component;
 
-        return Err(LoError {
-            message: format!("Unknown variable: {ident}"),
-            loc: loc.clone(),
-        });
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: value_local_index + component,
+                });
+                instrs.push(WasmInstr::LocalGet { local_index });
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: *index_local_index,
+                });
+                instrs.push(WasmInstr::I32Const { value: item_index });
+                instrs.push(WasmInstr::BinaryOp {
+                    kind: WasmBinaryOpKind::I32_EQ,
+                });
+                instrs.push(WasmInstr::Select);
+                instrs.push(WasmInstr::LocalSet { local_index });
+            }
+        }
     }
 
-    fn var_from_field_access(
-        &self,
-        ctx: &LoExprContext,
-        lhs: &CodeExpr,
-        field_name: &IdentExpr,
-    ) -> Result<VariableInfo, LoError> {
-        let CodeExpr::Ident(ident) = lhs else {
-            return Err(LoError {
-                message: format!(
-                    "Cannot access struct field '{}' lhs is not a struct local",
-                    field_name.repr
-                ),
-                loc: lhs.loc().clone(),
-            });
-        };
-        let Some(struct_local) = ctx.get_local(&ident.repr) else {
-            return Err(LoError {
-                message: format!("Unknown local {}", ident.repr),
-                loc: lhs.loc().clone(),
-            });
-        };
-        let lhs_type = self.get_expr_type(ctx, lhs)?;
-
-        if let LoType::StructInstance { struct_name } = &lhs_type {
-            let struct_def = self.get_struct_def(&struct_name).unwrap();
-            let Some(field) = struct_def
-                .fields
-                .iter()
-                .find(|f| &f.field_name == &field_name.repr)
-            else {
-                return Err(LoError {
-                    message: format!("Unknown field {} in struct {struct_name}", field_name.repr),
-                    loc: field_name.loc.clone(),
-                });
-            };
+    fn get_dbg_string_literal(&self, dbg: &DbgExpr) -> CodeExpr {
+        let message = Lexer::unescape_string(&dbg.message);
 
-            return Ok(VariableInfo::Local {
-                local_index: struct_local.local_index + field.field_index,
-                local_type: field.field_type.clone(),
-            });
-        }
+        CodeExpr::StringLiteral(StringLiteralExpr {
+            repr: dbg.message.clone(),
+            value: format!("{} - {}", dbg.loc, message),
+            zero_terminated: false,
+            loc: dbg.loc.clone(),
+        })
+    }
 
-        if let LoType::Pointer { pointee } = &lhs_type {
-            if let LoType::StructInstance { struct_name } = pointee.as_ref() {
-                let struct_def = self.get_struct_def(&struct_name).unwrap();
-                let Some(field) = struct_def
-                    .fields
-                    .iter()
-                    .find(|f| &f.field_name == &field_name.repr)
+    fn get_const_int_literal_value(&self, expr: &CodeExpr) -> Option<u32> {
+        match expr {
+            CodeExpr::IntLiteral(IntLiteralExpr {
+                repr: _,
+                value,
+                tag: _,
+                loc: _,
+            }) => Some(*value as u32),
+            CodeExpr::Cast(CastExpr {
+                expr,
+                casted_to: _,
+                loc: _,
+            }) => {
+                let CodeExpr::IntLiteral(IntLiteralExpr {
+                    repr: _,
+                    value,
+                    tag: _,
+                    loc: _,
+                }) = expr.as_ref()
                 else {
-                    return Err(LoError {
-                        message: format!(
-                            "Unknown field {} in struct {struct_name}",
-                            field_name.repr
-                        ),
-                        loc: field_name.loc.clone(),
-                    });
+                    return None;
                 };
 
-                return Ok(VariableInfo::Stored {
-                    address_local_index: struct_local.local_index,
-                    field_offset: field.byte_offset,
-                    value_type: field.field_type.clone(),
-                });
+                Some(*value as u32)
             }
-        };
-
-        return Err(LoError {
-            message: format!(
-                "Cannot get field '{}' on non struct: {lhs_type}",
-                field_name.repr
-            ),
-            loc: field_name.loc.clone(),
-        });
+            _ => None,
+        }
     }
 
-    fn codegen_var_get(&self, instrs: &mut Vec<WasmInstr>, var: &VariableInfo) {
-        match var {
-            VariableInfo::Local {
-                local_index,
-                local_type,
+    fn codegen_load_from_local(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        value_type: &LoType,
+        address_local_index: u32,
+        offset: u32,
+    ) {
+        match value_type {
+            LoType::StructInstance { struct_name } => {
+                let struct_def = self.get_struct_def(struct_name).unwrap();
+
+                for struct_field in &struct_def.fields {
+                    self.codegen_load_from_local(
+                        instrs,
+                        &struct_field.field_type,
+                        address_local_index,
+                        offset + struct_field.byte_offset,
+                    );
+                }
+            }
+            LoType::EnumInstance { enum_name } => {
+                let enum_def = self.get_enum_def(enum_name).unwrap();
+
+                self.codegen_load_from_local(
+                    instrs,
+                    &enum_def.tag_type,
+                    address_local_index,
+                    offset,
+                );
+                for variant in &enum_def.variants {
+                    self.codegen_load_from_local(
+                        instrs,
+                        &variant.payload_type,
+                        address_local_index,
+                        offset + variant.byte_offset,
+                    );
+                }
+            }
+            LoType::Array { item_type, length } => {
+                let item_layout = &mut LoTypeLayout::default();
+                self.get_type_layout(item_type, item_layout);
+
+                for item_index in 0..*length {
+                    self.codegen_load_from_local(
+                        instrs,
+                        item_type,
+                        address_local_index,
+                        offset + item_index * item_layout.byte_length,
+                    );
+                }
+            }
+            LoType::Slice { item_type: _ }
+            | LoType::Closure {
+                inputs: _,
+                output: _,
             } => {
-                for i in 0..self.count_wasm_type_components(local_type) {
+                self.codegen_load_from_local(instrs, &LoType::U32, address_local_index, offset);
+                self.codegen_load_from_local(instrs, &LoType::U32, address_local_index, offset + 4);
+            }
+            LoType::Result { ok_type, err_type } => {
+                let ok_layout = &mut LoTypeLayout::default();
+                self.get_type_layout(ok_type, ok_layout);
+
+                self.codegen_load_from_local(instrs, ok_type, address_local_index, offset);
+                self.codegen_load_from_local(
+                    instrs,
+                    err_type,
+                    address_local_index,
+                    offset + ok_layout.byte_length,
+                );
+            }
+            LoType::Tuple { item_types } => {
+                let layout = &mut LoTypeLayout::default();
+                for item_type in item_types {
+                    let item_offset = offset + layout.byte_length;
+                    self.codegen_load_from_local(
+                        instrs,
+                        item_type,
+                        address_local_index,
+                        item_offset,
+                    );
+                    self.get_type_layout(item_type, layout);
+                }
+            }
+            LoType::Never | LoType::Void => {}
+            _ => {
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: address_local_index,
+                });
+                self.codegen_load_or_store(instrs, value_type, offset, false);
+            }
+        }
+    }
+
+    fn codegen_store_from_local(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        value_type: &LoType,
+        address_local_index: u32,
+        value_local_index: u32,
+        offset: u32,
+    ) {
+        match value_type {
+            LoType::StructInstance { struct_name } => {
+                let struct_def = self.get_struct_def(struct_name).unwrap();
+
+                for struct_field in &struct_def.fields {
+                    self.codegen_store_from_local(
+                        instrs,
+                        &struct_field.field_type,
+                        address_local_index,
+                        value_local_index + struct_field.field_index,
+                        offset + struct_field.byte_offset,
+                    );
+                }
+            }
+            LoType::EnumInstance { enum_name } => {
+                let enum_def = self.get_enum_def(enum_name).unwrap();
+
+                self.codegen_store_from_local(
+                    instrs,
+                    &enum_def.tag_type,
+                    address_local_index,
+                    value_local_index,
+                    offset,
+                );
+
+                // payloads share the same bytes, only the active one is written
+                for variant in &enum_def.variants {
+                    if self.count_wasm_type_components(&variant.payload_type) == 0 {
+                        continue;
+                    }
+
                     instrs.push(WasmInstr::LocalGet {
-                        local_index: local_index + i,
+                        local_index: value_local_index,
+                    });
+                    instrs.push(WasmInstr::I32Const {
+                        value: variant.tag as i32,
+                    });
+                    instrs.push(WasmInstr::BinaryOp {
+                        kind: WasmBinaryOpKind::I32_EQ,
                     });
+                    instrs.push(WasmInstr::BlockStart {
+                        block_kind: WasmBlockKind::If,
+                        block_type: self.get_block_type(&LoType::Void),
+                    });
+                    self.codegen_store_from_local(
+                        instrs,
+                        &variant.payload_type,
+                        address_local_index,
+                        value_local_index + variant.field_index,
+                        offset + variant.byte_offset,
+                    );
+                    instrs.push(WasmInstr::BlockEnd);
                 }
             }
-            VariableInfo::Global {
-                global_index,
-                global_type,
-            } => {
-                for i in 0..self.count_wasm_type_components(global_type) {
-                    instrs.push(WasmInstr::GlobalGet {
-                        global_index: global_index + i,
-                    });
+            LoType::Array { item_type, length } => {
+                let item_layout = &mut LoTypeLayout::default();
+                self.get_type_layout(item_type, item_layout);
+
+                for item_index in 0..*length {
+                    self.codegen_store_from_local(
+                        instrs,
+                        item_type,
+                        address_local_index,
+                        value_local_index + item_index * item_layout.primities_count,
+                        offset + item_index * item_layout.byte_length,
+                    );
                 }
             }
-            VariableInfo::Stored {
-                address_local_index,
-                field_offset,
-                value_type,
+            LoType::Slice { item_type: _ }
+            | LoType::Closure {
+                inputs: _,
+                output: _,
             } => {
+                self.codegen_store_from_local(
+                    instrs,
+                    &LoType::U32,
+                    address_local_index,
+                    value_local_index,
+                    offset,
+                );
+                self.codegen_store_from_local(
+                    instrs,
+                    &LoType::U32,
+                    address_local_index,
+                    value_local_index + 1,
+                    offset + 4,
+                );
+            }
+            LoType::Result { ok_type, err_type } => {
+                let ok_layout = &mut LoTypeLayout::default();
+                self.get_type_layout(ok_type, ok_layout);
+
+                self.codegen_store_from_local(
+                    instrs,
+                    ok_type,
+                    address_local_index,
+                    value_local_index,
+                    offset,
+                );
+                self.codegen_store_from_local(
+                    instrs,
+                    err_type,
+                    address_local_index,
+                    value_local_index + ok_layout.primities_count,
+                    offset + ok_layout.byte_length,
+                );
+            }
+            LoType::Tuple { item_types } => {
+                let layout = &mut LoTypeLayout::default();
+                for item_type in item_types {
+                    self.codegen_store_from_local(
+                        instrs,
+                        item_type,
+                        address_local_index,
+                        value_local_index + layout.primities_count,
+                        offset + layout.byte_length,
+                    );
+                    self.get_type_layout(item_type, layout);
+                }
+            }
+            LoType::Never | LoType::Void => {}
+            _ => {
                 instrs.push(WasmInstr::LocalGet {
-                    local_index: *address_local_index,
+                    local_index: address_local_index,
                 });
-
-                self.codegen_load_or_store(instrs, &value_type, *field_offset, false);
+                instrs.push(WasmInstr::LocalGet {
+                    local_index: value_local_index,
+                });
+                self.codegen_load_or_store(instrs, value_type, offset, true);
             }
         }
     }
@@ -2013,14 +7590,14 @@ impl CodeGen {
                 local_index,
                 local_type,
             } => {
-                self.codegen(ctx, instrs, value)?;
+                self.codegen_with_expected_type(ctx, instrs, value, Some(local_type))?;
                 self.codegen_local_set(instrs, local_type, *local_index);
             }
             VariableInfo::Global {
                 global_index,
                 global_type,
             } => {
-                self.codegen(ctx, instrs, value)?;
+                self.codegen_with_expected_type(ctx, instrs, value, Some(global_type))?;
                 for i in (0..self.count_wasm_type_components(global_type)).rev() {
                     instrs.push(WasmInstr::GlobalSet {
                         global_index: global_index + i,
@@ -2032,84 +7609,396 @@ impl CodeGen {
                 field_offset,
                 value_type,
             } => {
-                instrs.push(WasmInstr::LocalGet {
-                    local_index: *address_local_index,
-                });
-                self.codegen(ctx, instrs, value)?;
-                self.codegen_load_or_store(instrs, &value_type, *field_offset, true);
+                let component_count = self.count_wasm_type_components(value_type);
+                if component_count == 1 {
+                    instrs.push(WasmInstr::LocalGet {
+                        local_index: *address_local_index,
+                    });
+                    self.codegen_with_expected_type(ctx, instrs, value, Some(value_type))?;
+                    self.codegen_load_or_store(instrs, &value_type, *field_offset, true);
+                } else {
+                    self.codegen_with_expected_type(ctx, instrs, value, Some(value_type))?;
+                    let tmp_local_index = self.define_local(
+                        ctx,
+                        value.loc().clone(),
+                        format!("%{}", ctx.last_local_index),
+                        value_type,
+                        false,
+                    )?;
+                    self.codegen_local_set(instrs, value_type, tmp_local_index);
+
+                    self.codegen_store_from_local(
+                        instrs,
+                        value_type,
+                        *address_local_index,
+                        tmp_local_index,
+                        *field_offset,
+                    );
+                }
+            }
+            VariableInfo::LocalItem {
+                item_local_indices: _,
+                index_local_index: _,
+                value_type,
+            } => {
+                self.codegen_with_expected_type(ctx, instrs, value, Some(value_type))?;
+                let tmp_local_index = self.define_local(
+                    ctx,
+                    value.loc().clone(),
+                    format!("%{}", ctx.last_local_index),
+                    value_type,
+                    false,
+                )?;
+                self.codegen_local_set(instrs, value_type, tmp_local_index);
+
+                self.codegen_local_item_set(instrs, var, tmp_local_index);
+            }
+        };
+
+        Ok(())
+    }
+
+    fn codegen_local_set(
+        &self,
+        instrs: &mut Vec<WasmInstr>,
+        local_type: &LoType,
+        local_index: u32,
+    ) {
+        for i in (0..self.count_wasm_type_components(local_type)).rev() {
+            instrs.push(WasmInstr::LocalSet {
+                local_index: local_index + i,
+            });
+        }
+    }
+
+    // TODO: add validation for const expr
+    fn ensure_const_expr(&self, _expr: &CodeExpr) -> Result<(), LoError> {
+        Ok(())
+    }
+
+    fn get_type_or_err(&self, type_name: &str, err_loc: &LoLocation) -> Result<LoType, LoError> {
+        if let Some(t) = self.get_typedef(type_name) {
+            return Ok(t.value.clone());
+        }
+
+        Err(LoError {
+            message: format!("Unknown type: {}", type_name),
+            loc: err_loc.clone(),
+        })
+    }
+
+    fn get_defined_type_loc(&self, type_name: &str) -> Option<&LoLocation> {
+        if let Some(typedef) = self.get_typedef(type_name) {
+            return Some(&typedef.loc);
+        }
+
+        if let Some(generic_def) = self.get_generic_struct_def(type_name) {
+            return Some(&generic_def.loc);
+        }
+
+        None
+    }
+
+    fn get_typedef(&self, type_name: &str) -> Option<&LoTypeDef> {
+        for type_def in &self.type_defs {
+            if type_def.name == type_name {
+                return Some(type_def);
+            }
+        }
+
+        None
+    }
+
+    fn get_const_def(&self, const_name: &str) -> Option<&ConstDefExpr> {
+        for const_def in &self.const_defs {
+            if const_def.const_name.repr == const_name {
+                return Some(const_def);
+            }
+        }
+
+        None
+    }
+
+    fn get_macro_def(&self, macro_name: &str) -> Option<&MacroDefExpr> {
+        for macro_def in &self.macro_defs {
+            if macro_def.macro_name.repr == macro_name {
+                return Some(macro_def);
+            }
+        }
+
+        None
+    }
+
+    fn get_macro_def_or_err(
+        &self,
+        macro_name: &str,
+        loc: &LoLocation,
+    ) -> Result<&MacroDefExpr, LoError> {
+        let Some(macro_def) = self.get_macro_def(macro_name) else {
+            return Err(LoError {
+                message: format!("Unknown macro: {macro_name}"),
+                loc: loc.clone(),
+            });
+        };
+
+        Ok(macro_def)
+    }
+
+    fn get_struct_def(&self, struct_name: &str) -> Option<Rc<LoStructDef>> {
+        for struct_def in self.struct_defs.borrow().iter() {
+            if struct_def.struct_name == struct_name {
+                return Some(struct_def.clone());
+            }
+        }
+
+        None
+    }
+
+    fn get_trait_def(&self, trait_name: &str) -> Option<&TraitDefExpr> {
+        for trait_def in &self.trait_defs {
+            if trait_def.trait_name.repr == trait_name {
+                return Some(trait_def);
+            }
+        }
+
+        None
+    }
+
+    fn get_generic_struct_def(&self, struct_name: &str) -> Option<&StructDefExpr> {
+        for generic_def in &self.generic_struct_defs {
+            if generic_def.struct_name.repr == struct_name {
+                return Some(generic_def);
+            }
+        }
+
+        None
+    }
+
+    fn get_generic_method_def(&self, fn_name: &str) -> Option<usize> {
+        self.generic_methods
+            .iter()
+            .position(|fn_def| fn_def.decl.fn_name.repr == fn_name)
+    }
+
+    // instantiates `Box::get` as `Box<u32>::get` when first called,
+    // its body is compiled later so recursive calls see the instance
+    fn get_generic_method_instance(
+        &self,
+        fn_name: &str,
+        loc: &LoLocation,
+    ) -> Result<Option<LoGenericMethodInstance>, LoError> {
+        for instance in self.generic_method_instances.borrow().iter() {
+            if instance.fn_name == fn_name {
+                return Ok(Some(instance.clone()));
             }
+        }
+
+        let Some((struct_name, method_name)) = fn_name.rsplit_once("::") else {
+            return Ok(None);
+        };
+        let Some(struct_def) = self.get_struct_def(struct_name) else {
+            return Ok(None);
+        };
+        let Some(generic_struct_name) = &struct_def.generic_struct_name else {
+            return Ok(None);
+        };
+        let Some(generic_method_index) =
+            self.get_generic_method_def(&format!("{generic_struct_name}::{method_name}"))
+        else {
+            return Ok(None);
+        };
+
+        let generic_def = self.get_generic_struct_def(generic_struct_name).unwrap();
+        let mut type_scope = Vec::new();
+        for (type_param, type_arg) in generic_def.type_params.iter().zip(&struct_def.type_args) {
+            type_scope.push(LoTypeDef {
+                name: type_param.name.clone(),
+                value: type_arg.clone(),
+                loc: generic_def.loc.clone(),
+            });
+        }
+
+        let self_type = LoType::StructInstance {
+            struct_name: String::from(struct_name),
+        };
+        let fn_def = &self.generic_methods[generic_method_index];
+        let (ctx, fn_type) = self
+            .build_fn_ctx(&fn_def.decl, &type_scope, Some(&self_type))
+            .map_err(|err| LoError {
+                message: format!("{}, in {fn_name} instantiated at {loc}", err.message),
+                loc: err.loc,
+            })?;
+
+        let wasm_fn_type = self.lower_fn_type(&fn_type.inputs, &fn_type.output);
+        let type_index = self.insert_fn_type(wasm_fn_type);
+
+        let mut late_fns = self.late_fns.borrow_mut();
+        let late_fn_index = late_fns.len() as u32;
+        late_fns.push(LoLateFn {
+            fn_name: String::from(fn_name),
+            type_index,
+            wasm_fn: WasmFn {
+                locals: Vec::new(),
+                expr: WasmExpr { instrs: Vec::new() },
+            },
+        });
+        drop(late_fns);
+
+        let instance = LoGenericMethodInstance {
+            fn_name: String::from(fn_name),
+            fn_type,
+            late_fn_index,
         };
+        self.generic_method_instances
+            .borrow_mut()
+            .push(instance.clone());
+        self.pending_generic_method_bodies
+            .borrow_mut()
+            .push(LoPendingFnBody {
+                ctx,
+                generic_method_index,
+                late_fn_index,
+                fn_name: String::from(fn_name),
+                instantiated_at: loc.clone(),
+            });
 
-        Ok(())
+        Ok(Some(instance))
     }
 
-    fn codegen_local_set(
-        &self,
-        instrs: &mut Vec<WasmInstr>,
-        local_type: &LoType,
-        local_index: u32,
-    ) {
-        for i in (0..self.count_wasm_type_components(local_type)).rev() {
-            instrs.push(WasmInstr::LocalSet {
-                local_index: local_index + i,
-            });
+    fn get_enum_def(&self, enum_name: &str) -> Option<&LoEnumDef> {
+        for enum_def in &self.enum_defs {
+            if enum_def.enum_name == enum_name {
+                return Some(enum_def);
+            }
         }
+
+        None
     }
 
-    // TODO: add validation for const expr
-    fn ensure_const_expr(&self, _expr: &CodeExpr) -> Result<(), LoError> {
-        Ok(())
+    // enums without payloads are castable to and from 32-bit integers
+    // `Enum::name(value)` is a builtin unless the enum has a `name` variant
+    fn get_enum_of_name_fn(&self, fn_name: &str) -> Option<&LoEnumDef> {
+        let Some((enum_name, "name")) = fn_name.rsplit_once("::") else {
+            return None;
+        };
+
+        self.get_enum_def(enum_name)
     }
 
-    fn get_type_or_err(&self, type_name: &str, err_loc: &LoLocation) -> Result<LoType, LoError> {
-        if let Some(t) = self.get_typedef(type_name) {
-            return Ok(t.value.clone());
+    // `x.clz()`, `x.rotl(n)`, etc. are builtins on 32 and 64 bit ints unless defined by the user
+    fn get_bit_intrinsic(
+        &self,
+        receiver_type: &LoType,
+        method_name: &str,
+    ) -> Option<(WasmInstr, usize)> {
+        let is_64_bit = match receiver_type {
+            LoType::I32 | LoType::U32 => false,
+            LoType::I64 | LoType::U64 => true,
+            _ => return None,
+        };
+
+        let unary = |kind_32, kind_64| {
+            let kind = if is_64_bit { kind_64 } else { kind_32 };
+            Some((WasmInstr::UnaryOp { kind }, 0))
+        };
+        let binary = |kind_32, kind_64| {
+            let kind = if is_64_bit { kind_64 } else { kind_32 };
+            Some((WasmInstr::BinaryOp { kind }, 1))
+        };
+
+        match method_name {
+            "clz" => unary(WasmUnaryOpKind::I32_CLZ, WasmUnaryOpKind::I64_CLZ),
+            "ctz" => unary(WasmUnaryOpKind::I32_CTZ, WasmUnaryOpKind::I64_CTZ),
+            "popcnt" => unary(WasmUnaryOpKind::I32_POPCNT, WasmUnaryOpKind::I64_POPCNT),
+            "rotl" => binary(WasmBinaryOpKind::I32_ROTL, WasmBinaryOpKind::I64_ROTL),
+            "rotr" => binary(WasmBinaryOpKind::I32_ROTR, WasmBinaryOpKind::I64_ROTR),
+            _ => None,
         }
+    }
 
-        Err(LoError {
-            message: format!("Unknown type: {}", type_name),
-            loc: err_loc.clone(),
-        })
+    // `__memory_size()`, `__memory_grow(pages)` and `__memory_copy(dest, src, size)` builtins
+    fn get_memory_intrinsic(&self, fn_name: &str) -> Option<(WasmInstr, Vec<LoType>, LoType)> {
+        match fn_name {
+            "__memory_size" => Some((WasmInstr::MemorySize, vec![], LoType::I32)),
+            "__memory_grow" => Some((WasmInstr::MemoryGrow, vec![LoType::U32], LoType::I32)),
+            "__memory_copy" => Some((
+                WasmInstr::MemoryCopy,
+                vec![LoType::U32, LoType::U32, LoType::U32],
+                LoType::Void,
+            )),
+            _ => None,
+        }
     }
 
-    fn get_typedef(&self, type_name: &str) -> Option<&LoTypeDef> {
-        for type_def in &self.type_defs {
-            if type_def.name == type_name {
-                return Some(type_def);
+    fn get_enum_variant(&self, variant_path: &str) -> Option<(&LoEnumDef, usize)> {
+        let Some((enum_name, variant_name)) = variant_path.rsplit_once("::") else {
+            return None;
+        };
+
+        let Some(enum_def) = self.get_enum_def(enum_name) else {
+            return None;
+        };
+
+        for (variant, variant_index) in enum_def.variants.iter().zip(0..) {
+            if variant.variant_name == variant_name {
+                return Some((enum_def, variant_index));
             }
         }
 
         None
     }
 
-    fn get_const_def(&self, const_name: &str) -> Option<&ConstDefExpr> {
-        for const_def in &self.const_defs {
-            if const_def.const_name.repr == const_name {
-                return Some(const_def);
-            }
+    fn get_struct_literal_def(
+        &self,
+        type_scope: &Vec<LoTypeDef>,
+        struct_name: &IdentExpr,
+        type_args: &Vec<TypeExpr>,
+        loc: &LoLocation,
+    ) -> Result<Rc<LoStructDef>, LoError> {
+        if type_args.len() != 0 || self.get_generic_struct_def(&struct_name.repr).is_some() {
+            return self.get_generic_struct_instance(type_scope, struct_name, type_args, loc);
         }
 
-        None
+        let Some(struct_def) = self.get_struct_def(&struct_name.repr) else {
+            return Err(LoError {
+                message: format!("Unknown struct: {}", struct_name.repr),
+                loc: loc.clone(),
+            });
+        };
+
+        Ok(struct_def)
     }
 
-    fn get_struct_def(&self, struct_name: &str) -> Option<&LoStructDef> {
-        for struct_def in &self.struct_defs {
-            if struct_def.struct_name == struct_name {
-                return Some(struct_def);
-            }
-        }
+    // instances of generic structs expose the type args they were created with
+    fn get_struct_type_args(&self, lo_type: &LoType) -> Vec<LoType> {
+        let LoType::StructInstance { struct_name } = lo_type.deref_rec() else {
+            return Vec::new();
+        };
 
-        None
+        let struct_def = self.get_struct_def(struct_name).unwrap();
+        struct_def.type_args.clone()
     }
 
-    fn get_struct_def_mut(&mut self, struct_name: &str) -> Option<&mut LoStructDef> {
-        for struct_def in &mut self.struct_defs {
-            if struct_def.struct_name == struct_name {
-                return Some(struct_def);
+    fn get_fn_name_from_method(&self, receiver_type: &LoType, method_name: &str) -> String {
+        let resolved_receiver_type = receiver_type.deref_rec();
+
+        // functions are resolved per instantiation, macros are shared by all of them
+        if let LoType::StructInstance { struct_name } = resolved_receiver_type {
+            let struct_def = self.get_struct_def(struct_name).unwrap();
+            if let Some(generic_struct_name) = &struct_def.generic_struct_name {
+                let instance_fn_name = format!("{struct_name}::{method_name}");
+                let generic_fn_name = format!("{generic_struct_name}::{method_name}");
+                if self.get_fn_info(&instance_fn_name).is_some()
+                    || self.get_generic_method_def(&generic_fn_name).is_some()
+                {
+                    return instance_fn_name;
+                }
+
+                return generic_fn_name;
             }
         }
 
-        None
+        format!("{resolved_receiver_type}::{method_name}")
     }
 
     fn lower_type(&self, lo_type: &LoType, wasm_types: &mut Vec<WasmType>) {
@@ -2129,6 +8018,11 @@ impl CodeGen {
             LoType::F64 => wasm_types.push(WasmType::F64),
             LoType::Pointer { pointee: _ } => wasm_types.push(WasmType::I32),
             LoType::SequencePointer { pointee: _ } => wasm_types.push(WasmType::I32),
+            // index into the function table
+            LoType::Fn {
+                inputs: _,
+                output: _,
+            } => wasm_types.push(WasmType::I32),
             LoType::StructInstance { struct_name } => {
                 let struct_def = self.get_struct_def(struct_name).unwrap();
 
@@ -2136,10 +8030,42 @@ impl CodeGen {
                     self.lower_type(&field.field_type, wasm_types);
                 }
             }
+            LoType::EnumInstance { enum_name } => {
+                let enum_def = self.get_enum_def(enum_name).unwrap();
+
+                // wasm values can't overlap, so every payload gets its own components
+                self.lower_type(&enum_def.tag_type, wasm_types);
+                for variant in &enum_def.variants {
+                    self.lower_type(&variant.payload_type, wasm_types);
+                }
+            }
+            LoType::Array { item_type, length } => {
+                for _ in 0..*length {
+                    self.lower_type(item_type, wasm_types);
+                }
+            }
+            // (ptr, len)
+            LoType::Slice { item_type: _ } => {
+                wasm_types.push(WasmType::I32);
+                wasm_types.push(WasmType::I32);
+            }
+            // (table index, env ptr)
+            LoType::Closure {
+                inputs: _,
+                output: _,
+            } => {
+                wasm_types.push(WasmType::I32);
+                wasm_types.push(WasmType::I32);
+            }
             LoType::Result { ok_type, err_type } => {
                 self.lower_type(ok_type, wasm_types);
                 self.lower_type(err_type, wasm_types);
             }
+            LoType::Tuple { item_types } => {
+                for item_type in item_types {
+                    self.lower_type(item_type, wasm_types);
+                }
+            }
         }
     }
 
@@ -2149,6 +8075,76 @@ impl CodeGen {
         layout.primities_count
     }
 
+    // structs and enums may only contain themselves through pointers
+    fn find_struct_cycle(
+        &self,
+        target_struct_name: &str,
+        lo_type: &LoType,
+        cycle_path: &mut Vec<String>,
+    ) -> bool {
+        match lo_type {
+            LoType::StructInstance { struct_name } => {
+                if struct_name == target_struct_name {
+                    return true;
+                }
+
+                let struct_def = self.get_struct_def(struct_name).unwrap();
+                for struct_field in &struct_def.fields {
+                    cycle_path.push(format!(
+                        "{}.{} at {}",
+                        struct_name, struct_field.field_name, struct_field.loc
+                    ));
+
+                    if self.find_struct_cycle(
+                        target_struct_name,
+                        &struct_field.field_type,
+                        cycle_path,
+                    ) {
+                        return true;
+                    }
+
+                    cycle_path.pop();
+                }
+
+                false
+            }
+            LoType::EnumInstance { enum_name } => {
+                if enum_name == target_struct_name {
+                    return true;
+                }
+
+                let enum_def = self.get_enum_def(enum_name).unwrap();
+                for variant in &enum_def.variants {
+                    cycle_path.push(format!(
+                        "{}::{} at {}",
+                        enum_name, variant.variant_name, variant.loc
+                    ));
+
+                    if self.find_struct_cycle(target_struct_name, &variant.payload_type, cycle_path)
+                    {
+                        return true;
+                    }
+
+                    cycle_path.pop();
+                }
+
+                false
+            }
+            LoType::Array {
+                item_type,
+                length: _,
+            } => self.find_struct_cycle(target_struct_name, item_type, cycle_path),
+            LoType::Result { ok_type, err_type } => {
+                self.find_struct_cycle(target_struct_name, ok_type, cycle_path)
+                    || self.find_struct_cycle(target_struct_name, err_type, cycle_path)
+            }
+            LoType::Tuple { item_types } => item_types
+                .iter()
+                .any(|item_type| self.find_struct_cycle(target_struct_name, item_type, cycle_path)),
+            _ => false,
+        }
+    }
+
     fn get_type_layout<'a>(&self, lo_type: &LoType, layout: &'a mut LoTypeLayout) {
         match lo_type {
             LoType::Never | LoType::Void => {}
@@ -2164,7 +8160,11 @@ impl CodeGen {
             | LoType::I32
             | LoType::F32
             | LoType::Pointer { pointee: _ }
-            | LoType::SequencePointer { pointee: _ } => {
+            | LoType::SequencePointer { pointee: _ }
+            | LoType::Fn {
+                inputs: _,
+                output: _,
+            } => {
                 layout.primities_count += 1;
                 layout.byte_length += 4;
             }
@@ -2179,11 +8179,95 @@ impl CodeGen {
                     self.get_type_layout(&field.field_type, layout);
                 }
             }
+            LoType::EnumInstance { enum_name } => {
+                let enum_def = self.get_enum_def(enum_name).unwrap();
+
+                // payloads overlap in memory, but each keeps its own components
+                self.get_type_layout(&enum_def.tag_type, layout);
+                let mut max_payload_byte_length = 0;
+                for variant in &enum_def.variants {
+                    let payload_layout = &mut LoTypeLayout::default();
+                    self.get_type_layout(&variant.payload_type, payload_layout);
+
+                    layout.primities_count += payload_layout.primities_count;
+                    max_payload_byte_length =
+                        max_payload_byte_length.max(payload_layout.byte_length);
+                }
+                layout.byte_length += max_payload_byte_length;
+            }
+            LoType::Array { item_type, length } => {
+                let item_layout = &mut LoTypeLayout::default();
+                self.get_type_layout(item_type, item_layout);
+
+                layout.primities_count += item_layout.primities_count * length;
+                layout.byte_length += item_layout.byte_length * length;
+            }
+            LoType::Slice { item_type: _ }
+            | LoType::Closure {
+                inputs: _,
+                output: _,
+            } => {
+                layout.primities_count += 2;
+                layout.byte_length += 8;
+            }
             LoType::Result { ok_type, err_type } => {
                 self.get_type_layout(ok_type, layout);
                 self.get_type_layout(err_type, layout);
             }
+            LoType::Tuple { item_types } => {
+                for item_type in item_types {
+                    self.get_type_layout(item_type, layout);
+                }
+            }
+        }
+    }
+
+    fn is_type_compatible(&self, value_type: &LoType, expected_type: &LoType) -> bool {
+        // pointers to arrays decay into sequence pointers to their items
+        if let (
+            LoType::Pointer { pointee },
+            LoType::SequencePointer {
+                pointee: expected_item_type,
+            },
+        ) = (value_type, expected_type)
+        {
+            if let LoType::Array {
+                item_type,
+                length: _,
+            } = pointee.as_ref()
+            {
+                return item_type == expected_item_type;
+            }
+        }
+
+        // `[]u8` is still accepted where the `str` struct is expected, other
+        // `(ptr, len)` structs need an explicit cast
+        if let (LoType::Slice { item_type }, LoType::StructInstance { struct_name }) =
+            (value_type, expected_type)
+        {
+            if struct_name == "str" {
+                let struct_def = self.get_struct_def(struct_name).unwrap();
+                if let [ptr_field, len_field] = struct_def.fields.as_slice() {
+                    let ptr_type = LoType::SequencePointer {
+                        pointee: item_type.clone(),
+                    };
+
+                    return ptr_field.field_type == ptr_type && len_field.field_type == LoType::U32;
+                }
+            }
         }
+
+        value_type == expected_type
+    }
+
+    fn are_types_compatible(&self, value_types: &[LoType], expected_types: &[LoType]) -> bool {
+        value_types.len() == expected_types.len()
+            && value_types
+                .iter()
+                .zip(expected_types)
+                .all(|(value_type, expected_type)| {
+                    self.is_type_compatible(value_type, expected_type)
+                })
     }
 
     fn get_binary_op_kind(
@@ -2192,6 +8276,16 @@ impl CodeGen {
         operand_type: &LoType,
         loc: &LoLocation,
     ) -> Result<WasmBinaryOpKind, LoError> {
+        // enums without payloads are compared by their tags
+        if let LoType::EnumInstance { enum_name } = operand_type {
+            let enum_def = self.get_enum_def(enum_name).unwrap();
+            if let InfixOpTag::Equal | InfixOpTag::NotEqual = op_tag {
+                if self.count_wasm_type_components(operand_type) == 1 {
+                    return self.get_binary_op_kind(op_tag, &enum_def.tag_type, loc);
+                }
+            }
+        }
+
         match op_tag {
             InfixOpTag::Equal => match operand_type {
                 LoType::Bool
@@ -2384,6 +8478,17 @@ impl CodeGen {
                 LoType::I64 | LoType::U64 => return Ok(WasmBinaryOpKind::I64_OR),
                 _ => {}
             },
+            InfixOpTag::BitXor => match operand_type {
+                LoType::Bool
+                | LoType::I8
+                | LoType::U8
+                | LoType::I16
+                | LoType::U16
+                | LoType::I32
+                | LoType::U32 => return Ok(WasmBinaryOpKind::I32_XOR),
+                LoType::I64 | LoType::U64 => return Ok(WasmBinaryOpKind::I64_XOR),
+                _ => {}
+            },
 
             // handled in get_compound_assignment_base_op
             InfixOpTag::AddAssign
@@ -2393,6 +8498,7 @@ impl CodeGen {
             | InfixOpTag::ModAssign
             | InfixOpTag::BitAndAssign
             | InfixOpTag::BitOrAssign
+            | InfixOpTag::BitXorAssign
             | InfixOpTag::ShiftLeftAssign
             | InfixOpTag::ShiftRightAssign => unreachable!(),
 
@@ -2400,6 +8506,7 @@ impl CodeGen {
             InfixOpTag::Cast
             | InfixOpTag::Assign
             | InfixOpTag::FieldAccess
+            | InfixOpTag::Index
             | InfixOpTag::Catch
             | InfixOpTag::ErrorPropagation => unreachable!(),
         }
@@ -2422,6 +8529,7 @@ impl CodeGen {
             InfixOpTag::ModAssign => Some(InfixOpTag::Mod),
             InfixOpTag::BitAndAssign => Some(InfixOpTag::BitAnd),
             InfixOpTag::BitOrAssign => Some(InfixOpTag::BitOr),
+            InfixOpTag::BitXorAssign => Some(InfixOpTag::BitXor),
             InfixOpTag::ShiftLeftAssign => Some(InfixOpTag::ShiftLeft),
             InfixOpTag::ShiftRightAssign => Some(InfixOpTag::ShiftRight),
 
@@ -2440,16 +8548,242 @@ impl CodeGen {
             | InfixOpTag::BitAnd
             | InfixOpTag::Or
             | InfixOpTag::BitOr
+            | InfixOpTag::BitXor
             | InfixOpTag::ShiftLeft
             | InfixOpTag::ShiftRight
             | InfixOpTag::Cast
             | InfixOpTag::Assign
             | InfixOpTag::FieldAccess
+            | InfixOpTag::Index
             | InfixOpTag::Catch
             | InfixOpTag::ErrorPropagation => None,
         }
     }
 
+    fn get_block_type(&self, output: &LoType) -> WasmBlockType {
+        let mut outputs = Vec::new();
+        self.lower_type(output, &mut outputs);
+
+        if outputs.len() == 0 {
+            return WasmBlockType::NoOut;
+        }
+
+        if outputs.len() == 1 {
+            return WasmBlockType::SingleOut {
+                wasm_type: outputs.pop().unwrap(),
+            };
+        }
+
+        WasmBlockType::InOut {
+            type_index: self.insert_fn_type(WasmFnType {
+                inputs: Vec::new(),
+                outputs,
+            }),
+        }
+    }
+
+    fn lower_locals(&self, ctx: &LoExprContext) -> Vec<WasmLocals> {
+        let mut wasm_locals_flat = Vec::new();
+        for local in &ctx.locals {
+            if local.is_fn_param {
+                continue;
+            }
+
+            self.lower_type(&local.local_type, &mut wasm_locals_flat);
+        }
+
+        let mut wasm_locals = Vec::<WasmLocals>::new();
+        for wasm_local_type in wasm_locals_flat {
+            if let Some(wasm_locals_of_type) = wasm_locals.last_mut() {
+                if wasm_locals_of_type.value_type == wasm_local_type {
+                    wasm_locals_of_type.count += 1;
+                    continue;
+                }
+            }
+
+            wasm_locals.push(WasmLocals {
+                count: 1,
+                value_type: wasm_local_type,
+            });
+        }
+
+        wasm_locals
+    }
+
+    // Calling convention, shared by guest functions, host imports, exports and fn pointers:
+    // - each param and the return value is flattened into wasm values by `lower_type`,
+    //   so a by-value struct takes `count_wasm_type_components` params or results
+    // - struct fields and tuple items are flattened in declaration order,
+    //   nested structs and tuples are inlined in place of the field
+    // - slices are (ptr, len), closures are (table index, env ptr),
+    //   enums are the tag followed by every variant payload
+    // - returns with more than one component use multi-value results
+    fn lower_fn_type(&self, inputs: &Vec<LoType>, output: &LoType) -> WasmFnType {
+        let mut wasm_fn_type = WasmFnType {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        };
+        for input in inputs {
+            self.lower_type(input, &mut wasm_fn_type.inputs);
+        }
+        self.lower_type(output, &mut wasm_fn_type.outputs);
+
+        wasm_fn_type
+    }
+
+    fn get_fn_table_index(&self, wasm_fn_index: u32) -> u32 {
+        let mut fn_table = self.fn_table.borrow_mut();
+        if let Some(table_slot) = fn_table.iter().position(|f| *f == wasm_fn_index) {
+            return table_slot as u32 + 1;
+        }
+
+        fn_table.push(wasm_fn_index);
+        fn_table.len() as u32
+    }
+
+    fn insert_fn_type(&self, fn_type: WasmFnType) -> u32 {
+        let mut wasm_types = self.wasm_types.borrow_mut();
+
+        let type_index = wasm_types.iter().position(|ft| *ft == fn_type);
+        if let Some(type_index) = type_index {
+            return type_index as u32;
+        }
+
+        wasm_types.push(fn_type);
+        wasm_types.len() as u32 - 1
+    }
+
+    fn get_or_append_string(&self, value: String) -> u32 {
+        if let Some(string_ptr) = self.string_pool.borrow().get(&value) {
+            return *string_ptr;
+        }
+
+        let string_ptr = self.append_data(value.clone().into_bytes());
+        self.string_pool.borrow_mut().insert(value, string_ptr);
+
+        string_ptr
+    }
+
+    fn append_data(&self, bytes: Vec<u8>) -> u32 {
+        let bytes_ptr = *self.data_size.borrow();
+        let bytes_len = bytes.len() as u32;
+
+        self.datas.borrow_mut().push(WasmData::Active {
+            offset: WasmExpr {
+                instrs: vec![WasmInstr::I32Const {
+                    value: bytes_ptr as i32,
+                }],
+            },
+            bytes,
+        });
+
+        *self.data_size.borrow_mut() += bytes_len;
+
+        bytes_ptr
+    }
+
+    fn append_const_bytes(
+        &self,
+        bytes: &mut Vec<u8>,
+        lo_type: &LoType,
+        expr: &CodeExpr,
+    ) -> Result<(), LoError> {
+        match expr {
+            CodeExpr::BoolLiteral(BoolLiteralExpr { value, loc: _ }) => {
+                bytes.push(*value as u8);
+            }
+            CodeExpr::CharLiteral(CharLiteralExpr {
+                repr: _,
+                value,
+                loc: _,
+            }) => {
+                let layout = &mut LoTypeLayout::default();
+                self.get_type_layout(lo_type, layout);
+
+                let value_bytes = (*value as u64).to_le_bytes();
+                bytes.extend_from_slice(&value_bytes[..layout.byte_length as usize]);
+            }
+            CodeExpr::IntLiteral(literal) => {
+                self.check_int_literal_range(literal, false, lo_type)?;
+
+                let layout = &mut LoTypeLayout::default();
+                self.get_type_layout(lo_type, layout);
+
+                let value_bytes = literal.value.to_le_bytes();
+                bytes.extend_from_slice(&value_bytes[..layout.byte_length as usize]);
+            }
+            CodeExpr::FloatLiteral(literal) => {
+                self.check_float_literal_range(literal, lo_type)?;
+
+                match lo_type {
+                    LoType::F32 => bytes.extend_from_slice(&(literal.value as f32).to_le_bytes()),
+                    _ => bytes.extend_from_slice(&literal.value.to_le_bytes()),
+                }
+            }
+            CodeExpr::StringLiteral(StringLiteralExpr {
+                repr: _,
+                value,
+                zero_terminated,
+                loc: _,
+            }) => {
+                let mut value = value.clone();
+                if *zero_terminated {
+                    value.push('\0');
+                }
+
+                let string_len = value.as_bytes().len() as u32;
+                let string_ptr = self.get_or_append_string(value);
+
+                bytes.extend_from_slice(&string_ptr.to_le_bytes());
+                if !*zero_terminated {
+                    bytes.extend_from_slice(&string_len.to_le_bytes());
+                }
+            }
+            CodeExpr::StructLiteral(StructLiteralExpr {
+                struct_name,
+                type_args,
+                fields,
+                loc,
+            }) => {
+                let struct_def =
+                    self.get_struct_literal_def(&Vec::new(), struct_name, type_args, loc)?;
+
+                if fields.len() != struct_def.fields.len() {
+                    return Err(LoError {
+                        message: format!(
+                            "Invalid number of struct fields, expected: {}, got: {}",
+                            struct_def.fields.len(),
+                            fields.len()
+                        ),
+                        loc: loc.clone(),
+                    });
+                }
+
+                for (field_literal, struct_field) in fields.iter().zip(&struct_def.fields) {
+                    if &field_literal.field_name != &struct_field.field_name {
+                        return Err(LoError {
+                            message: format!(
+                                "Unexpected struct field name, expecting: `{}`",
+                                struct_field.field_name
+                            ),
+                            loc: field_literal.loc.clone(),
+                        });
+                    }
+
+                    self.append_const_bytes(bytes, &struct_field.field_type, &field_literal.value)?;
+                }
+            }
+            _ => {
+                return Err(LoError {
+                    message: format!("Array literal items must be constant values"),
+                    loc: expr.loc().clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     fn get_fn_info(&self, fn_name: &str) -> Option<(&LoFnInfo, &WasmFnInfo)> {
         for wasm_fn_info in &self.wasm_functions {
             if wasm_fn_info.fn_name == fn_name {
@@ -2471,8 +8805,3 @@ impl CodeGen {
         None
     }
 }
-
-fn get_fn_name_from_method(receiver_type: &LoType, method_name: &str) -> String {
-    let resolved_receiver_type = receiver_type.deref_rec();
-    format!("{resolved_receiver_type}::{method_name}")
-}
@@ -6,6 +6,7 @@ pub enum CompilerMode {
     #[default]
     Compile,
     CompileV2,
+    CompareV1V2,
     Inspect,
     PrettyPrint,
     Eval,
@@ -341,15 +342,27 @@ impl LoErrorManager {
         self.errors.push(error);
     }
 
-    pub fn print_all(&self) -> Result<(), String> {
+    pub fn count(&self) -> usize {
+        self.errors.len()
+    }
+
+    // appends a note to errors reported after the first `start` ones
+    pub fn add_note_since(&mut self, start: usize, note: &str) {
+        for error in &mut self.errors[start..] {
+            error.message.push_str(note);
+        }
+    }
+
+    pub fn collect_all(&self) -> Result<(), String> {
         if self.errors.len() == 0 {
             return Ok(());
         }
 
+        let mut message = String::new();
         for error in &self.errors {
-            stderr_write(format!("{error}\n"));
+            message.push_str(&format!("{error}\n"));
         }
 
-        Err(format!(""))
+        Err(message)
     }
 }
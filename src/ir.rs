@@ -1,5 +1,5 @@
 use crate::{core::*, parser::*, wasm::*};
-use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, format, rc::Rc, string::String, vec, vec::Vec};
 use core::cell::RefCell;
 
 #[derive(Default)]
@@ -8,9 +8,13 @@ pub struct ModuleContext<'a> {
     pub wasm_module: RefCell<WasmModule>,
     pub fn_defs: BTreeMap<String, FnDef>,
     pub fn_bodies: RefCell<Vec<FnBody>>,
+    pub generic_methods: BTreeMap<String, GenericMethodDef>,
+    // instances are created while parsing function bodies, after `fn_defs` are complete
+    pub generic_method_instances: RefCell<BTreeMap<String, FnDef>>,
     pub fn_exports: Vec<FnExport>,
     pub memories: BTreeMap<String, u32>,
-    pub struct_defs: Vec<StructDef>,
+    pub struct_defs: RefCell<Vec<Rc<StructDef>>>,
+    pub generic_struct_defs: BTreeMap<String, GenericStructDef>,
     pub globals: BTreeMap<String, GlobalDef>,
     pub indicies_of_data_size_globals: Vec<usize>,
     pub imported_fns_count: u32,
@@ -23,12 +27,20 @@ pub struct ModuleContext<'a> {
 }
 
 impl<'a> ModuleContext<'a> {
-    pub fn get_struct_def(&self, struct_name: &str) -> Option<&StructDef> {
-        self.struct_defs.iter().find(|s| s.name == struct_name)
+    pub fn get_struct_def(&self, struct_name: &str) -> Option<Rc<StructDef>> {
+        self.struct_defs
+            .borrow()
+            .iter()
+            .find(|s| s.name == struct_name)
+            .cloned()
     }
 
-    pub fn get_struct_def_mut(&mut self, struct_name: &str) -> Option<&mut StructDef> {
-        self.struct_defs.iter_mut().find(|s| s.name == struct_name)
+    pub fn get_fn_def(&self, fn_name: &str) -> Option<FnDef> {
+        if let Some(fn_def) = self.fn_defs.get(fn_name) {
+            return Some(fn_def.clone());
+        }
+
+        self.generic_method_instances.borrow().get(fn_name).cloned()
     }
 
     pub fn insert_fn_type(&self, fn_type: WasmFnType) -> u32 {
@@ -86,6 +98,7 @@ pub enum LoBlockKind {
     Block,
     Loop,
     ForLoop,
+    WhileLoop,
 }
 
 impl Default for LoBlockKind {
@@ -97,6 +110,9 @@ impl Default for LoBlockKind {
 #[derive(Default)]
 pub struct Block<'a> {
     pub block_kind: LoBlockKind,
+    pub loop_label: Option<String>,
+    // value types of the `break`s out of a loop, `void` for breaks without a value
+    pub loop_breaks: RefCell<Vec<(LoType, LoLocation)>>,
     pub locals: BTreeMap<String, LocalDef>,
     pub macro_args: Option<BTreeMap<String, LoInstr>>,
     pub type_scope: Option<LoTypeScope<'a>>,
@@ -117,6 +133,11 @@ impl<'a> Block<'a> {
         self
     }
 
+    pub fn with_loop_label(mut self, loop_label: Option<String>) -> Self {
+        self.loop_label = loop_label;
+        self
+    }
+
     pub fn get_local(&self, local_name: &str) -> Option<&LocalDef> {
         if let Some(local_def) = self.locals.get(local_name) {
             return Some(local_def);
@@ -234,28 +255,6 @@ impl LoType {
             other => other,
         }
     }
-
-    pub fn resolve_macro_type_args(&self, type_scope: &LoTypeScope) -> Result<LoType, LoError> {
-        Ok(match self {
-            Self::Pointer(pointee) => {
-                Self::Pointer(Box::new(pointee.resolve_macro_type_args(type_scope)?))
-            }
-            Self::Tuple(items) => {
-                let mut resolved_items = Vec::new();
-                for item in items {
-                    resolved_items.push(item.resolve_macro_type_args(type_scope)?);
-                }
-                Self::Tuple(resolved_items)
-            }
-            Self::MacroTypeArg { name } => {
-                if let Some(t) = type_scope.get(name) {
-                    return Ok(t.clone());
-                }
-                unreachable!();
-            }
-            _ => self.clone(),
-        })
-    }
 }
 
 impl core::fmt::Display for LoType {
@@ -497,6 +496,8 @@ pub struct FnBody {
     pub type_index: u32,
     pub locals: BTreeMap<String, LocalDef>,
     pub locals_last_index: u32,
+    pub type_args: BTreeMap<String, LoType>,
+    pub instantiated_at: Option<(String, LoLocation)>,
     pub body: LoTokenStream,
 }
 
@@ -508,11 +509,27 @@ pub struct FnExport {
 #[derive(Clone)]
 pub struct StructDef {
     pub name: String,
+    pub generic_struct_name: Option<String>,
+    pub type_args: Vec<LoType>,
     pub fields: Vec<StructField>,
     pub fully_defined: bool, // used for self-reference checks
     pub loc: LoLocation,
 }
 
+pub struct GenericStructDef {
+    pub name: String,
+    pub type_params: Vec<String>,
+    pub fields: LoTokenStream, // parsed for every instance
+    pub loc: LoLocation,
+}
+
+pub struct GenericMethodDef {
+    pub struct_name: String,
+    pub method_name: String,
+    pub tokens: LoTokenStream, // params, return type and body, parsed for every instance
+    pub loc: LoLocation,
+}
+
 #[derive(Clone)]
 pub struct StructField {
     pub name: String,
@@ -699,6 +716,14 @@ pub enum LoInstr {
     I32FromI64 {
         expr: Box<LoInstr>,
     },
+    UnaryOp {
+        kind: WasmUnaryOpKind,
+        expr: Box<LoInstr>,
+    },
+    TruncSat {
+        kind: WasmTruncSatKind,
+        expr: Box<LoInstr>,
+    },
     Set {
         bind: LoSetBind,
     },
@@ -767,6 +792,36 @@ impl LoInstr {
             LoInstr::F64Const { .. } => LoType::F64,
             LoInstr::I64FromI32Signed { .. } => LoType::I64,
             LoInstr::I64FromI32Unsigned { .. } => LoType::I64,
+            LoInstr::UnaryOp { kind, expr } => match kind {
+                WasmUnaryOpKind::I32_EQZ | WasmUnaryOpKind::I64_EQZ => LoType::Bool,
+                WasmUnaryOpKind::I32_TRUNC_F32_S | WasmUnaryOpKind::I32_TRUNC_F64_S => LoType::I32,
+                WasmUnaryOpKind::I32_TRUNC_F32_U | WasmUnaryOpKind::I32_TRUNC_F64_U => LoType::U32,
+                WasmUnaryOpKind::I64_TRUNC_F32_S | WasmUnaryOpKind::I64_TRUNC_F64_S => LoType::I64,
+                WasmUnaryOpKind::I64_TRUNC_F32_U | WasmUnaryOpKind::I64_TRUNC_F64_U => LoType::U64,
+                WasmUnaryOpKind::F32_CONVERT_I32_S
+                | WasmUnaryOpKind::F32_CONVERT_I32_U
+                | WasmUnaryOpKind::F32_CONVERT_I64_S
+                | WasmUnaryOpKind::F32_CONVERT_I64_U
+                | WasmUnaryOpKind::F32_DEMOTE_F64 => LoType::F32,
+                WasmUnaryOpKind::F64_CONVERT_I32_S
+                | WasmUnaryOpKind::F64_CONVERT_I32_U
+                | WasmUnaryOpKind::F64_CONVERT_I64_S
+                | WasmUnaryOpKind::F64_CONVERT_I64_U
+                | WasmUnaryOpKind::F64_PROMOTE_F32 => LoType::F64,
+                _ => expr.get_type(ctx),
+            },
+            LoInstr::TruncSat { kind, .. } => {
+                match kind {
+                    WasmTruncSatKind::I32_TRUNC_SAT_F32_S
+                    | WasmTruncSatKind::I32_TRUNC_SAT_F64_S => LoType::I32,
+                    WasmTruncSatKind::I32_TRUNC_SAT_F32_U
+                    | WasmTruncSatKind::I32_TRUNC_SAT_F64_U => LoType::U32,
+                    WasmTruncSatKind::I64_TRUNC_SAT_F32_S
+                    | WasmTruncSatKind::I64_TRUNC_SAT_F64_S => LoType::I64,
+                    WasmTruncSatKind::I64_TRUNC_SAT_F32_U
+                    | WasmTruncSatKind::I64_TRUNC_SAT_F64_U => LoType::U64,
+                }
+            }
             LoInstr::UntypedLocalGet { .. } => unreachable!(),
 
             LoInstr::MultiValueEmit { values } => {
@@ -948,6 +1003,14 @@ pub fn lower_expr(out: &mut Vec<WasmInstr>, expr: &LoInstr) {
             lower_expr(out, expr);
             out.push(WasmInstr::I32WrapI64);
         }
+        LoInstr::UnaryOp { kind, expr } => {
+            lower_expr(out, expr);
+            out.push(WasmInstr::UnaryOp { kind: kind.clone() });
+        }
+        LoInstr::TruncSat { kind, expr } => {
+            lower_expr(out, expr);
+            out.push(WasmInstr::TruncSat { kind: kind.clone() });
+        }
         LoInstr::Set { bind } => match bind {
             LoSetBind::Local { index } => out.push(WasmInstr::LocalSet {
                 local_index: *index,
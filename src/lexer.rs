@@ -6,7 +6,9 @@ pub enum LoTokenType {
     StringLiteral,
     CharLiteral,
     IntLiteral,
+    FloatLiteral,
     Symbol,
+    Label,
     Delim,
     Operator,
     Terminal,
@@ -79,13 +81,22 @@ impl Lexer {
         let char = self.current_char()?;
 
         if char == '\'' {
+            // `'a'` is a char literal, `'outer` is a loop label
+            if let (Ok(next_char), Some(after_next)) =
+                (self.peek_next_char(), self.chars.get(self.index + 2))
+            {
+                if is_symbol_char(next_char) && !next_char.is_numeric() && *after_next != '\'' {
+                    return self.lex_label();
+                }
+            }
+
             return self.lex_char();
         }
         if char == '"' {
             return self.lex_string();
         }
         if char.is_numeric() {
-            return self.lex_int_literal();
+            return self.lex_number_literal();
         }
         // NOTE: must be after int because is_symbol_char matches digits
         if is_symbol_char(char) {
@@ -120,6 +131,24 @@ impl Lexer {
         })
     }
 
+    fn lex_label(&mut self) -> Result<LoToken, LoError> {
+        let mut loc = self.loc();
+
+        self.next_char(); // skip quote
+
+        while is_symbol_char(self.current_char()?) {
+            self.next_char();
+        }
+
+        loc.end_pos = self.pos();
+
+        Ok(LoToken {
+            type_: LoTokenType::Label,
+            value: self.chars[loc.pos.offset + 1..self.index].iter().collect(),
+            loc,
+        })
+    }
+
     fn lex_char(&mut self) -> Result<LoToken, LoError> {
         let mut loc = self.loc();
 
@@ -171,7 +200,7 @@ impl Lexer {
         }
     }
 
-    fn lex_int_literal(&mut self) -> Result<LoToken, LoError> {
+    fn lex_number_literal(&mut self) -> Result<LoToken, LoError> {
         let mut loc = self.loc();
 
         let hex = match (self.current_char(), self.peek_next_char()) {
@@ -183,24 +212,61 @@ impl Lexer {
             _ => false,
         };
 
-        loop {
-            match self.current_char() {
-                Ok('_') | Ok('0'..='9') => {}
-                Ok('A'..='F') if hex => {}
-                _ => break,
+        self.skip_digits(hex);
+
+        let mut type_ = LoTokenType::IntLiteral;
+
+        // `t.0.1` is two tuple item accesses, not an access with `0.1`
+        let start = loc.pos.offset;
+        let is_tuple_index = start >= 1
+            && self.chars[start - 1] == '.'
+            && (start < 2 || self.chars[start - 2] != '.');
+
+        if !hex && !is_tuple_index {
+            // `1.5` is a float but `0..10` and `1.max(2)` are not
+            if let (Ok('.'), Ok('0'..='9')) = (self.current_char(), self.peek_next_char()) {
+                self.next_char(); // skip `.`
+                self.skip_digits(false);
+                type_ = LoTokenType::FloatLiteral;
+            }
+
+            if let Ok('e' | 'E') = self.current_char() {
+                let exponent_start = match self.peek_next_char() {
+                    Ok('+' | '-') => self.chars.get(self.index + 2).copied(),
+                    next_char => next_char.ok(),
+                };
+
+                if let Some('0'..='9') = exponent_start {
+                    self.next_char(); // skip `e`
+                    if let Ok('+' | '-') = self.current_char() {
+                        self.next_char();
+                    }
+                    self.skip_digits(false);
+                    type_ = LoTokenType::FloatLiteral;
+                }
             }
-            self.next_char();
         }
 
         loc.end_pos = self.pos();
 
         Ok(LoToken {
-            type_: LoTokenType::IntLiteral,
+            type_,
             value: self.chars[loc.pos.offset..self.index].iter().collect(),
             loc,
         })
     }
 
+    fn skip_digits(&mut self, hex: bool) {
+        loop {
+            match self.current_char() {
+                Ok('_') | Ok('0'..='9') => {}
+                Ok('A'..='F') if hex => {}
+                _ => break,
+            }
+            self.next_char();
+        }
+    }
+
     pub fn parse_int_literal_value(int_literal: &str) -> u64 {
         let int_literal = int_literal.replace("_", "");
 
@@ -211,6 +277,10 @@ impl Lexer {
         int_literal.parse().unwrap()
     }
 
+    pub fn parse_float_literal_value(float_literal: &str) -> f64 {
+        float_literal.replace("_", "").parse().unwrap()
+    }
+
     fn lex_string(&mut self) -> Result<LoToken, LoError> {
         let mut loc = self.loc();
 
@@ -491,8 +561,10 @@ static OPERATORS: &[&str] = &[
     "^=",  // Bitwise exclusive OR and assignment
     "|",   // Bitwise OR
     "|=",  // Bitwise OR and assignment
+    "~",   // Bitwise NOT
     ".",   // Member access
     "..",  // Range operator
+    "..=", // Inclusive range operator
     ":",   // Type separator
     "::",  // Path separator
     "@",   // Memory index separator, defer label prefix
@@ -526,6 +598,7 @@ pub enum InfixOpTag {
     BitAnd,
     Or,
     BitOr,
+    BitXor,
     ShiftLeft,
     ShiftRight,
 
@@ -537,11 +610,13 @@ pub enum InfixOpTag {
     ModAssign,
     BitAndAssign,
     BitOrAssign,
+    BitXorAssign,
     ShiftLeftAssign,
     ShiftRightAssign,
 
     Cast,
     FieldAccess,
+    Index,
     Catch,
 
     ErrorPropagation,
@@ -565,6 +640,7 @@ impl InfixOpTag {
             InfixOpTag::BitAnd => "&",
             InfixOpTag::Or => "||",
             InfixOpTag::BitOr => "|",
+            InfixOpTag::BitXor => "^",
             InfixOpTag::ShiftLeft => "<<",
             InfixOpTag::ShiftRight => ">>",
             InfixOpTag::Assign => "=",
@@ -575,10 +651,12 @@ impl InfixOpTag {
             InfixOpTag::ModAssign => "%=",
             InfixOpTag::BitAndAssign => "&=",
             InfixOpTag::BitOrAssign => "|=",
+            InfixOpTag::BitXorAssign => "^=",
             InfixOpTag::ShiftLeftAssign => "<<=",
             InfixOpTag::ShiftRightAssign => ">>=",
             InfixOpTag::Cast => "as",
             InfixOpTag::FieldAccess => ".",
+            InfixOpTag::Index => "[",
             InfixOpTag::Catch => "catch",
             InfixOpTag::ErrorPropagation => "?",
         }
@@ -596,31 +674,34 @@ impl InfixOp {
         use InfixOpTag::*;
         use OpAssoc::*;
         let (tag, info) = match token.value.as_str() {
-            "catch" => (Catch, OpInfo { bp: 13, assoc: L }),
+            "catch" => (Catch, OpInfo { bp: 14, assoc: L }),
 
-            "." => (FieldAccess, OpInfo { bp: 12, assoc: L }),
+            "." => (FieldAccess, OpInfo { bp: 13, assoc: L }),
+            "[" => (Index, OpInfo { bp: 13, assoc: L }),
 
             "?" => (
                 ErrorPropagation,
                 OpInfo {
-                    bp: 11,
+                    bp: 12,
                     assoc: None,
                 },
             ),
 
-            "as" => (Cast, OpInfo { bp: 10, assoc: L }),
+            "as" => (Cast, OpInfo { bp: 11, assoc: L }),
+
+            "%" => (Mod, OpInfo { bp: 10, assoc: L }),
+            "/" => (Div, OpInfo { bp: 10, assoc: L }),
+            "*" => (Mul, OpInfo { bp: 10, assoc: L }),
 
-            "%" => (Mod, OpInfo { bp: 9, assoc: L }),
-            "/" => (Div, OpInfo { bp: 9, assoc: L }),
-            "*" => (Mul, OpInfo { bp: 9, assoc: L }),
+            "-" => (Sub, OpInfo { bp: 9, assoc: L }),
+            "+" => (Add, OpInfo { bp: 9, assoc: L }),
 
-            "-" => (Sub, OpInfo { bp: 8, assoc: L }),
-            "+" => (Add, OpInfo { bp: 8, assoc: L }),
+            ">>" => (ShiftRight, OpInfo { bp: 8, assoc: L }),
+            "<<" => (ShiftLeft, OpInfo { bp: 8, assoc: L }),
 
-            ">>" => (ShiftRight, OpInfo { bp: 7, assoc: L }),
-            "<<" => (ShiftLeft, OpInfo { bp: 7, assoc: L }),
+            "&" => (BitAnd, OpInfo { bp: 7, assoc: L }),
 
-            "&" => (BitAnd, OpInfo { bp: 6, assoc: L }),
+            "^" => (BitXor, OpInfo { bp: 6, assoc: L }),
 
             "|" => (BitOr, OpInfo { bp: 5, assoc: L }),
 
@@ -642,6 +723,7 @@ impl InfixOp {
             "%=" => (ModAssign, OpInfo { bp: 1, assoc: None }),
             "&=" => (BitAndAssign, OpInfo { bp: 1, assoc: None }),
             "|=" => (BitOrAssign, OpInfo { bp: 1, assoc: None }),
+            "^=" => (BitXorAssign, OpInfo { bp: 1, assoc: None }),
             "<<=" => (ShiftLeftAssign, OpInfo { bp: 1, assoc: None }),
             ">>=" => (ShiftRightAssign, OpInfo { bp: 1, assoc: None }),
             _ => return Option::None,
@@ -653,6 +735,7 @@ impl InfixOp {
 #[derive(Debug)]
 pub enum PrefixOpTag {
     Not,
+    BitNot,
     Dereference,
     Positive,
     Negative,
@@ -662,6 +745,7 @@ impl PrefixOpTag {
     pub fn to_str(&self) -> &str {
         match self {
             PrefixOpTag::Not => "!",
+            PrefixOpTag::BitNot => "~",
             PrefixOpTag::Dereference => "*",
             PrefixOpTag::Positive => "+",
             PrefixOpTag::Negative => "-",
@@ -680,10 +764,11 @@ impl PrefixOp {
         use OpAssoc::*;
         use PrefixOpTag::*;
         let (tag, info) = match token.value.as_str() {
-            "!" => (Not, OpInfo { bp: 8, assoc: L }),
-            "*" => (Dereference, OpInfo { bp: 8, assoc: L }),
-            "+" => (Positive, OpInfo { bp: 8, assoc: L }),
-            "-" => (Negative, OpInfo { bp: 8, assoc: L }),
+            "!" => (Not, OpInfo { bp: 9, assoc: L }),
+            "~" => (BitNot, OpInfo { bp: 9, assoc: L }),
+            "*" => (Dereference, OpInfo { bp: 9, assoc: L }),
+            "+" => (Positive, OpInfo { bp: 9, assoc: L }),
+            "-" => (Negative, OpInfo { bp: 9, assoc: L }),
             _ => return Option::None,
         };
         Some(Self { tag, info, token })
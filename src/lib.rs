@@ -1,5 +1,7 @@
 #![no_std]
-#![feature(alloc_error_handler, thread_local)]
+#![feature(alloc_error_handler, thread_local, core_intrinsics)]
+// float rounding and `sqrt` are not exposed by `core`, their intrinsics lower to wasm opcodes
+#![allow(internal_features)]
 
 extern crate alloc;
 
@@ -12,6 +14,7 @@ mod parser;
 mod parser_v2;
 mod printer;
 mod wasm;
+mod wasm_compare;
 mod wasm_eval;
 mod wasm_parser;
 
@@ -38,7 +41,8 @@ mod wasm_target {
 static USAGE: &str = "\
 Usage: lo <file> [mode]
   where [mode] is either:
-    --compile-v2 (temporary)
+    --compile-v2 [--no-bounds-checks] (temporary)
+    --compare-v1-v2 (temporary)
     --inspect
     --pretty-print
     --eval (experimental)
@@ -48,8 +52,8 @@ Usage: lo <file> [mode]
 
 mod wasi_api {
     use crate::{
-        codegen::*, core::*, lexer::*, parser, parser_v2::*, printer::*, wasm_eval::*,
-        wasm_parser::*, USAGE,
+        codegen::*, core::*, lexer::*, parser, parser_v2::*, printer::*, wasm::*, wasm_compare::*,
+        wasm_eval::*, wasm_parser::*, USAGE,
     };
     use alloc::{format, rc::Rc, string::String, vec::Vec};
 
@@ -80,6 +84,7 @@ mod wasi_api {
         let compiler_mode = match args.get(2) {
             None => CompilerMode::Compile,
             Some("--compile-v2") => CompilerMode::CompileV2,
+            Some("--compare-v1-v2") => CompilerMode::CompareV1V2,
             Some("--inspect") => CompilerMode::Inspect,
             Some("--pretty-print") => CompilerMode::PrettyPrint,
             Some("--eval") => CompilerMode::Eval,
@@ -90,15 +95,15 @@ mod wasi_api {
         };
 
         if compiler_mode == CompilerMode::CompileV2 {
-            let mut files = Vec::new();
-            parse_file_and_deps(&mut files, file_name, &LoLocation::internal())?;
+            let skip_bounds_checks = match args.get(3) {
+                None => false,
+                Some("--no-bounds-checks") => true,
+                Some(unknown_option) => {
+                    return Err(format!("Unknown option: {unknown_option}\n{}", USAGE));
+                }
+            };
 
-            let mut codegen = CodeGen::with_default_types();
-            for file in files.into_iter().rev() {
-                codegen.add_file(file)?;
-            }
-            codegen.errors.print_all()?;
-            let wasm_module = codegen.generate()?;
+            let wasm_module = compile_v2(file_name, skip_bounds_checks)?;
 
             let mut binary = Vec::new();
             wasm_module.dump(&mut binary);
@@ -107,6 +112,47 @@ mod wasi_api {
             return Ok(());
         }
 
+        if compiler_mode == CompilerMode::CompareV1V2 {
+            if file_name == "<stdin>" {
+                return Err(format!("Cannot compare <stdin> input, file expected"));
+            }
+
+            let (v1_module, v2_module) = match (compile_v1(file_name), compile_v2(file_name, false))
+            {
+                (Ok(v1_module), Ok(v2_module)) => (v1_module, v2_module),
+                (Ok(_), Err(v2_err)) => {
+                    return Err(format!("Only v1 accepts {file_name}, v2 errors:\n{v2_err}"));
+                }
+                (Err(v1_err), Ok(_)) => {
+                    return Err(format!("Only v2 accepts {file_name}, v1 errors:\n{v1_err}"));
+                }
+                (Err(v1_err), Err(v2_err)) => {
+                    return Err(format!(
+                        "Both v1 and v2 reject {file_name}, v1 errors:\n{v1_err}\nv2 errors:\n{v2_err}"
+                    ));
+                }
+            };
+
+            let v1_module = reparse_module(format!("{file_name} (v1)"), v1_module)?;
+            let v2_module = reparse_module(format!("{file_name} (v2)"), v2_module)?;
+
+            let differences = WasmCompare::compare(&v1_module, &v2_module);
+            for difference in &differences {
+                stdout_writeln(difference);
+            }
+
+            if differences.len() != 0 {
+                return Err(format!(
+                    "Found {} differences between v1 and v2",
+                    differences.len()
+                ));
+            }
+
+            stdout_writeln("No differences found");
+
+            return Ok(());
+        }
+
         if compiler_mode == CompilerMode::PrettyPrint {
             let chars = file_read_utf8(file_name)?;
             let tokens = Lexer::lex(file_name, &chars)?;
@@ -152,4 +198,37 @@ mod wasi_api {
 
         return Ok(());
     }
+
+    fn compile_v1(file_name: &str) -> Result<WasmModule, String> {
+        let ctx = &mut parser::init(CompilerMode::Compile);
+
+        parser::parse_file(ctx, file_name, &LoLocation::internal())?;
+        parser::finalize(ctx)?;
+
+        Ok(ctx.wasm_module.take())
+    }
+
+    fn compile_v2(file_name: &str, skip_bounds_checks: bool) -> Result<WasmModule, String> {
+        let mut files = Vec::new();
+        parse_file_and_deps(&mut files, file_name, &LoLocation::internal())?;
+
+        let mut codegen = CodeGen::with_default_types();
+        codegen.skip_bounds_checks = skip_bounds_checks;
+        for file in files {
+            codegen.add_file(file)?;
+        }
+        codegen.errors.borrow().collect_all()?;
+        let wasm_module = codegen.generate()?;
+        codegen.errors.borrow().collect_all()?;
+
+        Ok(wasm_module)
+    }
+
+    // round trip through the binary format so both sides are compared as emitted
+    fn reparse_module(module_name: String, wasm_module: WasmModule) -> Result<WasmModule, String> {
+        let mut binary = Vec::new();
+        wasm_module.dump(&mut binary);
+
+        WasmParser::parse(module_name, binary)
+    }
 }
@@ -1,5 +1,7 @@
 use crate::{core::*, ir::*, lexer::*, wasm::*};
-use alloc::{boxed::Box, collections::BTreeMap, format, str, string::String, vec, vec::Vec};
+use alloc::{
+    boxed::Box, collections::BTreeMap, format, rc::Rc, str, string::String, vec, vec::Vec,
+};
 use LoTokenType::*;
 
 const RECEIVER_PARAM_NAME: &str = "self";
@@ -87,80 +89,23 @@ pub fn finalize(ctx: &mut ModuleContext) -> Result<(), LoError> {
         });
     }
 
-    // push function codes
-    for mut fn_body in ctx.fn_bodies.take() {
-        let fn_def = ctx
-            .fn_defs
-            .values()
-            .find(|fd| fd.local && fd.fn_index == fn_body.fn_index)
-            .unwrap();
-
-        let mut fn_ctx = FnContext {
-            module: &ctx,
-            lo_fn_type: &fn_def.type_,
-            locals_last_index: fn_body.locals_last_index,
-            non_arg_wasm_locals: vec![],
-            defers: vec![],
-        };
-
-        let locals_block = Block {
-            locals: fn_body.locals,
-            ..Default::default()
-        };
-
-        let mut block_ctx = BlockContext {
-            module: &ctx,
-            fn_ctx: &mut fn_ctx,
-            block: Block::child_of(ctx, &locals_block).of_kind(LoBlockKind::Function),
-        };
-
-        let mut contents = parse_block_contents(&mut block_ctx, &mut fn_body.body, LoType::Void)?;
-
-        if !contents.has_return && !contents.has_never {
-            if let Some(mut values) = get_deferred(&mut block_ctx) {
-                contents.exprs.append(&mut values);
-            };
-
-            let return_type = &fn_def.type_.output;
-
-            match return_type {
-                LoType::Void => {}
-                LoType::Never => {
-                    return Err(LoError {
-                        message: format!("This function terminates but is marked as `never`"),
-                        loc: fn_def.loc.clone(),
-                    });
-                }
-                _ => {
-                    return Err(LoError {
-                        message: format!("Missing return expression"),
-                        loc: fn_def.loc.clone(),
-                    });
-                }
-            }
+    // push function codes, generic method instances found in the bodies are compiled after them
+    loop {
+        let fn_bodies = ctx.fn_bodies.take();
+        if fn_bodies.len() == 0 {
+            break;
         }
 
-        let mut locals = Vec::<WasmLocals>::new();
-        for local_type in &block_ctx.fn_ctx.non_arg_wasm_locals {
-            if let Some(wasm_locals) = locals.last_mut() {
-                if wasm_locals.value_type == *local_type {
-                    wasm_locals.count += 1;
-                    continue;
-                }
-            }
-            locals.push(WasmLocals {
-                count: 1,
-                value_type: local_type.clone(),
-            });
+        for fn_body in fn_bodies {
+            let instantiated_at = fn_body.instantiated_at.clone();
+            finalize_fn_body(ctx, fn_body).map_err(|err| match instantiated_at {
+                Some((fn_name, loc)) => LoError {
+                    message: format!("{}, in {fn_name} instantiated at {loc}", err.message),
+                    loc: err.loc,
+                },
+                None => err,
+            })?;
         }
-
-        let mut instrs = vec![];
-        lower_exprs(&mut instrs, &contents.exprs);
-
-        ctx.wasm_module.borrow_mut().codes.push(WasmFn {
-            locals,
-            expr: WasmExpr { instrs },
-        });
     }
 
     if ctx.mode != CompilerMode::Inspect {
@@ -195,6 +140,95 @@ pub fn finalize(ctx: &mut ModuleContext) -> Result<(), LoError> {
     Ok(())
 }
 
+fn finalize_fn_body(ctx: &ModuleContext, mut fn_body: FnBody) -> Result<(), LoError> {
+    let fn_def = ctx
+        .fn_defs
+        .values()
+        .find(|fd| fd.local && fd.fn_index == fn_body.fn_index)
+        .cloned()
+        .or_else(|| {
+            ctx.generic_method_instances
+                .borrow()
+                .values()
+                .find(|fd| fd.fn_index == fn_body.fn_index)
+                .cloned()
+        })
+        .unwrap();
+
+    let mut fn_ctx = FnContext {
+        module: ctx,
+        lo_fn_type: &fn_def.type_,
+        locals_last_index: fn_body.locals_last_index,
+        non_arg_wasm_locals: vec![],
+        defers: vec![],
+    };
+
+    let locals_block = Block {
+        locals: fn_body.locals,
+        type_scope: Some(LoTypeScope {
+            types: fn_body.type_args,
+            parent: Some(&ctx.type_scope),
+        }),
+        ..Default::default()
+    };
+
+    let mut block_ctx = BlockContext {
+        module: &ctx,
+        fn_ctx: &mut fn_ctx,
+        block: Block::child_of(ctx, &locals_block).of_kind(LoBlockKind::Function),
+    };
+
+    let mut contents = parse_block_contents(&mut block_ctx, &mut fn_body.body, LoType::Void)?;
+
+    if !contents.has_return && !contents.has_never {
+        if let Some(mut values) = get_deferred(&mut block_ctx) {
+            contents.exprs.append(&mut values);
+        };
+
+        let return_type = &fn_def.type_.output;
+
+        match return_type {
+            LoType::Void => {}
+            LoType::Never => {
+                return Err(LoError {
+                    message: format!("This function terminates but is marked as `never`"),
+                    loc: fn_def.loc.clone(),
+                });
+            }
+            _ => {
+                return Err(LoError {
+                    message: format!("Missing return expression"),
+                    loc: fn_def.loc.clone(),
+                });
+            }
+        }
+    }
+
+    let mut locals = Vec::<WasmLocals>::new();
+    for local_type in &block_ctx.fn_ctx.non_arg_wasm_locals {
+        if let Some(wasm_locals) = locals.last_mut() {
+            if wasm_locals.value_type == *local_type {
+                wasm_locals.count += 1;
+                continue;
+            }
+        }
+        locals.push(WasmLocals {
+            count: 1,
+            value_type: local_type.clone(),
+        });
+    }
+
+    let mut instrs = vec![];
+    lower_exprs(&mut instrs, &contents.exprs);
+
+    ctx.wasm_module.borrow_mut().codes.push(WasmFn {
+        locals,
+        expr: WasmExpr { instrs },
+    });
+
+    Ok(())
+}
+
 // TODO: add local names (requires sizable refactoring to achieve)
 fn write_debug_info(ctx: &mut ModuleContext) -> Result<(), LoError> {
     use crate::wasm::*;
@@ -206,10 +240,12 @@ fn write_debug_info(ctx: &mut ModuleContext) -> Result<(), LoError> {
 
     /* function names */
     {
+        let generic_method_instances = ctx.generic_method_instances.borrow();
         for fn_index in first_own_fn_index..first_own_fn_index + own_fns_count {
             let (fn_name, _) = ctx
                 .fn_defs
                 .iter()
+                .chain(generic_method_instances.iter())
                 .find(|(_, v)| v.get_absolute_index(ctx) == fn_index)
                 .unwrap();
 
@@ -447,13 +483,46 @@ fn parse_top_level_expr(
             });
         }
 
+        if let Some(_) = ctx.generic_struct_defs.get(&struct_name.value) {
+            return Err(LoError {
+                message: format!("Cannot redefine type {}", struct_name.value),
+                loc: struct_name.loc,
+            });
+        }
+
+        if let Some(_) = tokens.eat(Operator, "<")? {
+            let type_params = parse_type_params(ctx, &ctx.type_scope, tokens)?;
+
+            // fields are parsed when the struct is instantiated
+            let fields_start = tokens.index;
+            collect_block_tokens(tokens)?;
+            let fields = LoTokenStream::new(
+                tokens.tokens[fields_start..tokens.index].to_vec(),
+                tokens.loc().clone(),
+            );
+
+            ctx.generic_struct_defs.insert(
+                struct_name.value.clone(),
+                GenericStructDef {
+                    name: struct_name.value,
+                    type_params,
+                    fields,
+                    loc: struct_name.loc,
+                },
+            );
+
+            return Ok(());
+        }
+
         // declare not fully defined struct to use in self-references
-        ctx.struct_defs.push(StructDef {
+        ctx.struct_defs.borrow_mut().push(Rc::new(StructDef {
             name: struct_name.value.clone(),
+            generic_struct_name: None,
+            type_args: vec![],
             fields: vec![],
             fully_defined: false,
-            loc: struct_name.loc,
-        });
+            loc: struct_name.loc.clone(),
+        }));
 
         ctx.type_scope.insert(
             struct_name.value.clone(),
@@ -462,57 +531,19 @@ fn parse_top_level_expr(
             },
         );
 
-        let mut field_index = 0;
-        let mut byte_offset = 0;
-        let mut struct_fields = Vec::<StructField>::new();
-
-        tokens.expect(Delim, "{")?;
-        while let None = tokens.eat(Delim, "}")? {
-            let field_name = tokens.expect_any(Symbol)?.clone();
-            tokens.expect(Operator, ":")?;
-            let field_type_loc = tokens.loc().clone();
-            let field_type = parse_const_lo_type(ctx, tokens)?;
-            if !tokens.next_is(Delim, "}")? {
-                tokens.expect(Delim, ",")?;
-            }
-
-            if struct_fields
-                .iter()
-                .find(|f| f.name == field_name.value)
-                .is_some()
-            {
-                return Err(LoError {
-                    message: format!(
-                        "Found duplicate struct field name: '{}' of struct {}",
-                        field_name.value, struct_name.value,
-                    ),
-                    loc: field_name.loc,
-                });
-            }
+        let fields = parse_struct_fields(ctx, &ctx.type_scope, &struct_name.value, tokens)?;
 
-            let mut stats = EmitComponentStats::default();
-            field_type
-                .emit_sized_component_stats(ctx, &mut stats, &mut vec![])
-                .map_err(|err| LoError {
-                    message: err,
-                    loc: field_type_loc,
-                })?;
-
-            struct_fields.push(StructField {
-                name: field_name.value,
-                value_type: field_type,
-                field_index,
-                byte_offset,
-                loc: field_name.loc,
-            });
-
-            field_index += stats.count;
-            byte_offset += stats.byte_length;
-        }
-
-        let struct_def = ctx.get_struct_def_mut(&struct_name.value).unwrap();
-        struct_def.fields.append(&mut struct_fields);
-        struct_def.fully_defined = true;
+        replace_struct_def(
+            ctx,
+            StructDef {
+                name: struct_name.value,
+                generic_struct_name: None,
+                type_args: vec![],
+                fields,
+                fully_defined: true,
+                loc: struct_name.loc,
+            },
+        );
 
         return Ok(());
     }
@@ -600,6 +631,219 @@ fn parse_top_level_expr(
     });
 }
 
+fn parse_struct_fields(
+    ctx: &ModuleContext,
+    type_scope: &LoTypeScope,
+    struct_name: &str,
+    tokens: &mut LoTokenStream,
+) -> Result<Vec<StructField>, LoError> {
+    let mut field_index = 0;
+    let mut byte_offset = 0;
+    let mut struct_fields = Vec::<StructField>::new();
+
+    tokens.expect(Delim, "{")?;
+    while let None = tokens.eat(Delim, "}")? {
+        let field_name = tokens.expect_any(Symbol)?.clone();
+        tokens.expect(Operator, ":")?;
+        let field_type_loc = tokens.loc().clone();
+        let field_type = parse_lo_type_(ctx, type_scope, tokens, false)?;
+        if !tokens.next_is(Delim, "}")? {
+            tokens.expect(Delim, ",")?;
+        }
+
+        if struct_fields
+            .iter()
+            .find(|f| f.name == field_name.value)
+            .is_some()
+        {
+            return Err(LoError {
+                message: format!(
+                    "Found duplicate struct field name: '{}' of struct {struct_name}",
+                    field_name.value,
+                ),
+                loc: field_name.loc,
+            });
+        }
+
+        let mut stats = EmitComponentStats::default();
+        field_type
+            .emit_sized_component_stats(ctx, &mut stats, &mut vec![])
+            .map_err(|err| LoError {
+                message: err,
+                loc: field_type_loc,
+            })?;
+
+        struct_fields.push(StructField {
+            name: field_name.value,
+            value_type: field_type,
+            field_index,
+            byte_offset,
+            loc: field_name.loc,
+        });
+
+        field_index += stats.count;
+        byte_offset += stats.byte_length;
+    }
+
+    Ok(struct_fields)
+}
+
+fn replace_struct_def(ctx: &ModuleContext, struct_def: StructDef) {
+    let mut struct_defs = ctx.struct_defs.borrow_mut();
+
+    // safe, structs are declared before their fields are parsed
+    let index = struct_defs
+        .iter()
+        .position(|s| s.name == struct_def.name)
+        .unwrap();
+
+    struct_defs[index] = Rc::new(struct_def);
+}
+
+fn get_struct_instance(
+    ctx: &ModuleContext,
+    generic_def: &GenericStructDef,
+    type_args: Vec<LoType>,
+    loc: &LoLocation,
+) -> Result<Rc<StructDef>, LoError> {
+    if type_args.len() != generic_def.type_params.len() {
+        return Err(LoError {
+            message: format!(
+                "Invalid number of type params, expected {}, got {}",
+                generic_def.type_params.len(),
+                type_args.len()
+            ),
+            loc: loc.clone(),
+        });
+    }
+
+    let struct_name = format!("{}<{}>", generic_def.name, ListDisplay(&type_args));
+    if let Some(struct_def) = ctx.get_struct_def(&struct_name) {
+        return Ok(struct_def);
+    }
+
+    // instances in macro signatures only stand in for the ones built at macro call sites
+    let is_template = type_args.iter().any(|t| has_macro_type_args(ctx, t));
+
+    // pushed before the fields are parsed so self-references resolve to this instance
+    ctx.struct_defs.borrow_mut().push(Rc::new(StructDef {
+        name: struct_name.clone(),
+        generic_struct_name: Some(generic_def.name.clone()),
+        type_args: type_args.clone(),
+        fields: vec![],
+        fully_defined: is_template,
+        loc: generic_def.loc.clone(),
+    }));
+
+    if is_template {
+        return Ok(ctx.get_struct_def(&struct_name).unwrap());
+    }
+
+    let mut type_scope = LoTypeScope {
+        parent: Some(&ctx.type_scope),
+        ..Default::default()
+    };
+    for (type_param, type_arg) in generic_def.type_params.iter().zip(&type_args) {
+        type_scope.insert(type_param.clone(), type_arg.clone());
+    }
+
+    let fields = parse_struct_fields(
+        ctx,
+        &type_scope,
+        &struct_name,
+        &mut generic_def.fields.clone(),
+    )
+    .map_err(|err| {
+        ctx.struct_defs
+            .borrow_mut()
+            .retain(|struct_def| struct_def.name != struct_name);
+
+        LoError {
+            message: format!("{}, in {struct_name} instantiated at {loc}", err.message),
+            loc: err.loc,
+        }
+    })?;
+
+    replace_struct_def(
+        ctx,
+        StructDef {
+            name: struct_name.clone(),
+            generic_struct_name: Some(generic_def.name.clone()),
+            type_args,
+            fields,
+            fully_defined: true,
+            loc: generic_def.loc.clone(),
+        },
+    );
+
+    Ok(ctx.get_struct_def(&struct_name).unwrap())
+}
+
+fn has_macro_type_args(ctx: &ModuleContext, lo_type: &LoType) -> bool {
+    match lo_type {
+        LoType::MacroTypeArg { .. } => true,
+        LoType::Pointer(pointee) => has_macro_type_args(ctx, pointee),
+        LoType::Tuple(items) => items.iter().any(|t| has_macro_type_args(ctx, t)),
+        LoType::Result { ok_type, err_type } => {
+            has_macro_type_args(ctx, ok_type) || has_macro_type_args(ctx, err_type)
+        }
+        LoType::StructInstance { name } => {
+            let struct_def = ctx.get_struct_def(name).unwrap(); // safe
+            struct_def
+                .type_args
+                .iter()
+                .any(|t| has_macro_type_args(ctx, t))
+        }
+        _ => false,
+    }
+}
+
+fn resolve_macro_type_args(
+    ctx: &ModuleContext,
+    lo_type: &LoType,
+    type_scope: &LoTypeScope,
+    loc: &LoLocation,
+) -> Result<LoType, LoError> {
+    Ok(match lo_type {
+        LoType::Pointer(pointee) => LoType::Pointer(Box::new(resolve_macro_type_args(
+            ctx, pointee, type_scope, loc,
+        )?)),
+        LoType::Tuple(items) => {
+            let mut resolved_items = Vec::new();
+            for item in items {
+                resolved_items.push(resolve_macro_type_args(ctx, item, type_scope, loc)?);
+            }
+            LoType::Tuple(resolved_items)
+        }
+        LoType::Result { ok_type, err_type } => LoType::Result {
+            ok_type: Box::new(resolve_macro_type_args(ctx, ok_type, type_scope, loc)?),
+            err_type: Box::new(resolve_macro_type_args(ctx, err_type, type_scope, loc)?),
+        },
+        LoType::StructInstance { name } if has_macro_type_args(ctx, lo_type) => {
+            let struct_def = ctx.get_struct_def(name).unwrap(); // safe
+            let generic_struct_name = struct_def.generic_struct_name.as_ref().unwrap(); // safe
+            let generic_def = ctx.generic_struct_defs.get(generic_struct_name).unwrap(); // safe
+
+            let mut type_args = Vec::new();
+            for type_arg in &struct_def.type_args {
+                type_args.push(resolve_macro_type_args(ctx, type_arg, type_scope, loc)?);
+            }
+
+            let instance = get_struct_instance(ctx, generic_def, type_args, loc)?;
+            LoType::StructInstance {
+                name: instance.name.clone(),
+            }
+        }
+        LoType::MacroTypeArg { name } => {
+            if let Some(t) = type_scope.get(name) {
+                return Ok(t.clone());
+            }
+            unreachable!();
+        }
+        _ => lo_type.clone(),
+    })
+}
+
 fn parse_memory(
     ctx: &mut ModuleContext,
     tokens: &mut LoTokenStream,
@@ -660,6 +904,34 @@ fn parse_fn_def(
     tokens: &mut LoTokenStream,
     exported: bool,
 ) -> Result<(), LoError> {
+    let fn_start = tokens.index;
+    let fn_name = parse_nested_symbol(tokens)?;
+
+    // methods of generic structs are instantiated per receiver unless `self` is typed
+    if let Some((generic_def, method_name)) = get_generic_receiver(ctx, &fn_name) {
+        let has_typed_self = tokens
+            .tokens
+            .get(tokens.index + 1)
+            .is_some_and(|t| t.is(Symbol, RECEIVER_PARAM_NAME))
+            && tokens
+                .tokens
+                .get(tokens.index + 2)
+                .is_some_and(|t| t.is(Operator, ":"));
+
+        if !has_typed_self {
+            let struct_name = generic_def.name.clone();
+            return parse_generic_method_def(
+                ctx,
+                tokens,
+                fn_name,
+                struct_name,
+                method_name,
+                exported,
+            );
+        }
+    }
+    tokens.index = fn_start;
+
     let fn_decl = parse_fn_decl(ctx, tokens)?;
     let body = collect_block_tokens(tokens)?;
 
@@ -677,35 +949,161 @@ fn parse_fn_def(
         });
     }
 
+    let fn_name = fn_decl.fn_name.clone();
+    let fn_def = add_fn_body(ctx, fn_decl, BTreeMap::new(), None, body);
+    ctx.fn_defs.insert(fn_name, fn_def);
+
+    return Ok(());
+}
+
+fn add_fn_body(
+    ctx: &ModuleContext,
+    fn_decl: FnDecl,
+    type_args: BTreeMap<String, LoType>,
+    instantiated_at: Option<(String, LoLocation)>,
+    body: LoTokenStream,
+) -> FnDef {
     let locals_last_index = fn_decl.wasm_type.inputs.len() as u32;
     let type_index = ctx.insert_fn_type(fn_decl.wasm_type);
     ctx.wasm_module.borrow_mut().functions.push(type_index);
 
     let fn_index = ctx.wasm_module.borrow_mut().functions.len() as u32 - 1;
 
-    ctx.fn_defs.insert(
-        fn_decl.fn_name,
-        FnDef {
-            local: true,
-            fn_index,
-            fn_params: fn_decl.fn_params,
-            type_index,
-            type_: fn_decl.lo_type,
-            loc: fn_decl.loc,
-        },
-    );
-
     ctx.fn_bodies.borrow_mut().push(FnBody {
         fn_index,
         type_index,
         locals: fn_decl.locals,
         locals_last_index,
+        type_args,
+        instantiated_at,
         body,
     });
 
+    FnDef {
+        local: true,
+        fn_index,
+        fn_params: fn_decl.fn_params,
+        type_index,
+        type_: fn_decl.lo_type,
+        loc: fn_decl.loc,
+    }
+}
+
+fn parse_generic_method_def(
+    ctx: &mut ModuleContext,
+    tokens: &mut LoTokenStream,
+    fn_name: LoToken,
+    struct_name: String,
+    method_name: String,
+    exported: bool,
+) -> Result<(), LoError> {
+    if exported {
+        return Err(LoError {
+            message: format!("Cannot export generic method: {}", fn_name.value),
+            loc: fn_name.loc,
+        });
+    }
+
+    if ctx.generic_methods.contains_key(&fn_name.value) {
+        return Err(LoError {
+            message: format!("Cannot redefine function: {}", fn_name.value),
+            loc: fn_name.loc,
+        });
+    }
+
+    // params and return type are parsed when the method is instantiated
+    let method_start = tokens.index;
+    while !tokens.next_is(Delim, "{")? {
+        tokens.next();
+    }
+    collect_block_tokens(tokens)?;
+    let method_tokens = LoTokenStream::new(
+        tokens.tokens[method_start..tokens.index].to_vec(),
+        tokens.loc().clone(),
+    );
+
+    ctx.generic_methods.insert(
+        fn_name.value,
+        GenericMethodDef {
+            struct_name,
+            method_name,
+            tokens: method_tokens,
+            loc: fn_name.loc,
+        },
+    );
+
     return Ok(());
 }
 
+fn get_generic_method_instance(
+    ctx: &ModuleContext,
+    receiver_type: &LoType,
+    method_name: &str,
+    loc: &LoLocation,
+) -> Result<Option<FnDef>, LoError> {
+    let LoType::StructInstance { name: struct_name } = receiver_type.deref_rec() else {
+        return Ok(None);
+    };
+
+    let struct_def = ctx.get_struct_def(struct_name).unwrap(); // safe
+    let Some(generic_struct_name) = &struct_def.generic_struct_name else {
+        return Ok(None);
+    };
+
+    let generic_method_name = format!("{generic_struct_name}::{method_name}");
+    let Some(method_def) = ctx.generic_methods.get(&generic_method_name) else {
+        return Ok(None);
+    };
+
+    let fn_name = format!("{struct_name}::{method_name}");
+    if let Some(fn_def) = ctx.generic_method_instances.borrow().get(&fn_name) {
+        return Ok(Some(fn_def.clone()));
+    }
+
+    let generic_def = ctx.generic_struct_defs.get(generic_struct_name).unwrap(); // safe
+    let mut type_args = BTreeMap::new();
+    for (type_param, type_arg) in generic_def.type_params.iter().zip(&struct_def.type_args) {
+        type_args.insert(type_param.clone(), type_arg.clone());
+    }
+
+    let type_scope = LoTypeScope {
+        types: type_args.clone(),
+        parent: Some(&ctx.type_scope),
+    };
+    let receiver_type = Some(LoType::StructInstance {
+        name: struct_name.clone(),
+    });
+
+    let mut tokens = method_def.tokens.clone();
+    let (fn_decl, body) = parse_fn_params(ctx, &type_scope, &mut tokens, &receiver_type)
+        .and_then(|params| {
+            let fn_decl = build_fn_decl(
+                ctx,
+                &type_scope,
+                &mut tokens,
+                fn_name.clone(),
+                method_def.method_name.clone(),
+                method_def.loc.clone(),
+                params,
+            )?;
+            let body = collect_block_tokens(&mut tokens)?;
+
+            Ok((fn_decl, body))
+        })
+        .map_err(|err| LoError {
+            message: format!("{}, in {fn_name} instantiated at {loc}", err.message),
+            loc: err.loc,
+        })?;
+
+    let instantiated_at = Some((fn_name.clone(), loc.clone()));
+    let fn_def = add_fn_body(ctx, fn_decl, type_args, instantiated_at, body);
+    ctx.generic_method_instances
+        .borrow_mut()
+        .insert(fn_name, fn_def.clone());
+
+    Ok(Some(fn_def))
+}
+
 fn parse_macro_def(ctx: &mut ModuleContext, tokens: &mut LoTokenStream) -> Result<(), LoError> {
     let macro_name = parse_nested_symbol(tokens)?;
     tokens.expect(Operator, "!")?;
@@ -717,49 +1115,38 @@ fn parse_macro_def(ctx: &mut ModuleContext, tokens: &mut LoTokenStream) -> Resul
         });
     }
 
-    let (receiver_type, method_name) = extract_method_receiver_and_name(ctx, &macro_name)?;
-    let mut type_params = Vec::<String>::new();
-
-    if let Some(_) = tokens.eat(Operator, "<")? {
-        while let None = tokens.eat(Operator, ">")? {
-            let p_name = tokens.expect_any(Symbol)?.clone();
-            if !tokens.next_is(Operator, ">")? {
-                tokens.expect(Delim, ",")?;
-            }
-
-            if get_type_by_name(ctx, &ctx.type_scope, &p_name, false).is_ok() {
-                return Err(LoError {
-                    message: format!("Type parameter shadows existing type: {}", p_name.value),
-                    loc: p_name.loc.clone(),
-                });
-            }
-
-            for param in &type_params {
-                if *param == p_name.value {
-                    return Err(LoError {
-                        message: format!("Found duplicate type parameter: {}", p_name.value),
-                        loc: p_name.loc.clone(),
-                    });
-                }
-            }
-
-            type_params.push(p_name.value);
-        }
-    }
+    let type_params = if let Some(_) = tokens.eat(Operator, "<")? {
+        parse_type_params(ctx, &ctx.type_scope, tokens)?
+    } else {
+        Vec::new()
+    };
 
     let mut new_type_scope = LoTypeScope {
         parent: Some(&ctx.type_scope),
         ..Default::default()
     };
+    let mut macro_type_args = Vec::new();
     for type_param in &type_params {
-        new_type_scope.insert(
-            type_param.clone(),
-            LoType::MacroTypeArg {
-                name: type_param.clone(),
-            },
-        )
+        let macro_type_arg = LoType::MacroTypeArg {
+            name: type_param.clone(),
+        };
+        new_type_scope.insert(type_param.clone(), macro_type_arg.clone());
+        macro_type_args.push(macro_type_arg);
     }
 
+    let (receiver_type, method_name) =
+        if let Some((generic_def, method_name)) = get_generic_receiver(ctx, &macro_name) {
+            // `self` of a generic struct's macro is instantiated with the macro's type params
+            let receiver = get_struct_instance(ctx, generic_def, macro_type_args, &macro_name.loc)?;
+            let receiver_type = LoType::StructInstance {
+                name: receiver.name.clone(),
+            };
+
+            (Some(receiver_type), method_name)
+        } else {
+            extract_method_receiver_and_name(ctx, &macro_name)?
+        };
+
     let params = parse_fn_params(ctx, &new_type_scope, tokens, &receiver_type)?;
     let return_type = if let Some(_) = tokens.eat(Operator, ":")? {
         parse_lo_type_(ctx, &new_type_scope, tokens, false)?
@@ -784,6 +1171,43 @@ fn parse_macro_def(ctx: &mut ModuleContext, tokens: &mut LoTokenStream) -> Resul
     return Ok(());
 }
 
+fn parse_type_params(
+    ctx: &ModuleContext,
+    type_scope: &LoTypeScope,
+    tokens: &mut LoTokenStream,
+) -> Result<Vec<String>, LoError> {
+    let mut type_params = Vec::<String>::new();
+
+    while !tokens.eat_type_list_end()? {
+        let p_name = tokens.expect_any(Symbol)?.clone();
+        if !tokens.next_is_type_list_end()? {
+            tokens.expect(Delim, ",")?;
+        }
+
+        if get_type_by_name(ctx, type_scope, &p_name, false).is_ok()
+            || ctx.generic_struct_defs.contains_key(&p_name.value)
+        {
+            return Err(LoError {
+                message: format!("Type parameter shadows existing type: {}", p_name.value),
+                loc: p_name.loc.clone(),
+            });
+        }
+
+        for param in &type_params {
+            if *param == p_name.value {
+                return Err(LoError {
+                    message: format!("Found duplicate type parameter: {}", p_name.value),
+                    loc: p_name.loc.clone(),
+                });
+            }
+        }
+
+        type_params.push(p_name.value);
+    }
+
+    Ok(type_params)
+}
+
 struct FnDecl {
     fn_name: String,
     method_name: String,
@@ -794,17 +1218,73 @@ struct FnDecl {
     locals: BTreeMap<String, LocalDef>,
 }
 
-fn parse_fn_decl(ctx: &mut ModuleContext, tokens: &mut LoTokenStream) -> Result<FnDecl, LoError> {
+fn parse_fn_decl(ctx: &ModuleContext, tokens: &mut LoTokenStream) -> Result<FnDecl, LoError> {
     let fn_name = parse_nested_symbol(tokens)?;
-    let (receiver_type, method_name) = extract_method_receiver_and_name(ctx, &fn_name)?;
 
+    // methods of generic structs with a typed `self` are defined for a single instance
+    if let Some((generic_def, method_name)) = get_generic_receiver(ctx, &fn_name) {
+        let params = parse_fn_params(ctx, &ctx.type_scope, tokens, &None)?;
+
+        let self_struct_def = match params.first() {
+            Some(FnParam { name, type_, .. }) if name == RECEIVER_PARAM_NAME => {
+                match type_.deref_rec() {
+                    LoType::StructInstance { name } => ctx.get_struct_def(name),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let Some(self_struct_def) =
+            self_struct_def.filter(|s| s.generic_struct_name.as_ref() == Some(&generic_def.name))
+        else {
+            return Err(LoError {
+                message: format!(
+                    "Typed `self` param of {} must be an instance of {}",
+                    fn_name.value, generic_def.name
+                ),
+                loc: fn_name.loc,
+            });
+        };
+
+        return build_fn_decl(
+            ctx,
+            &ctx.type_scope,
+            tokens,
+            format!("{}::{method_name}", self_struct_def.name),
+            method_name,
+            fn_name.loc,
+            params,
+        );
+    }
+
+    let (receiver_type, method_name) = extract_method_receiver_and_name(ctx, &fn_name)?;
     let params = parse_fn_params(ctx, &ctx.type_scope, tokens, &receiver_type)?;
 
+    build_fn_decl(
+        ctx,
+        &ctx.type_scope,
+        tokens,
+        fn_name.value,
+        method_name,
+        fn_name.loc,
+        params,
+    )
+}
+
+fn build_fn_decl(
+    ctx: &ModuleContext,
+    type_scope: &LoTypeScope,
+    tokens: &mut LoTokenStream,
+    fn_name: String,
+    method_name: String,
+    loc: LoLocation,
+    params: Vec<FnParam>,
+) -> Result<FnDecl, LoError> {
     let mut fn_decl = FnDecl {
-        fn_name: fn_name.value.clone(),
+        fn_name,
         fn_params: params.clone(),
         method_name,
-        loc: fn_name.loc.clone(),
+        loc,
         lo_type: LoFnType {
             inputs: vec![],
             output: LoType::Void,
@@ -832,7 +1312,7 @@ fn parse_fn_decl(ctx: &mut ModuleContext, tokens: &mut LoTokenStream) -> Result<
     }
 
     let lo_output = if let Some(_) = tokens.eat(Operator, ":")? {
-        parse_const_lo_type(ctx, tokens)?
+        parse_lo_type_(ctx, type_scope, tokens, false)?
     } else {
         LoType::Void
     };
@@ -972,6 +1452,10 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
         return parse_const_int(tokens);
     }
 
+    if tokens.next_is_any(FloatLiteral)? {
+        return parse_const_float(tokens);
+    }
+
     if let Some(value) = tokens.eat_any(CharLiteral)? {
         return Ok(LoInstr::U32Const {
             value: Lexer::parse_char_literal_value(&value.value),
@@ -1188,11 +1672,28 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
         });
     }
 
+    let mut loop_label = None;
+    if let Some(label) = tokens.eat_any(Label)?.cloned() {
+        tokens.expect(Operator, ":")?;
+        if !tokens.next_is(Symbol, "loop")?
+            && !tokens.next_is(Symbol, "for")?
+            && !tokens.next_is(Symbol, "while")?
+        {
+            return Err(LoError {
+                message: format!("Only loops can be labeled"),
+                loc: label.loc,
+            });
+        }
+        loop_label = Some(label.value);
+    }
+
     if let Some(_) = tokens.eat(Symbol, "loop")? {
         let mut ctx = BlockContext {
             module: ctx.module,
             fn_ctx: ctx.fn_ctx,
-            block: Block::child_of(ctx.module, &ctx.block).of_kind(LoBlockKind::Loop),
+            block: Block::child_of(ctx.module, &ctx.block)
+                .of_kind(LoBlockKind::Loop)
+                .with_loop_label(loop_label),
         };
 
         let mut body = parse_block(&mut ctx, tokens)?;
@@ -1200,12 +1701,20 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
         let implicit_continue = LoInstr::Branch { label_index: 0 };
         body.push(implicit_continue);
 
-        return Ok(LoInstr::Block {
+        let value_type = get_loop_value_type(&ctx.block)?;
+
+        let mut block_body = vec![LoInstr::Loop {
             block_type: LoBlockType::void(),
-            body: vec![LoInstr::Loop {
-                block_type: LoBlockType::void(),
-                body,
-            }],
+            body,
+        }];
+        // the value is only produced by `break`, the loop never falls through
+        if value_type != LoType::Void {
+            block_body.push(LoInstr::Unreachable);
+        }
+
+        return Ok(LoInstr::Block {
+            block_type: LoBlockType::in_out(ctx.module, &[], &value_type),
+            body: block_body,
         });
     }
 
@@ -1219,7 +1728,12 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
         };
 
         let start_count = parse_expr(counter_ctx, tokens, 0)?;
-        tokens.expect(Operator, "..")?;
+        let is_inclusive = if let Some(_) = tokens.eat(Operator, "..=")? {
+            true
+        } else {
+            tokens.expect(Operator, "..")?;
+            false
+        };
         let end_count = parse_expr(counter_ctx, tokens, 0)?;
 
         let counter_type = start_count.get_type(counter_ctx.module);
@@ -1233,28 +1747,116 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
             });
         }
 
-        let check_op_kind;
-        let add_op_kind;
-        let step_instr;
-        match counter_type {
-            LoType::Bool | LoType::I8 | LoType::U8 | LoType::I32 | LoType::U32 => {
-                check_op_kind = WasmBinaryOpKind::I32_EQ;
-                add_op_kind = WasmBinaryOpKind::I32_ADD;
-                step_instr = LoInstr::U32Const { value: 1 };
-            }
-            LoType::I64 | LoType::U64 => {
-                check_op_kind = WasmBinaryOpKind::I64_EQ;
-                add_op_kind = WasmBinaryOpKind::I64_ADD;
-                step_instr = LoInstr::U64Const { value: 1 };
-            }
-            _ => {
-                return Err(LoError {
-                    message: format!("Invalid counter type: {counter_type}",),
-                    loc: for_loop.loc,
-                })
-            }
-        };
-
+        let is_64_bit;
+        let is_signed;
+        match counter_type {
+            LoType::Bool | LoType::U8 | LoType::U32 => {
+                is_64_bit = false;
+                is_signed = false;
+            }
+            LoType::I8 | LoType::I32 => {
+                is_64_bit = false;
+                is_signed = true;
+            }
+            LoType::U64 => {
+                is_64_bit = true;
+                is_signed = false;
+            }
+            LoType::I64 => {
+                is_64_bit = true;
+                is_signed = true;
+            }
+            _ => {
+                return Err(LoError {
+                    message: format!("Invalid counter type: {counter_type}",),
+                    loc: for_loop.loc,
+                })
+            }
+        };
+
+        let mut step = 1;
+        let mut is_descending = false;
+        if let Some(_) = tokens.eat(Symbol, "by")? {
+            let step_loc = tokens.peek().unwrap().loc.clone();
+
+            is_descending = tokens.eat(Operator, "-")?.is_some();
+            let Some(step_token) = tokens.eat_any(IntLiteral)? else {
+                return Err(LoError {
+                    message: format!("Range step must be an integer literal"),
+                    loc: step_loc,
+                });
+            };
+            step = Lexer::parse_int_literal_value(&step_token.value);
+
+            if step == 0 {
+                return Err(LoError {
+                    message: format!("Range step cannot be zero"),
+                    loc: step_loc,
+                });
+            }
+
+            if !is_64_bit && step > u32::MAX as u64 {
+                return Err(LoError {
+                    message: format!("Range step is out of range for {counter_type}"),
+                    loc: step_loc,
+                });
+            }
+        }
+
+        // distance to the end is compared unsigned so it works for the whole range of any type
+        let past_end_op_kind;
+        let last_item_op_kind;
+        let sub_op_kind;
+        let step_op_kind;
+        let step_instr;
+        if is_64_bit {
+            past_end_op_kind = match (is_descending, is_inclusive, is_signed) {
+                (false, false, false) => WasmBinaryOpKind::I64_GE_U,
+                (false, false, true) => WasmBinaryOpKind::I64_GE_S,
+                (false, true, false) => WasmBinaryOpKind::I64_GT_U,
+                (false, true, true) => WasmBinaryOpKind::I64_GT_S,
+                (true, false, false) => WasmBinaryOpKind::I64_LE_U,
+                (true, false, true) => WasmBinaryOpKind::I64_LE_S,
+                (true, true, false) => WasmBinaryOpKind::I64_LT_U,
+                (true, true, true) => WasmBinaryOpKind::I64_LT_S,
+            };
+            last_item_op_kind = if is_inclusive {
+                WasmBinaryOpKind::I64_LT_U
+            } else {
+                WasmBinaryOpKind::I64_LE_U
+            };
+            sub_op_kind = WasmBinaryOpKind::I64_SUB;
+            step_op_kind = if is_descending {
+                WasmBinaryOpKind::I64_SUB
+            } else {
+                WasmBinaryOpKind::I64_ADD
+            };
+            step_instr = LoInstr::U64Const { value: step };
+        } else {
+            past_end_op_kind = match (is_descending, is_inclusive, is_signed) {
+                (false, false, false) => WasmBinaryOpKind::I32_GE_U,
+                (false, false, true) => WasmBinaryOpKind::I32_GE_S,
+                (false, true, false) => WasmBinaryOpKind::I32_GT_U,
+                (false, true, true) => WasmBinaryOpKind::I32_GT_S,
+                (true, false, false) => WasmBinaryOpKind::I32_LE_U,
+                (true, false, true) => WasmBinaryOpKind::I32_LE_S,
+                (true, true, false) => WasmBinaryOpKind::I32_LT_U,
+                (true, true, true) => WasmBinaryOpKind::I32_LT_S,
+            };
+            last_item_op_kind = if is_inclusive {
+                WasmBinaryOpKind::I32_LT_U
+            } else {
+                WasmBinaryOpKind::I32_LE_U
+            };
+            sub_op_kind = WasmBinaryOpKind::I32_SUB;
+            step_op_kind = if is_descending {
+                WasmBinaryOpKind::I32_SUB
+            } else {
+                WasmBinaryOpKind::I32_ADD
+            };
+            step_instr = LoInstr::U32Const { value: step as u32 };
+        }
+
         let init_instr = define_local(counter_ctx, &counter, start_count, counter_type.clone())?;
         let get_counter_instr = LoInstr::LocalGet {
             local_index: counter_ctx
@@ -1265,23 +1867,57 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
             value_type: counter_type.clone(),
         };
 
-        let break_instr = LoInstr::Branch { label_index: 2 };
-        let implicit_continue = LoInstr::Branch { label_index: 0 };
+        // end is evaluated once
+        let end_local_index = counter_ctx.fn_ctx.locals_last_index;
+        let comp_count = counter_type.emit_components(
+            &counter_ctx.module,
+            &mut counter_ctx.fn_ctx.non_arg_wasm_locals,
+        );
+        counter_ctx.fn_ctx.locals_last_index += comp_count;
+        let get_end_instr = LoInstr::LocalGet {
+            local_index: end_local_index,
+            value_type: counter_type.clone(),
+        };
+        let init_end_instr =
+            compile_set(counter_ctx, end_count, get_end_instr.clone(), &for_loop.loc)?;
+
+        let (distance_lhs, distance_rhs) = if is_descending {
+            (get_counter_instr.clone(), get_end_instr.clone())
+        } else {
+            (get_end_instr.clone(), get_counter_instr.clone())
+        };
 
-        let end_check_instr = LoInstr::If {
+        let skip_empty_instr = LoInstr::If {
             block_type: LoBlockType::void(),
             cond: Box::new(LoInstr::BinaryOp {
-                kind: check_op_kind,
+                kind: past_end_op_kind,
                 lhs: Box::new(get_counter_instr.clone()),
-                rhs: Box::new(end_count),
+                rhs: Box::new(get_end_instr),
+            }),
+            then_branch: vec![LoInstr::Branch { label_index: 1 }],
+            else_branch: None,
+        };
+        // break on the last item, so the counter never steps past the end and overflows
+        let last_item_check_instr = LoInstr::If {
+            block_type: LoBlockType::void(),
+            cond: Box::new(LoInstr::BinaryOp {
+                kind: last_item_op_kind,
+                lhs: Box::new(LoInstr::BinaryOp {
+                    kind: sub_op_kind,
+                    lhs: Box::new(distance_lhs),
+                    rhs: Box::new(distance_rhs),
+                }),
+                rhs: Box::new(step_instr.clone()),
             }),
-            then_branch: vec![break_instr],
+            then_branch: vec![LoInstr::Branch { label_index: 2 }],
             else_branch: None,
         };
+        let implicit_continue = LoInstr::Branch { label_index: 0 };
+
         let update_instr = compile_set(
             counter_ctx,
             LoInstr::BinaryOp {
-                kind: add_op_kind,
+                kind: step_op_kind,
                 lhs: Box::new(get_counter_instr.clone()),
                 rhs: Box::new(step_instr),
             },
@@ -1292,83 +1928,130 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
         let loop_body_ctx = &mut BlockContext {
             module: counter_ctx.module,
             fn_ctx: counter_ctx.fn_ctx,
-            block: Block::child_of(ctx.module, &counter_ctx.block).of_kind(LoBlockKind::ForLoop),
+            block: Block::child_of(ctx.module, &counter_ctx.block)
+                .of_kind(LoBlockKind::ForLoop)
+                .with_loop_label(loop_label),
         };
         let loop_body = parse_block(loop_body_ctx, tokens)?;
 
         let instrs = vec![
             init_instr,
+            init_end_instr,
             LoInstr::Block {
                 block_type: LoBlockType::void(),
-                body: vec![LoInstr::Loop {
-                    body: vec![
-                        end_check_instr,
-                        LoInstr::Block {
-                            block_type: LoBlockType::void(),
-                            body: loop_body,
-                        },
-                        update_instr,
-                        implicit_continue,
-                    ],
-                    block_type: LoBlockType::void(),
-                }],
+                body: vec![
+                    skip_empty_instr,
+                    LoInstr::Loop {
+                        body: vec![
+                            LoInstr::Block {
+                                block_type: LoBlockType::void(),
+                                body: loop_body,
+                            },
+                            last_item_check_instr,
+                            update_instr,
+                            implicit_continue,
+                        ],
+                        block_type: LoBlockType::void(),
+                    },
+                ],
             },
         ];
 
         return Ok(LoInstr::MultiValueEmit { values: instrs }.casted(LoType::Void));
     }
 
-    if let Some(break_token) = tokens.eat(Symbol, "break")? {
-        let mut label_index = 1; // 0 = loop, 1 = loop wrapper block
+    if let Some(_) = tokens.eat(Symbol, "while")? {
+        let cond_loc = tokens.peek().unwrap().loc.clone();
+        let cond = parse_expr(ctx, tokens, 0)?;
 
-        let mut current_block = &ctx.block;
-        loop {
-            if current_block.block_kind == LoBlockKind::Function {
-                return Err(LoError {
-                    message: format!("Cannot break outside of a loop"),
-                    loc: break_token.loc.clone(),
-                });
-            }
+        let cond_type = cond.get_type(ctx.module);
+        if cond_type != LoType::Bool {
+            return Err(LoError {
+                message: format!(
+                    "Invalid while condition type: {cond_type}, expected: {}",
+                    LoType::Bool
+                ),
+                loc: cond_loc,
+            });
+        }
 
-            if current_block.block_kind == LoBlockKind::Loop {
-                break;
-            }
+        let mut ctx = BlockContext {
+            module: ctx.module,
+            fn_ctx: ctx.fn_ctx,
+            block: Block::child_of(ctx.module, &ctx.block)
+                .of_kind(LoBlockKind::WhileLoop)
+                .with_loop_label(loop_label),
+        };
 
-            if current_block.block_kind == LoBlockKind::ForLoop {
-                label_index += 1;
-                break;
-            }
+        let mut body = vec![LoInstr::If {
+            block_type: LoBlockType::void(),
+            cond: Box::new(LoInstr::UnaryOp {
+                kind: WasmUnaryOpKind::I32_EQZ,
+                expr: Box::new(cond),
+            }),
+            then_branch: vec![LoInstr::Branch { label_index: 2 }],
+            else_branch: None,
+        }];
+        body.append(&mut parse_block(&mut ctx, tokens)?);
 
-            current_block = current_block.parent.unwrap();
-            label_index += 1;
-        }
+        let implicit_continue = LoInstr::Branch { label_index: 0 };
+        body.push(implicit_continue);
 
-        return Ok(LoInstr::Branch { label_index });
+        return Ok(LoInstr::Block {
+            block_type: LoBlockType::void(),
+            body: vec![LoInstr::Loop {
+                block_type: LoBlockType::void(),
+                body,
+            }],
+        });
     }
 
-    if let Some(continue_token) = tokens.eat(Symbol, "continue")? {
-        let mut label_index = 0; // 0 = loop, 1 = loop wrapper block
+    if let Some(break_token) = tokens.eat(Symbol, "break")?.cloned() {
+        let label = tokens.eat_any(Label)?.map(|label| label.value.clone());
 
-        let mut current_block = &ctx.block;
-        loop {
-            if current_block.block_kind == LoBlockKind::Function {
-                return Err(LoError {
-                    message: format!("Cannot continue outside of a loop"),
-                    loc: continue_token.loc.clone(),
-                });
-            }
+        if tokens.next_is(Delim, ";")? || tokens.next_is(Delim, "}")? {
+            let (loop_block, label_index) =
+                get_loop_branch_target(&ctx.block, &label, true, &break_token.loc)?;
+            loop_block
+                .loop_breaks
+                .borrow_mut()
+                .push((LoType::Void, break_token.loc));
 
-            if current_block.block_kind == LoBlockKind::Loop {
-                break;
-            }
+            return Ok(LoInstr::Branch { label_index });
+        }
 
-            if current_block.block_kind == LoBlockKind::ForLoop {
-                break;
-            }
+        let value_loc = tokens.peek().unwrap().loc.clone();
+        let value = parse_expr(ctx, tokens, 0)?;
+
+        let (loop_block, label_index) =
+            get_loop_branch_target(&ctx.block, &label, true, &break_token.loc)?;
+        if loop_block.block_kind != LoBlockKind::Loop {
+            let loop_kind = if loop_block.block_kind == LoBlockKind::WhileLoop {
+                "while"
+            } else {
+                "for"
+            };
+            return Err(LoError {
+                message: format!("Cannot break with a value out of a {loop_kind} loop"),
+                loc: value_loc,
+            });
+        }
+        loop_block
+            .loop_breaks
+            .borrow_mut()
+            .push((value.get_type(ctx.module), value_loc));
 
-            current_block = current_block.parent.unwrap();
-            label_index += 1;
+        return Ok(LoInstr::MultiValueEmit {
+            values: vec![value, LoInstr::Branch { label_index }],
         }
+        .casted(LoType::Void));
+    }
+
+    if let Some(continue_token) = tokens.eat(Symbol, "continue")?.cloned() {
+        let label = tokens.eat_any(Label)?.map(|label| label.value.clone());
+
+        let (_, label_index) =
+            get_loop_branch_target(&ctx.block, &label, false, &continue_token.loc)?;
 
         return Ok(LoInstr::Branch { label_index });
     }
@@ -1504,6 +2187,10 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
                         rhs: Box::new(LoInstr::U32Const { value: 0 }),
                     });
                 }
+                PrefixOpTag::BitNot => {
+                    let value = parse_expr(ctx, tokens, min_bp)?;
+                    return bit_not(value, ctx.module, &op.token.loc);
+                }
                 PrefixOpTag::Positive => {
                     let value = parse_expr(ctx, tokens, min_bp + 1)?;
                     return cast_to_signed(value, &op.token.loc);
@@ -1533,7 +2220,24 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
     }
 
     if let Some(_) = tokens.eat(Operator, ".")? {
-        let struct_name = parse_nested_symbol(tokens)?;
+        let mut struct_name = parse_nested_symbol(tokens)?;
+
+        if let Some(generic_def) = ctx.module.generic_struct_defs.get(&struct_name.value) {
+            let mut type_args = Vec::new();
+            if let Some(_) = tokens.eat(Operator, "<")? {
+                while !tokens.eat_type_list_end()? {
+                    type_args.push(parse_lo_type(ctx, tokens)?);
+                    if !tokens.next_is_type_list_end()? {
+                        tokens.expect(Delim, ",")?;
+                    }
+                }
+            }
+
+            let struct_def =
+                get_struct_instance(ctx.module, generic_def, type_args, &struct_name.loc)?;
+            struct_name.value = struct_def.name.clone();
+        }
+
         let Some(struct_def) = ctx.module.get_struct_def(&struct_name.value) else {
             return Err(LoError {
                 message: format!("Can not create unknown struct: {}", struct_name.value),
@@ -1541,7 +2245,7 @@ fn parse_primary(ctx: &mut BlockContext, tokens: &mut LoTokenStream) -> Result<L
             });
         };
 
-        return parse_struct_literal(ctx, tokens, struct_name, struct_def);
+        return parse_struct_literal(ctx, tokens, struct_name, &struct_def);
     }
 
     let value = parse_nested_symbol(tokens)?;
@@ -1747,6 +2451,7 @@ fn cast_to_signed(value: LoInstr, loc: &LoLocation) -> Result<LoInstr, LoError>
                 value: value as i64,
             })
         }
+        LoInstr::F32Const { .. } | LoInstr::F64Const { .. } => return Ok(value),
         _ => {
             return Err(LoError {
                 message: format!("Cannot cast this expression to signed integer"),
@@ -1768,6 +2473,8 @@ fn negate(value: LoInstr, loc: &LoLocation) -> Result<LoInstr, LoError> {
                 value: -(value as i64),
             })
         }
+        LoInstr::F32Const { value } => return Ok(LoInstr::F32Const { value: -value }),
+        LoInstr::F64Const { value } => return Ok(LoInstr::F64Const { value: -value }),
         _ => {
             return Err(LoError {
                 message: format!("Cannot negate this expression"),
@@ -1777,6 +2484,33 @@ fn negate(value: LoInstr, loc: &LoLocation) -> Result<LoInstr, LoError> {
     }
 }
 
+fn bit_not(value: LoInstr, ctx: &ModuleContext, loc: &LoLocation) -> Result<LoInstr, LoError> {
+    let (kind, mask) = match value.get_type(ctx) {
+        // narrow unsigned values are kept zero-extended
+        LoType::U8 => (WasmBinaryOpKind::I32_XOR, LoInstr::U32Const { value: 0xFF }),
+        LoType::U16 => (
+            WasmBinaryOpKind::I32_XOR,
+            LoInstr::U32Const { value: 0xFFFF },
+        ),
+        LoType::I8 | LoType::I16 | LoType::I32 | LoType::U32 => {
+            (WasmBinaryOpKind::I32_XOR, LoInstr::I32Const { value: -1 })
+        }
+        LoType::I64 | LoType::U64 => (WasmBinaryOpKind::I64_XOR, LoInstr::I64Const { value: -1 }),
+        value_type => {
+            return Err(LoError {
+                message: format!("Cannot apply `~` to value of type {value_type}"),
+                loc: loc.clone(),
+            });
+        }
+    };
+
+    Ok(LoInstr::BinaryOp {
+        kind,
+        lhs: Box::new(value),
+        rhs: Box::new(mask),
+    })
+}
+
 fn define_local(
     ctx: &mut BlockContext,
     local_name: &LoToken,
@@ -1830,9 +2564,25 @@ fn parse_macro_call(
     macro_token: &LoToken,
     receiver: Option<LoInstr>,
 ) -> Result<LoInstr, LoError> {
-    let macro_name = if let Some(receiver) = &receiver {
-        let receiver_type = receiver.get_type(ctx.module);
-        get_fn_name_from_method(&receiver_type, &macro_token.value)
+    let receiver_type = receiver.as_ref().map(|r| r.get_type(ctx.module));
+    let receiver_struct_def = match receiver_type.as_ref().map(|t| t.deref_rec()) {
+        Some(LoType::StructInstance { name }) => ctx.module.get_struct_def(name),
+        _ => None,
+    };
+
+    let macro_name = if let Some(receiver_type) = &receiver_type {
+        let macro_name = get_fn_name_from_method(&receiver_type, &macro_token.value);
+
+        // macros of generic structs are defined once for all instances
+        match receiver_struct_def
+            .as_ref()
+            .and_then(|s| s.generic_struct_name.as_ref())
+        {
+            Some(generic_struct_name) if !ctx.module.macros.contains_key(&macro_name) => {
+                format!("{generic_struct_name}::{}", macro_token.value)
+            }
+            _ => macro_name,
+        }
     } else {
         macro_token.value.clone()
     };
@@ -1848,15 +2598,22 @@ fn parse_macro_call(
         let mut type_args = Vec::new();
 
         if let Some(_) = tokens.eat(Operator, "<")? {
-            while let None = tokens.eat(Operator, ">")? {
+            while !tokens.eat_type_list_end()? {
                 let macro_arg = parse_lo_type(ctx, tokens)?;
                 type_args.push(macro_arg);
-                if !tokens.next_is(Operator, ">")? {
+                if !tokens.next_is_type_list_end()? {
                     tokens.expect(Delim, ",")?;
                 }
             }
         }
 
+        // macros of generic structs take type args from the receiver if none are provided
+        if let Some(receiver_struct_def) = &receiver_struct_def {
+            if type_args.len() == 0 && receiver_struct_def.generic_struct_name.is_some() {
+                type_args = receiver_struct_def.type_args.clone();
+            }
+        }
+
         if type_args.len() != macro_def.type_params.len() {
             return Err(LoError {
                 message: format!(
@@ -1875,7 +2632,12 @@ fn parse_macro_call(
 
         type_scope
     };
-    let return_type = macro_def.return_type.resolve_macro_type_args(&type_scope)?;
+    let return_type = resolve_macro_type_args(
+        ctx.module,
+        &macro_def.return_type,
+        &type_scope,
+        &macro_token.loc,
+    )?;
 
     let macro_args = {
         let mut args = vec![];
@@ -1886,7 +2648,12 @@ fn parse_macro_call(
 
         let mut params = Vec::new();
         for param in &macro_def.params {
-            params.push(param.type_.resolve_macro_type_args(&type_scope)?);
+            params.push(resolve_macro_type_args(
+                ctx.module,
+                &param.type_,
+                &type_scope,
+                &macro_token.loc,
+            )?);
         }
         typecheck_fn_call_args(ctx.module, &params, &args, &macro_name, &macro_token.loc)?;
 
@@ -2029,6 +2796,7 @@ fn parse_postfix(
         | InfixOpTag::Mod
         | InfixOpTag::BitAnd
         | InfixOpTag::BitOr
+        | InfixOpTag::BitXor
         | InfixOpTag::ShiftLeft
         | InfixOpTag::ShiftRight => {
             let lhs = primary;
@@ -2056,6 +2824,7 @@ fn parse_postfix(
         | InfixOpTag::ModAssign
         | InfixOpTag::BitAndAssign
         | InfixOpTag::BitOrAssign
+        | InfixOpTag::BitXorAssign
         | InfixOpTag::ShiftLeftAssign
         | InfixOpTag::ShiftRightAssign => {
             op.tag = get_op_additional_to_assign(&op.tag)?;
@@ -2103,7 +2872,36 @@ fn parse_postfix(
                 let receiver_type = primary.get_type(ctx.module);
 
                 let fn_name = get_fn_name_from_method(&receiver_type, &method_name.value);
-                let Some(fn_def) = ctx.module.fn_defs.get(&fn_name) else {
+                let fn_def = match ctx.module.get_fn_def(&fn_name) {
+                    Some(fn_def) => Some(fn_def),
+                    None => get_generic_method_instance(
+                        ctx.module,
+                        &receiver_type,
+                        &method_name.value,
+                        &method_name.loc,
+                    )?,
+                };
+                let Some(fn_def) = fn_def else {
+                    if let Some(arg_count) =
+                        get_bit_intrinsic_arg_count(&receiver_type, &method_name.value)
+                    {
+                        let mut args = vec![primary];
+                        parse_fn_call_args(ctx, tokens, &mut args)?;
+                        typecheck_fn_call_args(
+                            ctx.module,
+                            &vec![receiver_type.clone(); arg_count + 1],
+                            &args,
+                            &fn_name,
+                            &method_name.loc,
+                        )?;
+
+                        return Ok(build_bit_intrinsic(
+                            &receiver_type,
+                            &method_name.value,
+                            args,
+                        ));
+                    }
+
                     return Err(LoError {
                         message: format!("Unknown function: {fn_name}"),
                         loc: method_name.loc,
@@ -2299,6 +3097,12 @@ fn parse_postfix(
                 loc: field_name.loc,
             });
         }
+        InfixOpTag::Index => {
+            return Err(LoError {
+                message: format!("Indexing is not supported, use pointer arithmetic"),
+                loc: op.token.loc,
+            });
+        }
         InfixOpTag::Catch => parse_catch(ctx, tokens, primary, op, false)?,
         InfixOpTag::ErrorPropagation => parse_catch(ctx, tokens, primary, op, true)?,
     })
@@ -2474,6 +3278,7 @@ fn get_op_additional_to_assign(op: &InfixOpTag) -> Result<InfixOpTag, LoError> {
         InfixOpTag::ModAssign => Ok(InfixOpTag::Mod),
         InfixOpTag::BitAndAssign => Ok(InfixOpTag::BitAnd),
         InfixOpTag::BitOrAssign => Ok(InfixOpTag::BitOr),
+        InfixOpTag::BitXorAssign => Ok(InfixOpTag::BitXor),
         InfixOpTag::ShiftLeftAssign => Ok(InfixOpTag::ShiftLeft),
         InfixOpTag::ShiftRightAssign => Ok(InfixOpTag::ShiftRight),
         _ => unreachable!(),
@@ -2675,6 +3480,17 @@ fn get_binary_op(
             LoType::I64 | LoType::U64 => WasmBinaryOpKind::I64_OR,
             operand_type => return err_incompatible_op(op, operand_type),
         },
+        InfixOpTag::BitXor => match lhs_type {
+            LoType::Bool
+            | LoType::I8
+            | LoType::U8
+            | LoType::I16
+            | LoType::U16
+            | LoType::I32
+            | LoType::U32 => WasmBinaryOpKind::I32_XOR,
+            LoType::I64 | LoType::U64 => WasmBinaryOpKind::I64_XOR,
+            operand_type => return err_incompatible_op(op, operand_type),
+        },
         _ => unreachable!(),
     })
 }
@@ -2745,6 +3561,20 @@ fn build_cast(
         }
     }
 
+    if let Some(kind) = get_float_conversion_op(&actual_type, &wanted_type) {
+        return Ok(LoInstr::UnaryOp {
+            kind,
+            expr: Box::new(value),
+        });
+    }
+
+    if let Some(kind) = get_float_trunc_sat_op(&actual_type, &wanted_type) {
+        return Ok(LoInstr::TruncSat {
+            kind,
+            expr: Box::new(value),
+        });
+    }
+
     let mut actual_wasm_types = vec![];
     actual_type.emit_components(ctx, &mut actual_wasm_types);
 
@@ -2761,6 +3591,37 @@ fn build_cast(
     Ok(value.casted(wanted_type))
 }
 
+fn get_float_conversion_op(actual_type: &LoType, wanted_type: &LoType) -> Option<WasmUnaryOpKind> {
+    Some(match (actual_type, wanted_type) {
+        (LoType::I8 | LoType::I16 | LoType::I32, LoType::F32) => WasmUnaryOpKind::F32_CONVERT_I32_S,
+        (LoType::U8 | LoType::U16 | LoType::U32, LoType::F32) => WasmUnaryOpKind::F32_CONVERT_I32_U,
+        (LoType::I64, LoType::F32) => WasmUnaryOpKind::F32_CONVERT_I64_S,
+        (LoType::U64, LoType::F32) => WasmUnaryOpKind::F32_CONVERT_I64_U,
+        (LoType::F64, LoType::F32) => WasmUnaryOpKind::F32_DEMOTE_F64,
+        (LoType::I8 | LoType::I16 | LoType::I32, LoType::F64) => WasmUnaryOpKind::F64_CONVERT_I32_S,
+        (LoType::U8 | LoType::U16 | LoType::U32, LoType::F64) => WasmUnaryOpKind::F64_CONVERT_I32_U,
+        (LoType::I64, LoType::F64) => WasmUnaryOpKind::F64_CONVERT_I64_S,
+        (LoType::U64, LoType::F64) => WasmUnaryOpKind::F64_CONVERT_I64_U,
+        (LoType::F32, LoType::F64) => WasmUnaryOpKind::F64_PROMOTE_F32,
+        _ => return None,
+    })
+}
+
+// float to int casts saturate instead of trapping on NaN and out of range values
+fn get_float_trunc_sat_op(actual_type: &LoType, wanted_type: &LoType) -> Option<WasmTruncSatKind> {
+    Some(match (actual_type, wanted_type) {
+        (LoType::F32, LoType::I32) => WasmTruncSatKind::I32_TRUNC_SAT_F32_S,
+        (LoType::F32, LoType::U32) => WasmTruncSatKind::I32_TRUNC_SAT_F32_U,
+        (LoType::F64, LoType::I32) => WasmTruncSatKind::I32_TRUNC_SAT_F64_S,
+        (LoType::F64, LoType::U32) => WasmTruncSatKind::I32_TRUNC_SAT_F64_U,
+        (LoType::F32, LoType::I64) => WasmTruncSatKind::I64_TRUNC_SAT_F32_S,
+        (LoType::F32, LoType::U64) => WasmTruncSatKind::I64_TRUNC_SAT_F32_U,
+        (LoType::F64, LoType::I64) => WasmTruncSatKind::I64_TRUNC_SAT_F64_S,
+        (LoType::F64, LoType::U64) => WasmTruncSatKind::I64_TRUNC_SAT_F64_U,
+        _ => return None,
+    })
+}
+
 fn parse_fn_call_args(
     ctx: &mut BlockContext,
     tokens: &mut LoTokenStream,
@@ -2778,6 +3639,57 @@ fn parse_fn_call_args(
     Ok(())
 }
 
+// `x.clz()`, `x.rotl(n)`, etc. are builtins on 32 and 64 bit ints unless defined by the user
+fn get_bit_intrinsic_arg_count(receiver_type: &LoType, method_name: &str) -> Option<usize> {
+    let (LoType::I32 | LoType::U32 | LoType::I64 | LoType::U64) = receiver_type else {
+        return None;
+    };
+
+    match method_name {
+        "clz" | "ctz" | "popcnt" => Some(0),
+        "rotl" | "rotr" => Some(1),
+        _ => None,
+    }
+}
+
+fn build_bit_intrinsic(
+    receiver_type: &LoType,
+    method_name: &str,
+    mut args: Vec<LoInstr>,
+) -> LoInstr {
+    let is_64_bit = matches!(receiver_type, LoType::I64 | LoType::U64);
+    let receiver = Box::new(args.remove(0));
+
+    let unary_kind = match (method_name, is_64_bit) {
+        ("clz", false) => WasmUnaryOpKind::I32_CLZ,
+        ("clz", true) => WasmUnaryOpKind::I64_CLZ,
+        ("ctz", false) => WasmUnaryOpKind::I32_CTZ,
+        ("ctz", true) => WasmUnaryOpKind::I64_CTZ,
+        ("popcnt", false) => WasmUnaryOpKind::I32_POPCNT,
+        ("popcnt", true) => WasmUnaryOpKind::I64_POPCNT,
+        _ => {
+            let binary_kind = match (method_name, is_64_bit) {
+                ("rotl", false) => WasmBinaryOpKind::I32_ROTL,
+                ("rotl", true) => WasmBinaryOpKind::I64_ROTL,
+                ("rotr", false) => WasmBinaryOpKind::I32_ROTR,
+                ("rotr", true) => WasmBinaryOpKind::I64_ROTR,
+                _ => unreachable!(),
+            };
+
+            return LoInstr::BinaryOp {
+                kind: binary_kind,
+                lhs: receiver,
+                rhs: Box::new(args.remove(0)),
+            };
+        }
+    };
+
+    LoInstr::UnaryOp {
+        kind: unary_kind,
+        expr: receiver,
+    }
+}
+
 fn typecheck_fn_call_args(
     ctx: &ModuleContext,
     params: &Vec<LoType>,
@@ -2837,6 +3749,10 @@ fn parse_const_primary(
         return parse_const_int(tokens);
     }
 
+    if tokens.next_is_any(FloatLiteral)? {
+        return parse_const_float(tokens);
+    }
+
     if let Some(value) = tokens.eat_any(CharLiteral)? {
         return Ok(LoInstr::U32Const {
             value: Lexer::parse_char_literal_value(&value.value),
@@ -2983,7 +3899,9 @@ fn parse_lo_type_primary(
         let ok_type = parse_lo_type_(ctx, type_scope, tokens, false)?;
         tokens.expect(Delim, ",")?;
         let err_type = parse_lo_type_(ctx, type_scope, tokens, false)?;
-        tokens.expect(Operator, ">")?;
+        if !tokens.eat_type_list_end()? {
+            tokens.expect(Operator, ">")?;
+        }
 
         return Ok(LoType::Result {
             ok_type: Box::new(ok_type),
@@ -2992,6 +3910,59 @@ fn parse_lo_type_primary(
     }
 
     let token = parse_nested_symbol(tokens)?;
+
+    if let Some(generic_def) = ctx.generic_struct_defs.get(&token.value) {
+        let mut type_args = Vec::new();
+        if let Some(_) = tokens.eat(Operator, "<")? {
+            while !tokens.eat_type_list_end()? {
+                type_args.push(parse_lo_type_(ctx, type_scope, tokens, false)?);
+                if !tokens.next_is_type_list_end()? {
+                    tokens.expect(Delim, ",")?;
+                }
+            }
+        } else if let Some(_) = tokens.eat(Symbol, "of")? {
+            // `Slice of u16` is a shorthand for `Slice<u16>`
+            type_args.push(parse_lo_type_(ctx, type_scope, tokens, false)?);
+        }
+
+        let struct_def = get_struct_instance(ctx, generic_def, type_args, &token.loc)?;
+        let name = &struct_def.name;
+
+        if !struct_def.fully_defined && !is_referenced {
+            return Err(LoError {
+                message: format!("Cannot use partially defined struct: {name}"),
+                loc: token.loc.clone(),
+            });
+        }
+
+        if ctx.mode == CompilerMode::Inspect {
+            let source_index = ctx.get_loc_module_index(&token.loc);
+            let source_range = RangeDisplay(&token.loc);
+            let target_index = ctx.get_loc_module_index(&generic_def.loc);
+            let target_range = RangeDisplay(&generic_def.loc);
+
+            // fields of instances in macro signatures are only known at call sites
+            let hover = if struct_def
+                .type_args
+                .iter()
+                .any(|t| has_macro_type_args(ctx, t))
+            {
+                format!("struct {name}")
+            } else {
+                format!("struct {name} {{ {} }}", ListDisplay(&struct_def.fields))
+            };
+
+            stdout_writeln(format!(
+                "{{ \"type\": \"info\", \
+                    \"link\": \"{target_index}/{target_range}\", \
+                    \"hover\": \"{hover}\", \
+                    \"loc\": \"{source_index}/{source_range}\" }}, ",
+            ));
+        }
+
+        return Ok(LoType::StructInstance { name: name.clone() });
+    }
+
     get_type_by_name(ctx, type_scope, &token, is_referenced)
 }
 
@@ -3182,6 +4153,16 @@ fn parse_nested_symbol(tokens: &mut LoTokenStream) -> Result<LoToken, LoError> {
     Ok(nested_symbol)
 }
 
+fn get_generic_receiver<'a>(
+    ctx: &'a ModuleContext,
+    token: &LoToken,
+) -> Option<(&'a GenericStructDef, String)> {
+    let (receiver_name, method_name) = token.value.rsplit_once("::")?;
+    let generic_def = ctx.generic_struct_defs.get(receiver_name)?;
+
+    Some((generic_def, String::from(method_name)))
+}
+
 fn extract_method_receiver_and_name(
     ctx: &ModuleContext,
     token: &LoToken,
@@ -3227,6 +4208,21 @@ fn parse_const_int(tokens: &mut LoTokenStream) -> Result<LoInstr, LoError> {
     });
 }
 
+fn parse_const_float(tokens: &mut LoTokenStream) -> Result<LoInstr, LoError> {
+    let float_literal = tokens.expect_any(FloatLiteral)?.clone();
+    let value = Lexer::parse_float_literal_value(&float_literal.value);
+
+    if let Some(_) = tokens.eat(Symbol, "f32")? {
+        return Ok(LoInstr::F32Const {
+            value: value as f32,
+        });
+    }
+
+    tokens.eat(Symbol, "f64")?;
+
+    return Ok(LoInstr::F64Const { value });
+}
+
 fn parse_u8_literal(int: &LoToken) -> Result<u8, LoError> {
     Ok(Lexer::parse_int_literal_value(&int.value) as u8)
 }
@@ -3248,6 +4244,110 @@ fn get_fn_name_from_method(receiver_type: &LoType, method_name: &str) -> String
     format!("{resolved_receiver_type}::{method_name}")
 }
 
+// returns the targeted loop block and the label index of its exit for `break`
+// or of its next iteration for `continue`
+fn get_loop_branch_target<'a>(
+    block: &'a Block<'a>,
+    label: &Option<String>,
+    is_break: bool,
+    loc: &LoLocation,
+) -> Result<(&'a Block<'a>, u32), LoError> {
+    let mut label_index = 0;
+
+    let mut current_block = block;
+    loop {
+        match current_block.block_kind {
+            LoBlockKind::Function => break,
+            LoBlockKind::Block => {
+                label_index += 1;
+            }
+            LoBlockKind::Loop => {
+                // 0 = loop, 1 = loop wrapper block
+                if label.is_none() || *label == current_block.loop_label {
+                    if is_break {
+                        label_index += 1;
+                    }
+                    return Ok((current_block, label_index));
+                }
+                label_index += 2;
+            }
+            LoBlockKind::WhileLoop => {
+                // 0 = loop, 1 = loop wrapper block
+                if label.is_none() || *label == current_block.loop_label {
+                    if is_break {
+                        label_index += 1;
+                    }
+                    return Ok((current_block, label_index));
+                }
+                label_index += 2;
+            }
+            LoBlockKind::ForLoop => {
+                // 0 = body block, 1 = loop, 2 = loop wrapper block
+                if label.is_none() || *label == current_block.loop_label {
+                    if is_break {
+                        label_index += 2;
+                    }
+                    return Ok((current_block, label_index));
+                }
+                label_index += 3;
+
+                // the counter block has no wasm block of its own
+                current_block = current_block.parent.unwrap();
+            }
+        }
+
+        current_block = current_block.parent.unwrap();
+    }
+
+    if let Some(label) = label {
+        return Err(LoError {
+            message: format!("Unknown loop label '{label}"),
+            loc: loc.clone(),
+        });
+    }
+
+    Err(LoError {
+        message: if is_break {
+            format!("Cannot break outside of a loop")
+        } else {
+            format!("Cannot continue outside of a loop")
+        },
+        loc: loc.clone(),
+    })
+}
+
+// a loop evaluates to the value of the first `break value` that exits it
+fn get_loop_value_type(loop_block: &Block) -> Result<LoType, LoError> {
+    let loop_breaks = loop_block.loop_breaks.borrow();
+
+    let value_type = loop_breaks
+        .iter()
+        .map(|(break_type, _)| break_type)
+        .find(|break_type| **break_type != LoType::Void)
+        .cloned()
+        .unwrap_or(LoType::Void);
+
+    for (break_type, loc) in loop_breaks.iter() {
+        if *break_type == value_type {
+            continue;
+        }
+
+        if *break_type == LoType::Void {
+            return Err(LoError {
+                message: format!("Missing break value, expected: {value_type}"),
+                loc: loc.clone(),
+            });
+        }
+
+        return Err(LoError {
+            message: format!("Invalid break value type: {break_type}, expected: {value_type}"),
+            loc: loc.clone(),
+        });
+    }
+
+    Ok(value_type)
+}
+
 fn get_deferred(ctx: &mut BlockContext) -> Option<Vec<LoInstr>> {
     if ctx.fn_ctx.defers.len() == 0 {
         return None;
@@ -3547,6 +4647,29 @@ impl LoTokenStream {
         }
     }
 
+    // `>>` closing nested type lists like `Wrap<Wrap<u32>>` is split into two `>`
+    pub fn eat_type_list_end(&mut self) -> Result<bool, LoError> {
+        if let Some(token) = self.tokens.get_mut(self.index) {
+            if token.is(Operator, ">>") {
+                let mut second = token.clone();
+                second.value = String::from(">");
+                second.loc.pos.offset += 1;
+                second.loc.pos.col += 1;
+
+                token.value = String::from(">");
+                token.loc.end_pos = second.loc.pos.clone();
+
+                self.tokens.insert(self.index + 1, second);
+            }
+        }
+
+        Ok(self.eat(Operator, ">")?.is_some())
+    }
+
+    pub fn next_is_type_list_end(&mut self) -> Result<bool, LoError> {
+        Ok(self.next_is(Operator, ">")? || self.next_is(Operator, ">>")?)
+    }
+
     pub fn next_is_any(&mut self, type_: LoTokenType) -> Result<bool, LoError> {
         match self.peek() {
             Some(token) if token.is_any(type_) => Ok(true),
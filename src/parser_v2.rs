@@ -1,5 +1,5 @@
 use crate::{ast::*, core::*, lexer::*};
-use alloc::{boxed::Box, format, string::String, vec::Vec};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
 
 use LoTokenType::*;
 
@@ -9,18 +9,28 @@ pub struct FileInfo {
     pub ast: AST,
 }
 
+// files are pushed after their includes, so dependencies always come first
 pub fn parse_file_and_deps(
     files: &mut Vec<FileInfo>,
     file_name: &str,
     loc: &LoLocation,
+) -> Result<(), LoError> {
+    parse_file_and_deps_inner(files, &mut Vec::new(), file_name, loc)
+}
+
+fn parse_file_and_deps_inner(
+    files: &mut Vec<FileInfo>,
+    files_in_progress: &mut Vec<String>,
+    file_name: &str,
+    loc: &LoLocation,
 ) -> Result<(), LoError> {
     let file_path = resolve_path(file_name, &loc.file_name);
 
-    for file in files.iter() {
-        // file already parsed, skip
-        if file.path == file_path {
-            return Ok(());
-        }
+    // file already parsed or currently being parsed (include cycle), skip
+    if files.iter().any(|file| file.path == file_path)
+        || files_in_progress.iter().any(|path| *path == file_path)
+    {
+        return Ok(());
     }
 
     let chars = file_read_utf8(&file_path).map_err(|message| LoError {
@@ -30,26 +40,24 @@ pub fn parse_file_and_deps(
     let tokens = Lexer::lex(&file_path, &chars)?;
     let ast = ParserV2::parse(tokens)?;
 
-    let mut includes = Vec::new();
+    files_in_progress.push(file_path.clone());
     for expr in &ast.exprs {
         if let TopLevelExpr::Include(include) = expr {
-            includes.push(include.clone());
+            parse_file_and_deps_inner(
+                files,
+                files_in_progress,
+                &Lexer::unescape_string(&include.file_path),
+                &include.loc,
+            )?;
         };
     }
+    files_in_progress.pop();
 
     files.push(FileInfo {
-        path: file_path.into(),
+        path: file_path,
         ast,
     });
 
-    for include in includes {
-        parse_file_and_deps(
-            files,
-            &Lexer::unescape_string(&include.file_path),
-            &include.loc,
-        )?;
-    }
-
     Ok(())
 }
 
@@ -211,6 +219,7 @@ impl ParserV2 {
             let mut loc = self.prev().loc.clone();
 
             let struct_name = self.parse_ident()?;
+            let type_params = self.parse_type_params()?;
 
             let mut fields = Vec::new();
 
@@ -239,11 +248,65 @@ impl ParserV2 {
 
             return Ok(TopLevelExpr::StructDef(StructDefExpr {
                 struct_name,
+                type_params,
                 fields,
                 loc,
             }));
         }
 
+        if let Some(_) = self.eat(Symbol, "enum")? {
+            let mut loc = self.prev().loc.clone();
+
+            let enum_name = self.parse_ident()?;
+
+            let mut tag_type = None;
+            if let Some(_) = self.eat(Operator, ":")? {
+                tag_type = Some(self.parse_type_expr()?);
+            }
+
+            let mut variants = Vec::new();
+
+            self.expect(Delim, "{")?;
+            while let None = self.eat(Delim, "}")? {
+                let mut variant_loc = self.current().loc.clone();
+
+                let variant_name = self.expect_any(Symbol)?.clone();
+
+                let mut payload_type = None;
+                if let Some(_) = self.eat(Delim, "(")? {
+                    payload_type = Some(self.parse_type_expr()?);
+                    self.expect(Delim, ")")?;
+                }
+
+                let mut tag_value = None;
+                if let Some(_) = self.eat(Operator, "=")? {
+                    tag_value = Some(self.parse_code_expr(0)?);
+                }
+
+                variant_loc.end_pos = self.prev().loc.end_pos.clone();
+
+                variants.push(EnumVariantDef {
+                    variant_name: variant_name.value,
+                    payload_type,
+                    tag_value,
+                    loc: variant_loc,
+                });
+
+                if !self.current().is(Delim, "}") {
+                    self.expect(Delim, ",")?;
+                }
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TopLevelExpr::EnumDef(EnumDefExpr {
+                enum_name,
+                tag_type,
+                variants,
+                loc,
+            }));
+        }
+
         if let Some(_) = self.eat(Symbol, "type")? {
             let mut loc = self.prev().loc.clone();
 
@@ -299,18 +362,7 @@ impl ParserV2 {
             let macro_name = self.parse_ident()?;
             self.expect(Operator, "!")?;
 
-            let mut macro_type_params = Vec::new();
-            if let Some(_) = self.eat(Operator, "<")? {
-                while let None = self.eat(Operator, ">")? {
-                    let type_param = self.expect_any(Symbol)?;
-                    macro_type_params.push(type_param.value.clone());
-
-                    if !self.current().is(Operator, ">") {
-                        self.expect(Delim, ",")?;
-                    }
-                }
-            }
-
+            let macro_type_params = self.parse_type_params()?;
             let macro_params = self.parse_fn_params()?;
 
             let return_type = if let Some(_) = self.eat(Operator, ":")? {
@@ -333,6 +385,56 @@ impl ParserV2 {
             }));
         }
 
+        if let Some(_) = self.eat(Symbol, "trait")? {
+            let mut loc = self.prev().loc.clone();
+
+            let trait_name = self.parse_ident()?;
+
+            let mut methods = Vec::new();
+
+            self.expect(Delim, "{")?;
+            while let None = self.eat(Delim, "}")? {
+                self.expect(Symbol, "fn")?;
+                methods.push(self.parse_fn_decl()?);
+                self.expect(Delim, ";")?;
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TopLevelExpr::TraitDef(TraitDefExpr {
+                trait_name,
+                methods,
+                loc,
+            }));
+        }
+
+        if let Some(_) = self.eat(Symbol, "impl")? {
+            let mut loc = self.prev().loc.clone();
+
+            let trait_name = self.parse_ident()?;
+            self.expect(Symbol, "for")?;
+            let type_name = self.parse_ident()?;
+
+            let mut methods = Vec::new();
+
+            self.expect(Delim, "{")?;
+            while let None = self.eat(Delim, "}")? {
+                self.expect(Symbol, "fn")?;
+                let fn_loc = self.prev().loc.clone();
+                methods.push(self.parse_fn_def(false, fn_loc)?);
+                self.expect(Delim, ";")?;
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TopLevelExpr::TraitImpl(TraitImplExpr {
+                trait_name,
+                type_name,
+                methods,
+                loc,
+            }));
+        }
+
         let unexpected = self.current();
         return Err(LoError {
             message: format!("Unexpected top level token: {:?}", unexpected.value),
@@ -451,7 +553,22 @@ impl ParserV2 {
 
             let p_name = self.expect_any(Symbol)?.clone();
 
-            if p_name.value != "self" {
+            // `self: Box<u32>` defines a method of a single generic struct instance
+            if p_name.value == "self" && self.current().is(Operator, ":") {
+                if let FnParamType::SelfRef = p_type {
+                    return Err(LoError {
+                        message: format!(
+                            "Typed `self` param cannot be preceded by the reference operator"
+                        ),
+                        loc: p_name.loc,
+                    });
+                }
+
+                self.expect(Operator, ":")?;
+                p_type = FnParamType::Type {
+                    expr: self.parse_type_expr()?,
+                };
+            } else if p_name.value != "self" {
                 if let FnParamType::SelfRef = p_type {
                     return Err(LoError {
                         message: format!(
@@ -483,6 +600,45 @@ impl ParserV2 {
         Ok(params)
     }
 
+    fn parse_type_params(&mut self) -> Result<Vec<TypeParam>, LoError> {
+        let mut type_params = Vec::new();
+
+        let Some(_) = self.eat(Operator, "<")? else {
+            return Ok(type_params);
+        };
+
+        while !self.eat_type_list_end()? {
+            let mut loc = self.current().loc.clone();
+
+            let type_param = self.expect_any(Symbol)?.clone();
+
+            let mut trait_bounds = Vec::new();
+            if let Some(_) = self.eat(Operator, ":")? {
+                loop {
+                    trait_bounds.push(self.parse_ident()?);
+
+                    if let None = self.eat(Operator, "+")? {
+                        break;
+                    }
+                }
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            type_params.push(TypeParam {
+                name: type_param.value,
+                trait_bounds,
+                loc,
+            });
+
+            if !self.current().is(Operator, ">") && !self.current().is(Operator, ">>") {
+                self.expect(Delim, ",")?;
+            }
+        }
+
+        return Ok(type_params);
+    }
+
     fn parse_type_expr(&mut self) -> Result<TypeExpr, LoError> {
         let mut loc = self.current().loc.clone();
         let primary = self.parse_type_expr_primary()?;
@@ -516,12 +672,122 @@ impl ParserV2 {
             return Ok(TypeExpr::SequencePointer { pointee, loc });
         }
 
+        if let Some(_) = self.eat(Delim, "[")? {
+            if let Some(_) = self.eat(Delim, "]")? {
+                let item_type = Box::new(self.parse_type_expr()?);
+                loc.end_pos = self.prev().loc.end_pos.clone();
+
+                return Ok(TypeExpr::Slice { item_type, loc });
+            }
+
+            let item_type = Box::new(self.parse_type_expr()?);
+            self.expect(Delim, ";")?;
+            let int = self.expect_any(IntLiteral)?;
+            let length = Lexer::parse_int_literal_value(&int.value) as u32;
+            self.expect(Delim, "]")?;
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TypeExpr::Array {
+                item_type,
+                length,
+                loc,
+            });
+        }
+
+        if let Some(_) = self.eat(Delim, "(")? {
+            let mut item_types = Vec::new();
+            let mut is_tuple = false;
+            while let None = self.eat(Delim, ")")? {
+                item_types.push(self.parse_type_expr()?);
+
+                if !self.current().is(Delim, ")") {
+                    self.expect(Delim, ",")?;
+                    is_tuple = true;
+                }
+            }
+
+            // `(T)` is just a parenthesized type, `(T,)` is a single item tuple
+            if !is_tuple && item_types.len() == 1 {
+                return Ok(item_types.pop().unwrap());
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TypeExpr::Tuple { item_types, loc });
+        }
+
+        if let Some(_) = self.eat(Symbol, "fn")? {
+            self.expect(Delim, "(")?;
+            let mut inputs = Vec::new();
+            while let None = self.eat(Delim, ")")? {
+                inputs.push(self.parse_type_expr()?);
+
+                if !self.current().is(Delim, ")") {
+                    self.expect(Delim, ",")?;
+                }
+            }
+
+            let mut output = None;
+            if let Some(_) = self.eat(Operator, ":")? {
+                output = Some(Box::new(self.parse_type_expr()?));
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TypeExpr::Fn {
+                inputs,
+                output,
+                loc,
+            });
+        }
+
+        if let Some(_) = self.eat(Operator, "||")? {
+            let mut output = None;
+            if let Some(_) = self.eat(Operator, ":")? {
+                output = Some(Box::new(self.parse_type_expr()?));
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TypeExpr::Closure {
+                inputs: Vec::new(),
+                output,
+                loc,
+            });
+        }
+
+        if let Some(_) = self.eat(Operator, "|")? {
+            let mut inputs = Vec::new();
+            while let None = self.eat(Operator, "|")? {
+                inputs.push(self.parse_type_expr()?);
+
+                if !self.current().is(Operator, "|") {
+                    self.expect(Delim, ",")?;
+                }
+            }
+
+            let mut output = None;
+            if let Some(_) = self.eat(Operator, ":")? {
+                output = Some(Box::new(self.parse_type_expr()?));
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TypeExpr::Closure {
+                inputs,
+                output,
+                loc,
+            });
+        }
+
         if let Some(_) = self.eat(Symbol, "Result")? {
             self.expect(Operator, "<")?;
             let ok_type = Box::new(self.parse_type_expr()?);
             self.expect(Delim, ",")?;
             let err_type = Box::new(self.parse_type_expr()?);
-            self.expect(Operator, ">")?;
+            if !self.eat_type_list_end()? {
+                self.expect(Operator, ">")?;
+            }
             loc.end_pos = self.prev().loc.end_pos.clone();
 
             return Ok(TypeExpr::Result {
@@ -532,6 +798,21 @@ impl ParserV2 {
         }
 
         let ident = self.parse_ident()?;
+
+        // `<` must be attached to the name, otherwise `x as u32 < y` would be ambiguous
+        if self.current().is(Operator, "<")
+            && self.current().loc.pos.offset == ident.loc.end_pos.offset
+        {
+            let type_args = self.parse_type_args()?;
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(TypeExpr::Generic {
+                name: ident,
+                type_args,
+                loc,
+            });
+        }
+
         return Ok(TypeExpr::Named { name: ident });
     }
 
@@ -576,6 +857,58 @@ impl ParserV2 {
         Ok(primary)
     }
 
+    fn parse_closure_expr(&mut self) -> Result<CodeExpr, LoError> {
+        let mut loc = self.current().loc.clone();
+
+        let mut params = Vec::new();
+        if let None = self.eat(Operator, "||")? {
+            self.expect(Operator, "|")?;
+
+            while let None = self.eat(Operator, "|")? {
+                let mut param_loc = self.current().loc.clone();
+
+                let param_name = self.expect_any(Symbol)?.clone();
+                let mut param_type = None;
+                if let Some(_) = self.eat(Operator, ":")? {
+                    param_type = Some(self.parse_type_expr()?);
+                }
+
+                param_loc.end_pos = self.prev().loc.end_pos.clone();
+
+                if !self.current().is(Operator, "|") {
+                    self.expect(Delim, ",")?;
+                }
+
+                params.push(ClosureParam {
+                    param_name: param_name.value,
+                    param_type,
+                    loc: param_loc,
+                });
+            }
+        }
+
+        let mut return_type = None;
+        if let Some(_) = self.eat(Operator, ":")? {
+            return_type = Some(self.parse_type_expr()?);
+        }
+
+        // annotated return type requires a block body
+        let body = if return_type.is_some() || self.current().is(Delim, "{") {
+            ClosureBody::Block(self.parse_code_block_expr()?)
+        } else {
+            ClosureBody::Expr(Box::new(self.parse_code_expr(0)?))
+        };
+
+        loc.end_pos = self.prev().loc.end_pos.clone();
+
+        Ok(CodeExpr::Closure(ClosureExpr {
+            params,
+            return_type,
+            body,
+            loc,
+        }))
+    }
+
     fn parse_code_expr_primary(&mut self) -> Result<CodeExpr, LoError> {
         if let Some(_) = self.eat(Symbol, "return")? {
             let mut loc = self.prev().loc.clone();
@@ -616,6 +949,47 @@ impl ParserV2 {
             }));
         };
 
+        if let Some(_) = self.eat(Symbol, "match")? {
+            let mut loc = self.prev().loc.clone();
+
+            let expr = Box::new(self.parse_code_expr(0)?);
+
+            let mut arms = Vec::new();
+
+            self.expect(Delim, "{")?;
+            while let None = self.eat(Delim, "}")? {
+                let mut arm_loc = self.current().loc.clone();
+
+                let variant_name = self.parse_ident()?;
+
+                let mut payload_bind = None;
+                if let Some(_) = self.eat(Delim, "(")? {
+                    payload_bind = Some(self.parse_ident()?);
+                    self.expect(Delim, ")")?;
+                }
+
+                self.expect(Operator, "=>")?;
+                let body = self.parse_code_block_expr()?;
+
+                arm_loc.end_pos = self.prev().loc.end_pos.clone();
+
+                arms.push(MatchArm {
+                    variant_name,
+                    payload_bind,
+                    body,
+                    loc: arm_loc,
+                });
+
+                if !self.current().is(Delim, "}") {
+                    self.expect(Delim, ",")?;
+                }
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(CodeExpr::Match(MatchExpr { expr, arms, loc }));
+        }
+
         if let Some(_) = self.eat(Symbol, "true")? {
             let loc = self.prev().loc.clone();
 
@@ -644,12 +1018,28 @@ impl ParserV2 {
 
             return Ok(CodeExpr::IntLiteral(IntLiteralExpr {
                 repr: int.value.clone(),
-                value: Lexer::parse_int_literal_value(&int.value) as u32,
+                value: Lexer::parse_int_literal_value(&int.value),
                 tag,
                 loc: int.loc.clone(),
             }));
         };
 
+        if let Some(float) = self.eat_any(FloatLiteral)?.cloned() {
+            let mut tag = None;
+            if let Some(_) = self.eat(Symbol, "f32")? {
+                tag = Some(String::from("f32"));
+            } else if let Some(_) = self.eat(Symbol, "f64")? {
+                tag = Some(String::from("f64"));
+            }
+
+            return Ok(CodeExpr::FloatLiteral(FloatLiteralExpr {
+                repr: float.value.clone(),
+                value: Lexer::parse_float_literal_value(&float.value),
+                tag,
+                loc: float.loc.clone(),
+            }));
+        };
+
         if let Some(string) = self.eat_any(StringLiteral)?.cloned() {
             let mut zero_terminated = false;
             if let Some(_) = self.eat(IntLiteral, "0")? {
@@ -667,18 +1057,66 @@ impl ParserV2 {
         if let Some(_) = self.eat(Delim, "(")? {
             let mut loc = self.prev().loc.clone();
 
-            let expr = Box::new(self.parse_code_expr(0)?);
-            self.expect(Delim, ")")?;
+            let expr = self.parse_code_expr(0)?;
+            if let None = self.eat(Delim, ",")? {
+                self.expect(Delim, ")")?;
+
+                loc.end_pos = self.prev().loc.end_pos.clone();
+
+                return Ok(CodeExpr::Paren(ParenExpr {
+                    expr: Box::new(expr),
+                    loc,
+                }));
+            }
+
+            let mut items = vec![expr];
+            while let None = self.eat(Delim, ")")? {
+                items.push(self.parse_code_expr(0)?);
+
+                if !self.current().is(Delim, ")") {
+                    self.expect(Delim, ",")?;
+                }
+            }
 
             loc.end_pos = self.prev().loc.end_pos.clone();
 
-            return Ok(CodeExpr::Paren(ParenExpr { expr, loc }));
+            return Ok(CodeExpr::TupleLiteral(TupleLiteralExpr { items, loc }));
         };
 
         if let Some(_) = self.eat(Symbol, "let")? {
             let mut loc = self.prev().loc.clone();
 
+            if let Some(_) = self.eat(Delim, "(")? {
+                let mut local_names = Vec::new();
+                while let None = self.eat(Delim, ")")? {
+                    local_names.push(self.expect_any(Symbol)?.value.clone());
+
+                    if !self.current().is(Delim, ")") {
+                        self.expect(Delim, ",")?;
+                    }
+                }
+                let mut local_type = None;
+                if let Some(_) = self.eat(Operator, ":")? {
+                    local_type = Some(self.parse_type_expr()?);
+                }
+                self.expect(Operator, "=")?;
+                let value = self.parse_code_expr(0)?;
+
+                loc.end_pos = self.prev().loc.end_pos.clone();
+
+                return Ok(CodeExpr::LetTuple(LetTupleExpr {
+                    local_names,
+                    local_type,
+                    value: Box::new(value),
+                    loc,
+                }));
+            }
+
             let local_name = self.expect_any(Symbol)?.clone();
+            let mut local_type = None;
+            if let Some(_) = self.eat(Operator, ":")? {
+                local_type = Some(self.parse_type_expr()?);
+            }
             self.expect(Operator, "=")?;
             let value = self.parse_code_expr(0)?;
 
@@ -686,11 +1124,46 @@ impl ParserV2 {
 
             return Ok(CodeExpr::Let(LetExpr {
                 local_name: local_name.value,
+                local_type,
                 value: Box::new(value),
                 loc,
             }));
         }
 
+        if let Some(label) = self.eat_any(Label)?.cloned() {
+            self.expect(Operator, ":")?;
+
+            let mut expr = self.parse_code_expr_primary()?;
+            match &mut expr {
+                CodeExpr::Loop(LoopExpr {
+                    label: loop_label,
+                    body: _,
+                    loc,
+                })
+                | CodeExpr::ForLoop(ForLoopExpr {
+                    label: loop_label,
+                    loc,
+                    ..
+                })
+                | CodeExpr::WhileLoop(WhileLoopExpr {
+                    label: loop_label,
+                    loc,
+                    ..
+                }) => {
+                    *loop_label = Some(label.value);
+                    loc.pos = label.loc.pos;
+                }
+                _ => {
+                    return Err(LoError {
+                        message: format!("Only loops can be labeled"),
+                        loc: label.loc,
+                    });
+                }
+            }
+
+            return Ok(expr);
+        }
+
         if let Some(_) = self.eat(Symbol, "loop")? {
             let mut loc = self.prev().loc.clone();
 
@@ -699,42 +1172,90 @@ impl ParserV2 {
             loc.end_pos = self.prev().loc.end_pos.clone();
 
             return Ok(CodeExpr::Loop(LoopExpr {
+                label: None,
                 body: Box::new(body),
                 loc,
             }));
         }
 
         if let Some(_) = self.eat(Symbol, "break")? {
-            let loc = self.prev().loc.clone();
+            let mut loc = self.prev().loc.clone();
 
-            return Ok(CodeExpr::Break(BreakExpr { loc }));
+            let label = self.eat_any(Label)?.map(|label| label.value.clone());
+            let mut value = None;
+            if !self.current().is(Delim, ";") && !self.current().is(Delim, "}") {
+                value = Some(Box::new(self.parse_code_expr(0)?));
+            }
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(CodeExpr::Break(BreakExpr { label, value, loc }));
         }
 
         if let Some(_) = self.eat(Symbol, "for")? {
             let mut loc = self.prev().loc.clone();
 
-            let counter = self.expect_any(Symbol)?.clone();
+            let item_name = self.expect_any(Symbol)?.clone();
             self.expect(Symbol, "in")?;
             let start = self.parse_code_expr(0)?;
-            self.expect(Operator, "..")?;
-            let end = self.parse_code_expr(0)?;
+
+            let iterable;
+            if self.current().is(Operator, "..") || self.current().is(Operator, "..=") {
+                let is_inclusive = self.next().unwrap().value == "..=";
+                let end = self.parse_code_expr(0)?;
+
+                let mut step = None;
+                if let Some(_) = self.eat(Symbol, "by")? {
+                    step = Some(Box::new(self.parse_code_expr(0)?));
+                }
+
+                iterable = ForLoopIterable::Range(RangeExpr {
+                    start: Box::new(start),
+                    end: Box::new(end),
+                    is_inclusive,
+                    step,
+                });
+            } else {
+                iterable = ForLoopIterable::Items(Box::new(start));
+            }
+
             let body = self.parse_code_block_expr()?;
 
             loc.end_pos = self.prev().loc.end_pos.clone();
 
             return Ok(CodeExpr::ForLoop(ForLoopExpr {
-                counter: counter.value,
-                start: Box::new(start),
-                end: Box::new(end),
+                label: None,
+                item_name: item_name.value,
+                iterable,
                 body: Box::new(body),
                 loc,
             }));
         }
 
+        if let Some(_) = self.eat(Symbol, "while")? {
+            let mut loc = self.prev().loc.clone();
+
+            let cond = Box::new(self.parse_code_expr(0)?);
+            let body = Box::new(self.parse_code_block_expr()?);
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(CodeExpr::WhileLoop(WhileLoopExpr {
+                label: None,
+                cond,
+                body,
+                loc,
+            }));
+        }
+
         if let Some(_) = self.eat(Symbol, "continue")? {
-            let loc = self.prev().loc.clone();
+            let mut loc = self.prev().loc.clone();
+
+            let label = self.eat_any(Label)?.map(|label| label.value.clone());
 
-            return Ok(CodeExpr::Continue(ContinueExpr { loc }));
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(CodeExpr::Continue(ContinueExpr { label, loc }));
         }
 
         if let Some(_) = self.eat(Symbol, "dbg")? {
@@ -769,6 +1290,20 @@ impl ParserV2 {
             }));
         }
 
+        if let Some(_) = self.eat(Operator, "&")? {
+            let mut loc = self.prev().loc.clone();
+
+            let fn_name = self.parse_ident()?;
+
+            loc.end_pos = self.prev().loc.end_pos.clone();
+
+            return Ok(CodeExpr::FnRef(FnRefExpr { fn_name, loc }));
+        }
+
+        if self.current().is(Operator, "|") || self.current().is(Operator, "||") {
+            return self.parse_closure_expr();
+        }
+
         if let Some(token) = self.peek().cloned() {
             if let Some(op) = PrefixOp::parse(token) {
                 self.next(); // skip operator
@@ -780,6 +1315,7 @@ impl ParserV2 {
                 match op.tag {
                     PrefixOpTag::Dereference
                     | PrefixOpTag::Not
+                    | PrefixOpTag::BitNot
                     | PrefixOpTag::Positive
                     | PrefixOpTag::Negative => {
                         let expr = Box::new(self.parse_code_expr(min_bp)?);
@@ -845,7 +1381,8 @@ impl ParserV2 {
         if let Some(_) = self.eat(Operator, ".")? {
             let loc = self.prev().loc.clone();
             let struct_name = self.parse_ident()?;
-            let struct_literal = self.parse_struct_literal(struct_name, loc)?;
+            let type_args = self.parse_type_args()?;
+            let struct_literal = self.parse_struct_literal(struct_name, type_args, loc)?;
             return Ok(CodeExpr::StructLiteral(struct_literal));
         }
 
@@ -868,7 +1405,7 @@ impl ParserV2 {
         if let Some(_) = self.eat(Operator, "!")? {
             let mut loc = ident.loc.clone();
 
-            let type_args = self.parse_macro_type_args()?;
+            let type_args = self.parse_type_args()?;
             let args = self.parse_fn_args()?;
 
             loc.end_pos = self.prev().loc.end_pos.clone();
@@ -912,6 +1449,7 @@ impl ParserV2 {
     fn parse_struct_literal(
         &mut self,
         ident: IdentExpr,
+        type_args: Vec<TypeExpr>,
         mut loc: LoLocation,
     ) -> Result<StructLiteralExpr, LoError> {
         let mut fields = Vec::new();
@@ -941,6 +1479,7 @@ impl ParserV2 {
 
         return Ok(StructLiteralExpr {
             struct_name: ident,
+            type_args,
             fields,
             loc,
         });
@@ -960,17 +1499,17 @@ impl ParserV2 {
         return Ok(args);
     }
 
-    fn parse_macro_type_args(&mut self) -> Result<Vec<TypeExpr>, LoError> {
+    fn parse_type_args(&mut self) -> Result<Vec<TypeExpr>, LoError> {
         let mut type_args = Vec::new();
 
         let Some(_) = self.eat(Operator, "<")? else {
             return Ok(type_args);
         };
 
-        while let None = self.eat(Operator, ">")? {
+        while !self.eat_type_list_end()? {
             type_args.push(self.parse_type_expr()?);
 
-            if !self.current().is(Operator, ">") {
+            if !self.current().is(Operator, ">") && !self.current().is(Operator, ">>") {
                 self.expect(Delim, ",")?;
             }
         }
@@ -1001,6 +1540,7 @@ impl ParserV2 {
             | InfixOpTag::BitAnd
             | InfixOpTag::Or
             | InfixOpTag::BitOr
+            | InfixOpTag::BitXor
             | InfixOpTag::ShiftLeft
             | InfixOpTag::ShiftRight
             | InfixOpTag::AddAssign
@@ -1010,6 +1550,7 @@ impl ParserV2 {
             | InfixOpTag::ModAssign
             | InfixOpTag::BitAndAssign
             | InfixOpTag::BitOrAssign
+            | InfixOpTag::BitXorAssign
             | InfixOpTag::ShiftLeftAssign
             | InfixOpTag::ShiftRightAssign => {
                 let lhs = primary;
@@ -1042,6 +1583,20 @@ impl ParserV2 {
             InfixOpTag::FieldAccess => {
                 let mut loc = primary.loc().clone();
 
+                if let Some(index) = self.eat_any(IntLiteral)?.cloned() {
+                    loc.end_pos = self.prev().loc.end_pos.clone();
+
+                    return Ok(CodeExpr::FieldAccess(FieldAccessExpr {
+                        lhs: Box::new(primary),
+                        field_name: IdentExpr {
+                            repr: index.value.clone(),
+                            parts: vec![index.value],
+                            loc: index.loc,
+                        },
+                        loc,
+                    }));
+                }
+
                 let field_name = self.parse_ident()?;
 
                 if self.current().is(Delim, "(") {
@@ -1058,7 +1613,7 @@ impl ParserV2 {
                 }
 
                 if let Some(_) = self.eat(Operator, "!")? {
-                    let type_args = self.parse_macro_type_args()?;
+                    let type_args = self.parse_type_args()?;
                     let args = self.parse_fn_args()?;
 
                     loc.end_pos = self.prev().loc.end_pos.clone();
@@ -1080,6 +1635,47 @@ impl ParserV2 {
                     loc,
                 }))
             }
+            InfixOpTag::Index => {
+                let mut loc = primary.loc().clone();
+
+                let mut start = None;
+                if !self.current().is(Operator, "..") {
+                    start = Some(Box::new(self.parse_code_expr(0)?));
+                }
+
+                if let Some(_) = self.eat(Operator, "..")? {
+                    let mut end = None;
+                    if !self.current().is(Delim, "]") {
+                        end = Some(Box::new(self.parse_code_expr(0)?));
+                    }
+                    self.expect(Delim, "]")?;
+
+                    loc.end_pos = self.prev().loc.end_pos.clone();
+
+                    return Ok(CodeExpr::Slice(SliceExpr {
+                        lhs: Box::new(primary),
+                        start,
+                        end,
+                        loc,
+                    }));
+                }
+
+                let Some(index) = start else {
+                    return Err(LoError {
+                        message: format!("Expected index or range"),
+                        loc: self.current().loc.clone(),
+                    });
+                };
+                self.expect(Delim, "]")?;
+
+                loc.end_pos = self.prev().loc.end_pos.clone();
+
+                Ok(CodeExpr::Index(IndexExpr {
+                    lhs: Box::new(primary),
+                    index,
+                    loc,
+                }))
+            }
             InfixOpTag::Assign => {
                 let mut loc = primary.loc().clone();
 
@@ -1150,6 +1746,25 @@ impl ParserV2 {
         }
     }
 
+    // `>>` closing nested type lists like `Wrap<Wrap<u32>>` is split into two `>`
+    fn eat_type_list_end(&mut self) -> Result<bool, LoError> {
+        if let Some(token) = self.tokens.get_mut(self.tokens_processed) {
+            if token.is(Operator, ">>") {
+                let mut second = token.clone();
+                second.value = String::from(">");
+                second.loc.pos.offset += 1;
+                second.loc.pos.col += 1;
+
+                token.value = String::from(">");
+                token.loc.end_pos = second.loc.pos.clone();
+
+                self.tokens.insert(self.tokens_processed + 1, second);
+            }
+        }
+
+        Ok(self.eat(Operator, ">")?.is_some())
+    }
+
     fn eat_any(&mut self, type_: LoTokenType) -> Result<Option<&LoToken>, LoError> {
         let was_some = self.peek().is_some();
         match self.expect_any(type_) {
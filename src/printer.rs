@@ -1,7 +1,11 @@
 use core::usize;
 
 use crate::{ast::*, core::*};
-use alloc::{rc::Rc, string::ToString, vec::Vec};
+use alloc::{
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
 
 pub struct Printer {
     ast: Rc<AST>,
@@ -100,11 +104,13 @@ impl Printer {
             }
             TopLevelExpr::StructDef(StructDefExpr {
                 struct_name,
+                type_params,
                 fields,
                 loc,
             }) => {
                 stdout_write("struct ");
                 stdout_write(&struct_name.repr);
+                self.print_type_params(type_params);
 
                 if fields.len() == 0 {
                     stdout_writeln(" {};");
@@ -129,6 +135,49 @@ impl Printer {
                     stdout_writeln("};");
                 }
             }
+            TopLevelExpr::EnumDef(EnumDefExpr {
+                enum_name,
+                tag_type,
+                variants,
+                loc,
+            }) => {
+                stdout_write("enum ");
+                stdout_write(&enum_name.repr);
+                if let Some(tag_type) = tag_type {
+                    stdout_write(": ");
+                    self.print_type_expr(tag_type);
+                }
+
+                if variants.len() == 0 {
+                    stdout_writeln(" {};");
+                } else {
+                    stdout_writeln(" {");
+                    self.indent += 1;
+                    for variant in variants {
+                        self.print_comments_before_pos(variant.loc.pos.offset);
+                        self.print_indent();
+                        stdout_write(&variant.variant_name);
+                        if let Some(payload_type) = &variant.payload_type {
+                            stdout_write("(");
+                            self.print_type_expr(payload_type);
+                            stdout_write(")");
+                        }
+                        if let Some(tag_value) = &variant.tag_value {
+                            stdout_write(" = ");
+                            self.print_code_expr(tag_value);
+                        }
+                        stdout_writeln(",");
+                    }
+
+                    // print the rest of the comments
+                    self.print_comments_before_pos(loc.end_pos.offset);
+
+                    self.indent -= 1;
+                    self.print_indent();
+
+                    stdout_writeln("};");
+                }
+            }
             TopLevelExpr::TypeDef(TypeDefExpr {
                 type_name,
                 type_value,
@@ -196,16 +245,7 @@ impl Printer {
                 stdout_write("macro ");
                 stdout_write(&macro_name.repr);
                 stdout_write("!");
-                if macro_type_params.len() != 0 {
-                    stdout_write("<");
-                    for (type_param, i) in macro_type_params.iter().zip(0..) {
-                        stdout_write(type_param);
-                        if i != macro_type_params.len() - 1 {
-                            stdout_write(",");
-                        }
-                    }
-                    stdout_write(">");
-                }
+                self.print_type_params(macro_type_params);
                 self.print_fn_params(macro_params);
                 if let Some(return_type) = return_type {
                     stdout_write(": ");
@@ -215,6 +255,73 @@ impl Printer {
                 self.print_code_block_expr(body);
                 stdout_writeln(";");
             }
+            TopLevelExpr::TraitDef(TraitDefExpr {
+                trait_name,
+                methods,
+                loc,
+            }) => {
+                stdout_write("trait ");
+                stdout_write(&trait_name.repr);
+
+                if methods.len() == 0 {
+                    stdout_writeln(" {};");
+                } else {
+                    stdout_writeln(" {");
+                    self.indent += 1;
+                    for method in methods {
+                        self.print_comments_before_pos(method.loc.pos.offset);
+                        self.print_indent();
+                        self.print_fn_decl(method);
+                        stdout_writeln(";");
+                    }
+
+                    // print the rest of the comments
+                    self.print_comments_before_pos(loc.end_pos.offset);
+
+                    self.indent -= 1;
+                    self.print_indent();
+
+                    stdout_writeln("};");
+                }
+            }
+            TopLevelExpr::TraitImpl(TraitImplExpr {
+                trait_name,
+                type_name,
+                methods,
+                loc,
+            }) => {
+                stdout_write("impl ");
+                stdout_write(&trait_name.repr);
+                stdout_write(" for ");
+                stdout_write(&type_name.repr);
+
+                if methods.len() == 0 {
+                    stdout_writeln(" {};");
+                } else {
+                    stdout_writeln(" {");
+                    self.indent += 1;
+                    for (method, method_index) in methods.iter().zip(0..) {
+                        if method_index != 0 {
+                            stdout_writeln("");
+                        }
+
+                        self.print_comments_before_pos(method.loc.pos.offset);
+                        self.print_indent();
+                        self.print_fn_decl(&method.decl);
+                        stdout_write(" ");
+                        self.print_code_block_expr(&method.body);
+                        stdout_writeln(";");
+                    }
+
+                    // print the rest of the comments
+                    self.print_comments_before_pos(loc.end_pos.offset);
+
+                    self.indent -= 1;
+                    self.print_indent();
+
+                    stdout_writeln("};");
+                }
+            }
         }
 
         if expr_index != self.ast.exprs.len() - 1 {
@@ -325,6 +432,89 @@ impl Printer {
                 stdout_write(" of ");
                 self.print_type_expr(item_type);
             }
+            TypeExpr::Generic {
+                name,
+                type_args,
+                loc: _,
+            } => {
+                stdout_write(&name.repr);
+                self.print_type_args(type_args);
+            }
+            TypeExpr::Array {
+                item_type,
+                length,
+                loc: _,
+            } => {
+                stdout_write("[");
+                self.print_type_expr(item_type);
+                stdout_write("; ");
+                stdout_write(length.to_string());
+                stdout_write("]");
+            }
+            TypeExpr::Slice { item_type, loc: _ } => {
+                stdout_write("[]");
+                self.print_type_expr(item_type);
+            }
+            TypeExpr::Fn {
+                inputs,
+                output,
+                loc: _,
+            } => {
+                stdout_write("fn(");
+                for (input, index) in inputs.iter().zip(0..) {
+                    if index != 0 {
+                        stdout_write(", ");
+                    }
+
+                    self.print_type_expr(input);
+                }
+                stdout_write(")");
+                if let Some(output) = output {
+                    stdout_write(": ");
+                    self.print_type_expr(output);
+                }
+            }
+            TypeExpr::Closure {
+                inputs,
+                output,
+                loc: _,
+            } => {
+                stdout_write("|");
+                for (input, index) in inputs.iter().zip(0..) {
+                    if index != 0 {
+                        stdout_write(", ");
+                    }
+
+                    self.print_type_expr(input);
+                }
+                stdout_write("|");
+                if let Some(output) = output {
+                    stdout_write(": ");
+                    self.print_type_expr(output);
+                }
+            }
+            TypeExpr::Tuple { item_types, loc: _ } => {
+                stdout_write("(");
+                for (item_type, index) in item_types.iter().zip(0..) {
+                    if index != 0 {
+                        stdout_write(", ");
+                    }
+
+                    self.print_type_expr(item_type);
+                }
+                if item_types.len() == 1 {
+                    stdout_write(",");
+                }
+                stdout_write(")");
+            }
+        }
+    }
+
+    fn print_loop_label(&mut self, label: &Option<String>) {
+        if let Some(label) = label {
+            stdout_write("'");
+            stdout_write(label);
+            stdout_write(": ");
         }
     }
 
@@ -376,6 +566,17 @@ impl Printer {
                     stdout_write(tag);
                 }
             }
+            CodeExpr::FloatLiteral(FloatLiteralExpr {
+                repr,
+                tag,
+                value: _,
+                loc: _,
+            }) => {
+                stdout_write(repr);
+                if let Some(tag) = tag {
+                    stdout_write(tag);
+                }
+            }
             CodeExpr::StringLiteral(StringLiteralExpr {
                 repr,
                 zero_terminated,
@@ -409,6 +610,20 @@ impl Printer {
                 self.print_indent();
                 stdout_write("]");
             }
+            CodeExpr::TupleLiteral(TupleLiteralExpr { items, loc: _ }) => {
+                stdout_write("(");
+                for (item, index) in items.iter().zip(0..) {
+                    if index != 0 {
+                        stdout_write(", ");
+                    }
+
+                    self.print_code_expr(item);
+                }
+                if items.len() == 1 {
+                    stdout_write(",");
+                }
+                stdout_write(")");
+            }
 
             CodeExpr::Ident(IdentExpr {
                 repr,
@@ -419,14 +634,68 @@ impl Printer {
             }
             CodeExpr::Let(LetExpr {
                 local_name,
+                local_type,
                 value,
                 loc: _,
             }) => {
                 stdout_write("let ");
                 stdout_write(local_name);
+                if let Some(local_type) = local_type {
+                    stdout_write(": ");
+                    self.print_type_expr(local_type);
+                }
+                stdout_write(" = ");
+                self.print_code_expr(&value);
+            }
+            CodeExpr::LetTuple(LetTupleExpr {
+                local_names,
+                local_type,
+                value,
+                loc: _,
+            }) => {
+                stdout_write("let (");
+                stdout_write(local_names.join(", "));
+                stdout_write(")");
+                if let Some(local_type) = local_type {
+                    stdout_write(": ");
+                    self.print_type_expr(local_type);
+                }
                 stdout_write(" = ");
                 self.print_code_expr(&value);
             }
+            CodeExpr::FnRef(FnRefExpr { fn_name, loc: _ }) => {
+                stdout_write("&");
+                stdout_write(&fn_name.repr);
+            }
+            CodeExpr::Closure(ClosureExpr {
+                params,
+                return_type,
+                body,
+                loc: _,
+            }) => {
+                stdout_write("|");
+                for (param, index) in params.iter().zip(0..) {
+                    if index != 0 {
+                        stdout_write(", ");
+                    }
+
+                    stdout_write(&param.param_name);
+                    if let Some(param_type) = &param.param_type {
+                        stdout_write(": ");
+                        self.print_type_expr(param_type);
+                    }
+                }
+                stdout_write("|");
+                if let Some(return_type) = return_type {
+                    stdout_write(": ");
+                    self.print_type_expr(return_type);
+                }
+                stdout_write(" ");
+                match body {
+                    ClosureBody::Expr(expr) => self.print_code_expr(expr),
+                    ClosureBody::Block(block) => self.print_code_block_expr(block),
+                }
+            }
 
             CodeExpr::Return(ReturnExpr { expr, loc: _ }) => {
                 stdout_write("return");
@@ -483,31 +752,108 @@ impl Printer {
                     }
                 }
             }
-            CodeExpr::Loop(LoopExpr { body, loc: _ }) => {
+            CodeExpr::Match(MatchExpr { expr, arms, loc }) => {
+                stdout_write("match ");
+                self.print_code_expr(expr);
+                stdout_writeln(" {");
+                self.indent += 1;
+                for arm in arms {
+                    self.print_comments_before_pos(arm.loc.pos.offset);
+                    self.print_indent();
+                    stdout_write(&arm.variant_name.repr);
+                    if let Some(payload_bind) = &arm.payload_bind {
+                        stdout_write("(");
+                        stdout_write(&payload_bind.repr);
+                        stdout_write(")");
+                    }
+                    stdout_write(" => ");
+                    self.print_code_block_expr(&arm.body);
+                    stdout_writeln(",");
+                }
+
+                // print the rest of the comments
+                self.print_comments_before_pos(loc.end_pos.offset);
+
+                self.indent -= 1;
+                self.print_indent();
+
+                stdout_write("}");
+            }
+            CodeExpr::Loop(LoopExpr {
+                label,
+                body,
+                loc: _,
+            }) => {
+                self.print_loop_label(label);
                 stdout_write("loop ");
                 self.print_code_block_expr(&body);
             }
             CodeExpr::ForLoop(ForLoopExpr {
-                counter,
-                start,
-                end,
+                label,
+                item_name,
+                iterable,
                 body,
                 loc: _,
             }) => {
+                self.print_loop_label(label);
                 stdout_write("for ");
-                stdout_write(counter);
+                stdout_write(item_name);
                 stdout_write(" in ");
-                self.print_code_expr(&start);
-                stdout_write("..");
-                self.print_code_expr(&end);
+                match iterable {
+                    ForLoopIterable::Range(RangeExpr {
+                        start,
+                        end,
+                        is_inclusive,
+                        step,
+                    }) => {
+                        self.print_code_expr(&start);
+                        stdout_write(if *is_inclusive { "..=" } else { ".." });
+                        self.print_code_expr(&end);
+                        if let Some(step) = step {
+                            stdout_write(" by ");
+                            self.print_code_expr(step);
+                        }
+                    }
+                    ForLoopIterable::Items(items) => {
+                        self.print_code_expr(items);
+                    }
+                }
                 stdout_write(" ");
                 self.print_code_block_expr(&body);
             }
-            CodeExpr::Break(BreakExpr { loc: _ }) => {
+            CodeExpr::WhileLoop(WhileLoopExpr {
+                label,
+                cond,
+                body,
+                loc: _,
+            }) => {
+                self.print_loop_label(label);
+                stdout_write("while ");
+                self.print_code_expr(cond);
+                stdout_write(" ");
+                self.print_code_block_expr(&body);
+            }
+            CodeExpr::Break(BreakExpr {
+                label,
+                value,
+                loc: _,
+            }) => {
                 stdout_write("break");
+                if let Some(label) = label {
+                    stdout_write(" '");
+                    stdout_write(label);
+                }
+                if let Some(value) = value {
+                    stdout_write(" ");
+                    self.print_code_expr(value);
+                }
             }
-            CodeExpr::Continue(ContinueExpr { loc: _ }) => {
+            CodeExpr::Continue(ContinueExpr { label, loc: _ }) => {
                 stdout_write("continue");
+                if let Some(label) = label {
+                    stdout_write(" '");
+                    stdout_write(label);
+                }
             }
             CodeExpr::Unreachable(UnreachableExpr { loc: _ }) => {
                 stdout_write("unreachable");
@@ -531,11 +877,13 @@ impl Printer {
             }
             CodeExpr::StructLiteral(StructLiteralExpr {
                 struct_name,
+                type_args,
                 fields,
                 loc,
             }) => {
                 stdout_write(".");
                 stdout_write(&struct_name.repr);
+                self.print_type_args(type_args);
                 stdout_writeln(" {");
                 self.indent += 1;
                 for field in fields {
@@ -574,6 +922,29 @@ impl Printer {
                 stdout_write(".");
                 stdout_write(&field_name.repr);
             }
+            CodeExpr::Index(IndexExpr { lhs, index, loc: _ }) => {
+                self.print_code_expr(lhs);
+                stdout_write("[");
+                self.print_code_expr(index);
+                stdout_write("]");
+            }
+            CodeExpr::Slice(SliceExpr {
+                lhs,
+                start,
+                end,
+                loc: _,
+            }) => {
+                self.print_code_expr(lhs);
+                stdout_write("[");
+                if let Some(start) = start {
+                    self.print_code_expr(start);
+                }
+                stdout_write("..");
+                if let Some(end) = end {
+                    self.print_code_expr(end);
+                }
+                stdout_write("]");
+            }
             CodeExpr::Catch(CatchExpr {
                 lhs,
                 error_bind,
@@ -662,6 +1033,29 @@ impl Printer {
         stdout_write(")");
     }
 
+    fn print_type_params(&mut self, type_params: &Vec<TypeParam>) {
+        if type_params.len() == 0 {
+            return;
+        }
+
+        stdout_write("<");
+        for (type_param, i) in type_params.iter().zip(0..) {
+            stdout_write(&type_param.name);
+            for (trait_bound, bound_index) in type_param.trait_bounds.iter().zip(0..) {
+                if bound_index == 0 {
+                    stdout_write(": ");
+                } else {
+                    stdout_write(" + ");
+                }
+                stdout_write(&trait_bound.repr);
+            }
+            if i != type_params.len() - 1 {
+                stdout_write(",");
+            }
+        }
+        stdout_write(">");
+    }
+
     fn print_type_args(&mut self, type_args: &Vec<TypeExpr>) {
         if type_args.len() == 0 {
             return;
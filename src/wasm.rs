@@ -67,7 +67,65 @@ pub struct WasmExpr {
 pub enum WasmUnaryOpKind {
     I32_EQZ = 0x45,
     I64_EQZ = 0x50,
+
+    I32_CLZ = 0x67,
+    I32_CTZ = 0x68,
+    I32_POPCNT = 0x69,
+
+    I64_CLZ = 0x79,
+    I64_CTZ = 0x7A,
+    I64_POPCNT = 0x7B,
+
+    F32_ABS = 0x8B,
+    F32_NEG = 0x8C,
+    F32_CEIL = 0x8D,
+    F32_FLOOR = 0x8E,
+    F32_TRUNC = 0x8F,
+    F32_NEAREST = 0x90,
+    F32_SQRT = 0x91,
+
+    F64_ABS = 0x99,
     F64_NEG = 0x9A,
+    F64_CEIL = 0x9B,
+    F64_FLOOR = 0x9C,
+    F64_TRUNC = 0x9D,
+    F64_NEAREST = 0x9E,
+    F64_SQRT = 0x9F,
+
+    I32_TRUNC_F32_S = 0xA8,
+    I32_TRUNC_F32_U = 0xA9,
+    I32_TRUNC_F64_S = 0xAA,
+    I32_TRUNC_F64_U = 0xAB,
+    I64_TRUNC_F32_S = 0xAE,
+    I64_TRUNC_F32_U = 0xAF,
+    I64_TRUNC_F64_S = 0xB0,
+    I64_TRUNC_F64_U = 0xB1,
+
+    F32_CONVERT_I32_S = 0xB2,
+    F32_CONVERT_I32_U = 0xB3,
+    F32_CONVERT_I64_S = 0xB4,
+    F32_CONVERT_I64_U = 0xB5,
+    F32_DEMOTE_F64 = 0xB6,
+    F64_CONVERT_I32_S = 0xB7,
+    F64_CONVERT_I32_U = 0xB8,
+    F64_CONVERT_I64_S = 0xB9,
+    F64_CONVERT_I64_U = 0xBA,
+    F64_PROMOTE_F32 = 0xBB,
+}
+
+// encoded after the 0xFC prefix
+#[repr(u8)]
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[allow(non_camel_case_types)]
+pub enum WasmTruncSatKind {
+    I32_TRUNC_SAT_F32_S = 0x00,
+    I32_TRUNC_SAT_F32_U = 0x01,
+    I32_TRUNC_SAT_F64_S = 0x02,
+    I32_TRUNC_SAT_F64_U = 0x03,
+    I64_TRUNC_SAT_F32_S = 0x04,
+    I64_TRUNC_SAT_F32_U = 0x05,
+    I64_TRUNC_SAT_F64_S = 0x06,
+    I64_TRUNC_SAT_F64_U = 0x07,
 }
 
 #[repr(u8)]
@@ -123,6 +181,8 @@ pub enum WasmBinaryOpKind {
     I32_SHL = 0x74,
     I32_SHR_S = 0x75,
     I32_SHR_U = 0x76,
+    I32_ROTL = 0x77,
+    I32_ROTR = 0x78,
 
     I64_ADD = 0x7C,
     I64_SUB = 0x7D,
@@ -133,19 +193,28 @@ pub enum WasmBinaryOpKind {
     I64_REM_U = 0x82,
     I64_AND = 0x83,
     I64_OR = 0x84,
+    I64_XOR = 0x85,
     I64_SHL = 0x86,
     I64_SHR_S = 0x87,
     I64_SHR_U = 0x88,
+    I64_ROTL = 0x89,
+    I64_ROTR = 0x8A,
 
     F32_ADD = 0x92,
     F32_SUB = 0x93,
     F32_MUL = 0x94,
     F32_DIV = 0x95,
+    F32_MIN = 0x96,
+    F32_MAX = 0x97,
+    F32_COPYSIGN = 0x98,
 
     F64_ADD = 0xA0,
     F64_SUB = 0xA1,
     F64_MUL = 0xA2,
     F64_DIV = 0xA3,
+    F64_MIN = 0xA4,
+    F64_MAX = 0xA5,
+    F64_COPYSIGN = 0xA6,
 }
 
 #[repr(u8)]
@@ -228,6 +297,9 @@ pub enum WasmInstr {
     I64ExtendI32u,
     I64ReinterpretF64,
     F64ReinterpretI64,
+    TruncSat {
+        kind: WasmTruncSatKind,
+    },
     LocalGet {
         local_index: u32,
     },
@@ -349,12 +421,16 @@ impl WasmModule {
 
         self.write_function_section(output, section_buffer);
 
+        self.write_table_section(output, section_buffer);
+
         self.write_memory_section(output, section_buffer);
 
         self.write_global_section(output, section_buffer);
 
         self.write_export_section(output, section_buffer);
 
+        self.write_element_section(output, section_buffer);
+
         self.write_code_section(output, section_buffer);
 
         self.write_data_section(output, section_buffer);
@@ -426,6 +502,20 @@ impl WasmModule {
         write_section(output, section, 0x03);
     }
 
+    fn write_table_section(&self, output: &mut Vec<u8>, section: &mut Vec<u8>) {
+        if self.tables.len() == 0 {
+            return;
+        }
+
+        write_u32(section, self.tables.len() as u32);
+        for table in &self.tables {
+            write_u8(section, WasmType::FuncRef as u8);
+            write_limits(section, &table.limits);
+        }
+
+        write_section(output, section, 0x04);
+    }
+
     fn write_memory_section(&self, output: &mut Vec<u8>, section: &mut Vec<u8>) {
         if self.memories.len() == 0 {
             return;
@@ -478,6 +568,25 @@ impl WasmModule {
         write_section(output, section, 0x07);
     }
 
+    fn write_element_section(&self, output: &mut Vec<u8>, section: &mut Vec<u8>) {
+        if self.elements.len() == 0 {
+            return;
+        }
+
+        write_u32(section, self.elements.len() as u32);
+        for element in &self.elements {
+            let WasmElement::Passive { expr, fn_idx } = element;
+            write_u32(section, 0);
+            write_expr(section, expr);
+            write_u32(section, fn_idx.len() as u32);
+            for fn_index in fn_idx {
+                write_u32(section, *fn_index);
+            }
+        }
+
+        write_section(output, section, 0x09);
+    }
+
     fn write_code_section(&self, output: &mut Vec<u8>, section: &mut Vec<u8>) {
         if self.codes.len() == 0 {
             return;
@@ -719,6 +828,10 @@ fn write_instr(out: &mut Vec<u8>, instr: &WasmInstr) {
         WasmInstr::F64ReinterpretI64 => {
             write_u8(out, 0xBF);
         }
+        WasmInstr::TruncSat { kind } => {
+            write_u8(out, 0xFC);
+            write_u32(out, kind.clone() as u32);
+        }
         WasmInstr::MemoryCopy => {
             write_u8(out, 0xFC);
             write_u32(out, 10);
@@ -768,13 +881,21 @@ pub fn write_all(out: &mut Vec<u8>, value: &[u8]) {
 
 const CONTINUATION_BIT: u8 = 1 << 7;
 
+const SIGN_BIT: u8 = 1 << 6;
+
 fn leb128_write_signed(output: &mut Vec<u8>, mut val: i64) {
-    while val != 0 && val != -1 {
+    loop {
         let byte = (val as u8) & !CONTINUATION_BIT;
         val >>= 7;
+
+        // done once the remaining bits are just the sign extension of the last byte
+        if (val == 0 && byte & SIGN_BIT == 0) || (val == -1 && byte & SIGN_BIT != 0) {
+            output.push(byte);
+            return;
+        }
+
         output.push(byte | CONTINUATION_BIT);
     }
-    output.push((val as u8) & !CONTINUATION_BIT);
 }
 
 fn leb128_write_unsigned(output: &mut Vec<u8>, mut val: u64) {
@@ -0,0 +1,297 @@
+use crate::wasm::*;
+use alloc::{format, string::String, vec::Vec};
+
+pub struct WasmCompare<'a> {
+    v1: &'a WasmModule,
+    v2: &'a WasmModule,
+    differences: Vec<String>,
+}
+
+impl<'a> WasmCompare<'a> {
+    pub fn compare(v1: &'a WasmModule, v2: &'a WasmModule) -> Vec<String> {
+        let mut compare = WasmCompare {
+            v1,
+            v2,
+            differences: Vec::new(),
+        };
+
+        compare.compare_imports();
+        compare.compare_exports();
+        compare.compare_functions();
+
+        compare.differences
+    }
+
+    fn compare_imports(&mut self) {
+        for v1_import in &self.v1.imports {
+            let import_name = format!("{}.{}", v1_import.module_name, v1_import.item_name);
+
+            let Some(v2_import) = find_import(self.v2, v1_import) else {
+                self.report(format!("import {import_name}: only in v1"));
+                continue;
+            };
+
+            let v1_desc = describe_import(self.v1, v1_import);
+            let v2_desc = describe_import(self.v2, v2_import);
+            if v1_desc != v2_desc {
+                self.report(format!(
+                    "import {import_name}: v1 imports {v1_desc}, v2 imports {v2_desc}"
+                ));
+            }
+        }
+
+        for v2_import in &self.v2.imports {
+            if let None = find_import(self.v1, v2_import) {
+                self.report(format!(
+                    "import {}.{}: only in v2",
+                    v2_import.module_name, v2_import.item_name
+                ));
+            }
+        }
+    }
+
+    fn compare_exports(&mut self) {
+        for v1_export in &self.v1.exports {
+            let export_name = &v1_export.export_name;
+
+            let Some(v2_export) = find_export(self.v2, export_name) else {
+                self.report(format!("export \"{export_name}\": only in v1"));
+                continue;
+            };
+
+            let v1_desc = describe_export(self.v1, v1_export);
+            let v2_desc = describe_export(self.v2, v2_export);
+            if v1_desc != v2_desc {
+                self.report(format!(
+                    "export \"{export_name}\": v1 exports {v1_desc}, v2 exports {v2_desc}"
+                ));
+            }
+        }
+
+        for v2_export in &self.v2.exports {
+            if let None = find_export(self.v1, &v2_export.export_name) {
+                self.report(format!("export \"{}\": only in v2", v2_export.export_name));
+            }
+        }
+    }
+
+    fn compare_functions(&mut self) {
+        let v1_imports_len = get_fn_imports_len(self.v1);
+        let v2_imports_len = get_fn_imports_len(self.v2);
+
+        for v1_code_index in 0..self.v1.codes.len() {
+            let v1_fn_index = v1_imports_len + v1_code_index as u32;
+            let fn_name = get_fn_name(self.v1, v1_fn_index);
+
+            let Some(v2_fn_index) = find_fn_index(self.v2, &fn_name) else {
+                self.report(format!("fn {fn_name}: only in v1"));
+                continue;
+            };
+
+            if v2_fn_index < v2_imports_len {
+                self.report(format!("fn {fn_name}: defined in v1, imported in v2"));
+                continue;
+            }
+
+            let v1_fn_type = describe_fn_type(self.v1, v1_fn_index);
+            let v2_fn_type = describe_fn_type(self.v2, v2_fn_index);
+            if v1_fn_type != v2_fn_type {
+                self.report(format!(
+                    "fn {fn_name}: v1 type is {v1_fn_type}, v2 type is {v2_fn_type}"
+                ));
+            }
+
+            let v1_code = &self.v1.codes[v1_code_index];
+            let v2_code = &self.v2.codes[(v2_fn_index - v2_imports_len) as usize];
+
+            let v1_locals = describe_locals(&v1_code.locals);
+            let v2_locals = describe_locals(&v2_code.locals);
+            if v1_locals != v2_locals {
+                self.report(format!(
+                    "fn {fn_name}: v1 locals are {v1_locals}, v2 locals are {v2_locals}"
+                ));
+            }
+
+            self.compare_instrs(&fn_name, &v1_code.expr.instrs, &v2_code.expr.instrs);
+        }
+
+        for v2_code_index in 0..self.v2.codes.len() {
+            let v2_fn_index = v2_imports_len + v2_code_index as u32;
+            let fn_name = get_fn_name(self.v2, v2_fn_index);
+
+            if let None = find_fn_index(self.v1, &fn_name) {
+                self.report(format!("fn {fn_name}: only in v2"));
+            }
+        }
+    }
+
+    fn compare_instrs(&mut self, fn_name: &str, v1_instrs: &[WasmInstr], v2_instrs: &[WasmInstr]) {
+        for (instr_index, (v1_instr, v2_instr)) in v1_instrs.iter().zip(v2_instrs).enumerate() {
+            let v1_instr = describe_instr(self.v1, v1_instr);
+            let v2_instr = describe_instr(self.v2, v2_instr);
+
+            // only the first mismatch is reported, the rest is usually noise
+            if v1_instr != v2_instr {
+                self.report(format!(
+                    "fn {fn_name}: instruction #{instr_index} differs, v1: {v1_instr}, v2: {v2_instr}"
+                ));
+                return;
+            }
+        }
+
+        if v1_instrs.len() != v2_instrs.len() {
+            self.report(format!(
+                "fn {fn_name}: v1 has {} instructions, v2 has {}",
+                v1_instrs.len(),
+                v2_instrs.len()
+            ));
+        }
+    }
+
+    fn report(&mut self, difference: String) {
+        self.differences.push(difference);
+    }
+}
+
+fn find_import<'a>(module: &'a WasmModule, import: &WasmImport) -> Option<&'a WasmImport> {
+    module.imports.iter().find(|other| {
+        other.module_name == import.module_name && other.item_name == import.item_name
+    })
+}
+
+fn find_export<'a>(module: &'a WasmModule, export_name: &str) -> Option<&'a WasmExport> {
+    module
+        .exports
+        .iter()
+        .find(|export| export.export_name == export_name)
+}
+
+fn find_fn_index(module: &WasmModule, fn_name: &str) -> Option<u32> {
+    let fns_len = get_fn_imports_len(module) + module.functions.len() as u32;
+    (0..fns_len).find(|fn_index| get_fn_name(module, *fn_index) == fn_name)
+}
+
+fn get_fn_imports_len(module: &WasmModule) -> u32 {
+    let mut fn_imports_len = 0;
+    for import in &module.imports {
+        if let WasmImportDesc::Func { type_index: _ } = import.item_desc {
+            fn_imports_len += 1;
+        }
+    }
+
+    fn_imports_len
+}
+
+fn get_fn_type_index(module: &WasmModule, fn_index: u32) -> Option<u32> {
+    let fn_imports_len = get_fn_imports_len(module);
+    if fn_index >= fn_imports_len {
+        return module
+            .functions
+            .get((fn_index - fn_imports_len) as usize)
+            .cloned();
+    }
+
+    let mut fn_import_index = 0;
+    for import in &module.imports {
+        if let WasmImportDesc::Func { type_index } = import.item_desc {
+            if fn_import_index == fn_index {
+                return Some(type_index);
+            }
+
+            fn_import_index += 1;
+        }
+    }
+
+    None
+}
+
+fn get_fn_name(module: &WasmModule, fn_index: u32) -> String {
+    for fn_info in &module.debug_fn_info {
+        if fn_info.fn_index == fn_index {
+            return fn_info.fn_name.clone();
+        }
+    }
+
+    let mut fn_import_index = 0;
+    for import in &module.imports {
+        if let WasmImportDesc::Func { type_index: _ } = import.item_desc {
+            if fn_import_index == fn_index {
+                return format!("{}.{}", import.module_name, import.item_name);
+            }
+
+            fn_import_index += 1;
+        }
+    }
+
+    format!("<fn #{fn_index}>")
+}
+
+fn describe_fn_type(module: &WasmModule, fn_index: u32) -> String {
+    let Some(type_index) = get_fn_type_index(module, fn_index) else {
+        return format!("<unknown fn #{fn_index}>");
+    };
+
+    describe_type(module, type_index)
+}
+
+fn describe_type(module: &WasmModule, type_index: u32) -> String {
+    let Some(fn_type) = module.types.get(type_index as usize) else {
+        return format!("<unknown type #{type_index}>");
+    };
+
+    format!("{:?} -> {:?}", fn_type.inputs, fn_type.outputs)
+}
+
+fn describe_locals(locals: &Vec<WasmLocals>) -> String {
+    let mut local_types = Vec::new();
+    for locals_of_type in locals {
+        for _ in 0..locals_of_type.count {
+            local_types.push(&locals_of_type.value_type);
+        }
+    }
+
+    format!("{:?}", local_types)
+}
+
+fn describe_import(module: &WasmModule, import: &WasmImport) -> String {
+    match &import.item_desc {
+        WasmImportDesc::Func { type_index } => {
+            format!("fn {}", describe_type(module, *type_index))
+        }
+        WasmImportDesc::Memory(limits) => format!("memory {:?}", limits),
+    }
+}
+
+fn describe_export(module: &WasmModule, export: &WasmExport) -> String {
+    match export.export_type {
+        WasmExportType::Func => format!(
+            "fn {}: {}",
+            get_fn_name(module, export.exported_item_index),
+            describe_fn_type(module, export.exported_item_index)
+        ),
+        WasmExportType::Mem => format!("memory #{}", export.exported_item_index),
+    }
+}
+
+// type and fn indicies are resolved since they depend on definition order
+fn describe_instr(module: &WasmModule, instr: &WasmInstr) -> String {
+    match instr {
+        WasmInstr::Call { fn_index } => format!("Call {}", get_fn_name(module, *fn_index)),
+        WasmInstr::CallIndirect {
+            type_index,
+            table_index,
+        } => format!(
+            "CallIndirect {} (table #{table_index})",
+            describe_type(module, *type_index)
+        ),
+        WasmInstr::BlockStart {
+            block_kind,
+            block_type: WasmBlockType::InOut { type_index },
+        } => format!(
+            "BlockStart {:?} {}",
+            block_kind,
+            describe_type(module, *type_index)
+        ),
+        _ => format!("{:?}", instr),
+    }
+}
@@ -1,4 +1,8 @@
 use crate::{core::*, wasm::*};
+use ::core::intrinsics::{
+    ceilf32, ceilf64, copysignf32, copysignf64, fabsf32, fabsf64, floorf32, floorf64, rintf32,
+    rintf64, sqrtf32, sqrtf64, truncf32, truncf64,
+};
 use alloc::{
     alloc::{alloc, dealloc, Layout},
     format, str,
@@ -449,11 +453,257 @@ impl WasmEval {
                         let value = if op == 0 { 1 } else { 0 };
                         self.stack.push(WasmValue::I32 { value });
                     }
+                    WasmUnaryOpKind::I32_CLZ => {
+                        let op = self.pop_i32();
+                        let value = op.leading_zeros() as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmUnaryOpKind::I32_CTZ => {
+                        let op = self.pop_i32();
+                        let value = op.trailing_zeros() as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmUnaryOpKind::I32_POPCNT => {
+                        let op = self.pop_i32();
+                        let value = op.count_ones() as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmUnaryOpKind::I64_CLZ => {
+                        let op = self.pop_i64();
+                        let value = op.leading_zeros() as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmUnaryOpKind::I64_CTZ => {
+                        let op = self.pop_i64();
+                        let value = op.trailing_zeros() as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmUnaryOpKind::I64_POPCNT => {
+                        let op = self.pop_i64();
+                        let value = op.count_ones() as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
                     WasmUnaryOpKind::F64_NEG => {
                         let op = self.pop_f64();
                         let value = -op;
                         self.stack.push(WasmValue::F64 { value });
                     }
+
+                    WasmUnaryOpKind::F32_ABS => {
+                        let op = self.pop_f32();
+                        let value = unsafe { fabsf32(op) };
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_NEG => {
+                        let op = self.pop_f32();
+                        let value = -op;
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_CEIL => {
+                        let op = self.pop_f32();
+                        let value = unsafe { ceilf32(op) };
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_FLOOR => {
+                        let op = self.pop_f32();
+                        let value = unsafe { floorf32(op) };
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_TRUNC => {
+                        let op = self.pop_f32();
+                        let value = unsafe { truncf32(op) };
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_NEAREST => {
+                        let op = self.pop_f32();
+                        let value = unsafe { rintf32(op) };
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_SQRT => {
+                        let op = self.pop_f32();
+                        let value = unsafe { sqrtf32(op) };
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F64_ABS => {
+                        let op = self.pop_f64();
+                        let value = unsafe { fabsf64(op) };
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_CEIL => {
+                        let op = self.pop_f64();
+                        let value = unsafe { ceilf64(op) };
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_FLOOR => {
+                        let op = self.pop_f64();
+                        let value = unsafe { floorf64(op) };
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_TRUNC => {
+                        let op = self.pop_f64();
+                        let value = unsafe { truncf64(op) };
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_NEAREST => {
+                        let op = self.pop_f64();
+                        let value = unsafe { rintf64(op) };
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_SQRT => {
+                        let op = self.pop_f64();
+                        let value = unsafe { sqrtf64(op) };
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+
+                    WasmUnaryOpKind::I32_TRUNC_F32_S => {
+                        let op = self.pop_f32() as f64;
+                        self.check_trunc_range(op, -2_147_483_649.0, 2_147_483_648.0)?;
+                        let value = op as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmUnaryOpKind::I32_TRUNC_F32_U => {
+                        let op = self.pop_f32() as f64;
+                        self.check_trunc_range(op, -1.0, 4_294_967_296.0)?;
+                        let value = op as u32 as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmUnaryOpKind::I32_TRUNC_F64_S => {
+                        let op = self.pop_f64();
+                        self.check_trunc_range(op, -2_147_483_649.0, 2_147_483_648.0)?;
+                        let value = op as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmUnaryOpKind::I32_TRUNC_F64_U => {
+                        let op = self.pop_f64();
+                        self.check_trunc_range(op, -1.0, 4_294_967_296.0)?;
+                        let value = op as u32 as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmUnaryOpKind::I64_TRUNC_F32_S => {
+                        let op = self.pop_f32() as f64;
+                        self.check_trunc_range(
+                            op,
+                            -9_223_372_036_854_777_856.0,
+                            9_223_372_036_854_775_808.0,
+                        )?;
+                        let value = op as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmUnaryOpKind::I64_TRUNC_F32_U => {
+                        let op = self.pop_f32() as f64;
+                        self.check_trunc_range(op, -1.0, 18_446_744_073_709_551_616.0)?;
+                        let value = op as u64 as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmUnaryOpKind::I64_TRUNC_F64_S => {
+                        let op = self.pop_f64();
+                        self.check_trunc_range(
+                            op,
+                            -9_223_372_036_854_777_856.0,
+                            9_223_372_036_854_775_808.0,
+                        )?;
+                        let value = op as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmUnaryOpKind::I64_TRUNC_F64_U => {
+                        let op = self.pop_f64();
+                        self.check_trunc_range(op, -1.0, 18_446_744_073_709_551_616.0)?;
+                        let value = op as u64 as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmUnaryOpKind::F32_CONVERT_I32_S => {
+                        let op = self.pop_i32();
+                        let value = op as f32;
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_CONVERT_I32_U => {
+                        let op = self.pop_i32();
+                        let value = op as u32 as f32;
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_CONVERT_I64_S => {
+                        let op = self.pop_i64();
+                        let value = op as f32;
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_CONVERT_I64_U => {
+                        let op = self.pop_i64();
+                        let value = op as u64 as f32;
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F32_DEMOTE_F64 => {
+                        let op = self.pop_f64();
+                        let value = op as f32;
+                        self.stack.push(WasmValue::F32 { value });
+                    }
+                    WasmUnaryOpKind::F64_CONVERT_I32_S => {
+                        let op = self.pop_i32();
+                        let value = op as f64;
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_CONVERT_I32_U => {
+                        let op = self.pop_i32();
+                        let value = op as u32 as f64;
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_CONVERT_I64_S => {
+                        let op = self.pop_i64();
+                        let value = op as f64;
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_CONVERT_I64_U => {
+                        let op = self.pop_i64();
+                        let value = op as u64 as f64;
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                    WasmUnaryOpKind::F64_PROMOTE_F32 => {
+                        let op = self.pop_f32();
+                        let value = op as f64;
+                        self.stack.push(WasmValue::F64 { value });
+                    }
+                },
+                // Rust float to int `as` casts saturate and map NaN to 0 just like these
+                WasmInstr::TruncSat { kind } => match kind {
+                    WasmTruncSatKind::I32_TRUNC_SAT_F32_S => {
+                        let op = self.pop_f32();
+                        let value = op as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmTruncSatKind::I32_TRUNC_SAT_F32_U => {
+                        let op = self.pop_f32();
+                        let value = op as u32 as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmTruncSatKind::I32_TRUNC_SAT_F64_S => {
+                        let op = self.pop_f64();
+                        let value = op as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmTruncSatKind::I32_TRUNC_SAT_F64_U => {
+                        let op = self.pop_f64();
+                        let value = op as u32 as i32;
+                        self.stack.push(WasmValue::I32 { value });
+                    }
+                    WasmTruncSatKind::I64_TRUNC_SAT_F32_S => {
+                        let op = self.pop_f32();
+                        let value = op as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmTruncSatKind::I64_TRUNC_SAT_F32_U => {
+                        let op = self.pop_f32();
+                        let value = op as u64 as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmTruncSatKind::I64_TRUNC_SAT_F64_S => {
+                        let op = self.pop_f64();
+                        let value = op as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
+                    WasmTruncSatKind::I64_TRUNC_SAT_F64_U => {
+                        let op = self.pop_f64();
+                        let value = op as u64 as i64;
+                        self.stack.push(WasmValue::I64 { value });
+                    }
                 },
                 WasmInstr::BinaryOp { kind } => match kind {
                     WasmBinaryOpKind::I32_ADD => {
@@ -492,6 +742,18 @@ impl WasmEval {
                         let value = lhs ^ rhs;
                         self.stack.push(WasmValue::I32 { value })
                     }
+                    WasmBinaryOpKind::I32_ROTL => {
+                        let rhs = self.pop_i32();
+                        let lhs = self.pop_i32();
+                        let value = lhs.rotate_left(rhs as u32 % 32);
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::I32_ROTR => {
+                        let rhs = self.pop_i32();
+                        let lhs = self.pop_i32();
+                        let value = lhs.rotate_right(rhs as u32 % 32);
+                        self.stack.push(WasmValue::I32 { value })
+                    }
                     WasmBinaryOpKind::I32_DIV_U => {
                         let rhs = self.pop_i32();
                         let lhs = self.pop_i32();
@@ -619,6 +881,24 @@ impl WasmEval {
                         let value = (lhs as u64 % rhs as u64) as i64;
                         self.stack.push(WasmValue::I64 { value })
                     }
+                    WasmBinaryOpKind::I64_XOR => {
+                        let rhs = self.pop_i64();
+                        let lhs = self.pop_i64();
+                        let value = lhs ^ rhs;
+                        self.stack.push(WasmValue::I64 { value })
+                    }
+                    WasmBinaryOpKind::I64_ROTL => {
+                        let rhs = self.pop_i64();
+                        let lhs = self.pop_i64();
+                        let value = lhs.rotate_left((rhs as u64 % 64) as u32);
+                        self.stack.push(WasmValue::I64 { value })
+                    }
+                    WasmBinaryOpKind::I64_ROTR => {
+                        let rhs = self.pop_i64();
+                        let lhs = self.pop_i64();
+                        let value = lhs.rotate_right((rhs as u64 % 64) as u32);
+                        self.stack.push(WasmValue::I64 { value })
+                    }
                     WasmBinaryOpKind::I64_NE
                     | WasmBinaryOpKind::I64_LT_S
                     | WasmBinaryOpKind::I64_LT_U
@@ -638,27 +918,163 @@ impl WasmEval {
                     | WasmBinaryOpKind::I64_SHR_S
                     | WasmBinaryOpKind::I64_SHR_U => todo!("{kind:?}"),
 
-                    WasmBinaryOpKind::F32_EQ
-                    | WasmBinaryOpKind::F32_NE
-                    | WasmBinaryOpKind::F32_LT
-                    | WasmBinaryOpKind::F32_GT
-                    | WasmBinaryOpKind::F32_LE
-                    | WasmBinaryOpKind::F32_GE
-                    | WasmBinaryOpKind::F32_ADD
-                    | WasmBinaryOpKind::F32_SUB
-                    | WasmBinaryOpKind::F32_MUL
-                    | WasmBinaryOpKind::F32_DIV => todo!("{kind:?}"),
-
-                    WasmBinaryOpKind::F64_EQ
-                    | WasmBinaryOpKind::F64_NE
-                    | WasmBinaryOpKind::F64_LT
-                    | WasmBinaryOpKind::F64_GT
-                    | WasmBinaryOpKind::F64_LE
-                    | WasmBinaryOpKind::F64_GE
-                    | WasmBinaryOpKind::F64_ADD
-                    | WasmBinaryOpKind::F64_SUB
-                    | WasmBinaryOpKind::F64_MUL
-                    | WasmBinaryOpKind::F64_DIV => todo!("{kind:?}"),
+                    WasmBinaryOpKind::F32_EQ => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = if lhs == rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F32_NE => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = if lhs != rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F32_LT => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = if lhs < rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F32_GT => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = if lhs > rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F32_LE => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = if lhs <= rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F32_GE => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = if lhs >= rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F32_ADD => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = lhs + rhs;
+                        self.stack.push(WasmValue::F32 { value })
+                    }
+                    WasmBinaryOpKind::F32_SUB => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = lhs - rhs;
+                        self.stack.push(WasmValue::F32 { value })
+                    }
+                    WasmBinaryOpKind::F32_MUL => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = lhs * rhs;
+                        self.stack.push(WasmValue::F32 { value })
+                    }
+                    WasmBinaryOpKind::F32_DIV => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = lhs / rhs;
+                        self.stack.push(WasmValue::F32 { value })
+                    }
+                    WasmBinaryOpKind::F32_MIN => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = float_min(lhs as f64, rhs as f64) as f32;
+                        self.stack.push(WasmValue::F32 { value })
+                    }
+                    WasmBinaryOpKind::F32_MAX => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = float_max(lhs as f64, rhs as f64) as f32;
+                        self.stack.push(WasmValue::F32 { value })
+                    }
+                    WasmBinaryOpKind::F32_COPYSIGN => {
+                        let rhs = self.pop_f32();
+                        let lhs = self.pop_f32();
+                        let value = unsafe { copysignf32(lhs, rhs) };
+                        self.stack.push(WasmValue::F32 { value })
+                    }
+
+                    WasmBinaryOpKind::F64_EQ => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = if lhs == rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F64_NE => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = if lhs != rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F64_LT => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = if lhs < rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F64_GT => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = if lhs > rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F64_LE => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = if lhs <= rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F64_GE => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = if lhs >= rhs { 1 } else { 0 };
+                        self.stack.push(WasmValue::I32 { value })
+                    }
+                    WasmBinaryOpKind::F64_ADD => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = lhs + rhs;
+                        self.stack.push(WasmValue::F64 { value })
+                    }
+                    WasmBinaryOpKind::F64_SUB => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = lhs - rhs;
+                        self.stack.push(WasmValue::F64 { value })
+                    }
+                    WasmBinaryOpKind::F64_MUL => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = lhs * rhs;
+                        self.stack.push(WasmValue::F64 { value })
+                    }
+                    WasmBinaryOpKind::F64_DIV => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = lhs / rhs;
+                        self.stack.push(WasmValue::F64 { value })
+                    }
+                    WasmBinaryOpKind::F64_MIN => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = float_min(lhs, rhs);
+                        self.stack.push(WasmValue::F64 { value })
+                    }
+                    WasmBinaryOpKind::F64_MAX => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = float_max(lhs, rhs);
+                        self.stack.push(WasmValue::F64 { value })
+                    }
+                    WasmBinaryOpKind::F64_COPYSIGN => {
+                        let rhs = self.pop_f64();
+                        let lhs = self.pop_f64();
+                        let value = unsafe { copysignf64(lhs, rhs) };
+                        self.stack.push(WasmValue::F64 { value })
+                    }
                 },
             }
 
@@ -751,6 +1167,33 @@ impl WasmEval {
         value
     }
 
+    // trapping truncations only accept values strictly between the bounds
+    fn check_trunc_range(&mut self, value: f64, min: f64, max: f64) -> Result<(), EvalError> {
+        if value.is_nan() {
+            return Err(self.err_with_stack("Invalid conversion to integer"));
+        }
+
+        if value <= min || value >= max {
+            return Err(self.err_with_stack("Integer overflow"));
+        }
+
+        Ok(())
+    }
+
+    fn pop_f32(&mut self) -> f32 {
+        let wasm_value = self.stack.pop().unwrap();
+        let WasmValue::F32 { value } = wasm_value else {
+            let err = self.err_with_stack(format!(
+                "Trying to pop F32 but got {:?}",
+                wasm_value.get_type()
+            ));
+            stderr_write(format!("Error: {}\n", err.message));
+            proc_exit(1);
+        };
+
+        value
+    }
+
     fn pop_f64(&mut self) -> f64 {
         let wasm_value = self.stack.pop().unwrap();
         let WasmValue::F64 { value } = wasm_value else {
@@ -1363,3 +1806,36 @@ fn call_host_fn(eval: &mut WasmEval, fn_index: u32) -> Result<(), EvalError> {
 fn unsafe_borrow<T>(x: &T) -> &'static T {
     unsafe { &*(x as *const T) }
 }
+
+// unlike `f64::min`/`f64::max`, wasm propagates NaN and orders -0 below +0
+fn float_min(lhs: f64, rhs: f64) -> f64 {
+    if lhs.is_nan() || rhs.is_nan() {
+        return f64::NAN;
+    }
+
+    if lhs == rhs {
+        return if lhs.is_sign_negative() { lhs } else { rhs };
+    }
+
+    if lhs < rhs {
+        lhs
+    } else {
+        rhs
+    }
+}
+
+fn float_max(lhs: f64, rhs: f64) -> f64 {
+    if lhs.is_nan() || rhs.is_nan() {
+        return f64::NAN;
+    }
+
+    if lhs == rhs {
+        return if lhs.is_sign_positive() { lhs } else { rhs };
+    }
+
+    if lhs > rhs {
+        lhs
+    } else {
+        rhs
+    }
+}
@@ -337,9 +337,35 @@ impl WasmParser {
 
     // TODO: support parsing custom section
     fn parse_custom_section(&mut self) -> Result<(), String> {
-        let _section_size = self.parse_u32()?;
+        let section_size = self.parse_u32()?;
+        let section_end = self.offset + section_size as usize;
 
-        self.expect_many(_section_size as usize)?;
+        let section_name = self.parse_string()?;
+        if section_name != "name" {
+            self.expect_many(section_end - self.offset)?;
+            return Ok(());
+        }
+
+        while self.offset < section_end {
+            let subsection_id = self.expect_any()?;
+            let subsection_size = self.parse_u32()?;
+
+            // only function names are used
+            if subsection_id != 1 {
+                self.expect_many(subsection_size as usize)?;
+                continue;
+            }
+
+            let fn_names_len = self.parse_u32()?;
+            for _ in 0..fn_names_len {
+                let fn_index = self.parse_u32()?;
+                let fn_name = self.parse_string()?;
+
+                self.module
+                    .debug_fn_info
+                    .push(WasmDebugFnInfo { fn_index, fn_name });
+            }
+        }
 
         Ok(())
     }
@@ -549,11 +575,55 @@ impl WasmParser {
                     let value = f64::from_le_bytes(bytes.try_into().unwrap());
                     expr.instrs.push(WasmInstr::F64Const { value });
                 }
-                op_code @ (0x45 | 0x50 | 0x9A) => {
+                op_code @ (0x45
+                | 0x50
+                | 0x67..=0x69
+                | 0x79..=0x7B
+                | 0x8B..=0x91
+                | 0x99..=0x9F
+                | 0xA8..=0xAB
+                | 0xAE..=0xBB) => {
                     let unary_op_kind = match op_code {
                         0x45 => WasmUnaryOpKind::I32_EQZ,
                         0x50 => WasmUnaryOpKind::I64_EQZ,
+                        0x67 => WasmUnaryOpKind::I32_CLZ,
+                        0x68 => WasmUnaryOpKind::I32_CTZ,
+                        0x69 => WasmUnaryOpKind::I32_POPCNT,
+                        0x79 => WasmUnaryOpKind::I64_CLZ,
+                        0x7A => WasmUnaryOpKind::I64_CTZ,
+                        0x7B => WasmUnaryOpKind::I64_POPCNT,
+                        0x8B => WasmUnaryOpKind::F32_ABS,
+                        0x8C => WasmUnaryOpKind::F32_NEG,
+                        0x8D => WasmUnaryOpKind::F32_CEIL,
+                        0x8E => WasmUnaryOpKind::F32_FLOOR,
+                        0x8F => WasmUnaryOpKind::F32_TRUNC,
+                        0x90 => WasmUnaryOpKind::F32_NEAREST,
+                        0x91 => WasmUnaryOpKind::F32_SQRT,
+                        0x99 => WasmUnaryOpKind::F64_ABS,
                         0x9A => WasmUnaryOpKind::F64_NEG,
+                        0x9B => WasmUnaryOpKind::F64_CEIL,
+                        0x9C => WasmUnaryOpKind::F64_FLOOR,
+                        0x9D => WasmUnaryOpKind::F64_TRUNC,
+                        0x9E => WasmUnaryOpKind::F64_NEAREST,
+                        0x9F => WasmUnaryOpKind::F64_SQRT,
+                        0xA8 => WasmUnaryOpKind::I32_TRUNC_F32_S,
+                        0xA9 => WasmUnaryOpKind::I32_TRUNC_F32_U,
+                        0xAA => WasmUnaryOpKind::I32_TRUNC_F64_S,
+                        0xAB => WasmUnaryOpKind::I32_TRUNC_F64_U,
+                        0xAE => WasmUnaryOpKind::I64_TRUNC_F32_S,
+                        0xAF => WasmUnaryOpKind::I64_TRUNC_F32_U,
+                        0xB0 => WasmUnaryOpKind::I64_TRUNC_F64_S,
+                        0xB1 => WasmUnaryOpKind::I64_TRUNC_F64_U,
+                        0xB2 => WasmUnaryOpKind::F32_CONVERT_I32_S,
+                        0xB3 => WasmUnaryOpKind::F32_CONVERT_I32_U,
+                        0xB4 => WasmUnaryOpKind::F32_CONVERT_I64_S,
+                        0xB5 => WasmUnaryOpKind::F32_CONVERT_I64_U,
+                        0xB6 => WasmUnaryOpKind::F32_DEMOTE_F64,
+                        0xB7 => WasmUnaryOpKind::F64_CONVERT_I32_S,
+                        0xB8 => WasmUnaryOpKind::F64_CONVERT_I32_U,
+                        0xB9 => WasmUnaryOpKind::F64_CONVERT_I64_S,
+                        0xBA => WasmUnaryOpKind::F64_CONVERT_I64_U,
+                        0xBB => WasmUnaryOpKind::F64_PROMOTE_F32,
                         _ => unreachable!(),
                     };
 
@@ -565,10 +635,11 @@ impl WasmParser {
                 | 0x51 | 0x52 | 0x53 | 0x54 | 0x55 | 0x56 | 0x57 | 0x58 | 0x59
                 | 0x5A | 0x5B | 0x5C | 0x5D | 0x5E | 0x5F | 0x60 | 0x61 | 0x62
                 | 0x63 | 0x64 | 0x65 | 0x66 | 0x6A | 0x6B | 0x6C | 0x6D | 0x6E
-                | 0x6F | 0x70 | 0x71 | 0x72 | 0x73 | 0x74 | 0x75 | 0x76 | 0x7C
-                | 0x7D | 0x7E | 0x7F | 0x80 | 0x81 | 0x82 | 0x83 | 0x84 | 0x86
-                | 0x87 | 0x88 | 0x92 | 0x93 | 0x94 | 0x95 | 0xA0 | 0xA1 | 0xA2
-                | 0xA3) => {
+                | 0x6F | 0x70 | 0x71 | 0x72 | 0x73 | 0x74 | 0x75 | 0x76 | 0x77
+                | 0x78 | 0x7C | 0x7D | 0x7E | 0x7F | 0x80 | 0x81 | 0x82 | 0x83
+                | 0x84 | 0x85 | 0x86 | 0x87 | 0x88 | 0x89 | 0x8A | 0x92 | 0x93
+                | 0x94 | 0x95 | 0x96 | 0x97 | 0x98 | 0xA0 | 0xA1 | 0xA2 | 0xA3
+                | 0xA4 | 0xA5 | 0xA6) => {
                     let binary_op_kind = match op_code {
                         0x46 => WasmBinaryOpKind::I32_EQ,
                         0x47 => WasmBinaryOpKind::I32_NE,
@@ -615,6 +686,8 @@ impl WasmParser {
                         0x74 => WasmBinaryOpKind::I32_SHL,
                         0x75 => WasmBinaryOpKind::I32_SHR_S,
                         0x76 => WasmBinaryOpKind::I32_SHR_U,
+                        0x77 => WasmBinaryOpKind::I32_ROTL,
+                        0x78 => WasmBinaryOpKind::I32_ROTR,
                         0x7C => WasmBinaryOpKind::I64_ADD,
                         0x7D => WasmBinaryOpKind::I64_SUB,
                         0x7E => WasmBinaryOpKind::I64_MUL,
@@ -624,17 +697,26 @@ impl WasmParser {
                         0x82 => WasmBinaryOpKind::I64_REM_U,
                         0x83 => WasmBinaryOpKind::I64_AND,
                         0x84 => WasmBinaryOpKind::I64_OR,
+                        0x85 => WasmBinaryOpKind::I64_XOR,
                         0x86 => WasmBinaryOpKind::I64_SHL,
                         0x87 => WasmBinaryOpKind::I64_SHR_S,
                         0x88 => WasmBinaryOpKind::I64_SHR_U,
+                        0x89 => WasmBinaryOpKind::I64_ROTL,
+                        0x8A => WasmBinaryOpKind::I64_ROTR,
                         0x92 => WasmBinaryOpKind::F32_ADD,
                         0x93 => WasmBinaryOpKind::F32_SUB,
                         0x94 => WasmBinaryOpKind::F32_MUL,
                         0x95 => WasmBinaryOpKind::F32_DIV,
+                        0x96 => WasmBinaryOpKind::F32_MIN,
+                        0x97 => WasmBinaryOpKind::F32_MAX,
+                        0x98 => WasmBinaryOpKind::F32_COPYSIGN,
                         0xA0 => WasmBinaryOpKind::F64_ADD,
                         0xA1 => WasmBinaryOpKind::F64_SUB,
                         0xA2 => WasmBinaryOpKind::F64_MUL,
                         0xA3 => WasmBinaryOpKind::F64_DIV,
+                        0xA4 => WasmBinaryOpKind::F64_MIN,
+                        0xA5 => WasmBinaryOpKind::F64_MAX,
+                        0xA6 => WasmBinaryOpKind::F64_COPYSIGN,
                         _ => unreachable!(),
                     };
                     expr.instrs.push(WasmInstr::BinaryOp {
@@ -660,6 +742,23 @@ impl WasmParser {
                     let memory_op_kind = self.parse_u32()?;
 
                     match memory_op_kind {
+                        op_code @ 0..=7 => {
+                            let trunc_sat_kind = match op_code {
+                                0 => WasmTruncSatKind::I32_TRUNC_SAT_F32_S,
+                                1 => WasmTruncSatKind::I32_TRUNC_SAT_F32_U,
+                                2 => WasmTruncSatKind::I32_TRUNC_SAT_F64_S,
+                                3 => WasmTruncSatKind::I32_TRUNC_SAT_F64_U,
+                                4 => WasmTruncSatKind::I64_TRUNC_SAT_F32_S,
+                                5 => WasmTruncSatKind::I64_TRUNC_SAT_F32_U,
+                                6 => WasmTruncSatKind::I64_TRUNC_SAT_F64_S,
+                                7 => WasmTruncSatKind::I64_TRUNC_SAT_F64_U,
+                                _ => unreachable!(),
+                            };
+
+                            expr.instrs.push(WasmInstr::TruncSat {
+                                kind: trunc_sat_kind,
+                            });
+                        }
                         // memory.copy
                         10 => {
                             if !self.eat(0x00) {
@@ -785,12 +884,12 @@ impl WasmParser {
             byte = self.expect_any()?;
 
             result |= ((byte & 0x7F) as i32) << shift;
+            shift += 7;
 
             if byte & 0x80 == 0 {
                 break;
             }
 
-            shift += 7;
             if shift >= 32 {
                 return Err(format!(
                     "{} LEB128 i32 overflow",
@@ -815,12 +914,12 @@ impl WasmParser {
             byte = self.expect_any()?;
 
             result |= ((byte & 0x7F) as i64) << shift;
+            shift += 7;
 
             if byte & 0x80 == 0 {
                 break;
             }
 
-            shift += 7;
             if shift >= 64 {
                 return Err(format!(
                     "{} LEB128 i64 overflow",
@@ -829,7 +928,7 @@ impl WasmParser {
             }
         }
 
-        if (shift < 32) && (byte & 0x40 != 0) {
+        if (shift < 64) && (byte & 0x40 != 0) {
             result |= !0 << shift;
         }
 
@@ -260,6 +260,43 @@ async function testCommand() {
         assert.strictEqual(result, 138);
     });
 
+    testCompilers("compiles labeled-loops.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/labeled-loops.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.labeled_break(), 26);
+        assert.strictEqual(program.labeled_continue(), 15);
+        assert.strictEqual(program.labeled_loop_continue(), 25);
+        assert.strictEqual(program.loop_value(), 8);
+        assert.strictEqual(program.nested_value(), 37);
+    });
+
+    testCompilers("compiles ranges.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/ranges.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.while_loop(), 25);
+        assert.strictEqual(program.labeled_while(), 3);
+        assert.strictEqual(program.inclusive(), 55);
+        assert.strictEqual(program.stepped(), 369);
+        assert.strictEqual(program.descending(), 543210);
+        assert.strictEqual(program.descending_exclusive(), 951);
+        assert.strictEqual(program.empty(), 0);
+        // ranges ending at the bounds of the counter type must not overflow
+        assert.strictEqual(program.count_u8(0, 255), 256);
+        assert.strictEqual(program.count_down_u32(5, 0), 3);
+        assert.strictEqual(program.count_down_u32(1, 0), 1);
+        assert.strictEqual(program.count_down_u32(4294967295, 4294967290), 3);
+        assert.strictEqual(program.sum_signed(3, -3), 0);
+        assert.strictEqual(program.sum_signed(-2147483647, -2147483648), -2147483647);
+        assert.strictEqual(
+            program.count_u64(18446744073709551610n, 18446744073709551615n),
+            3
+        );
+    });
+
     testCompilers("compiles methods.lo", { v1, v2 }, async (compile) => {
         const output = await compile("./examples/test/methods.lo");
 
@@ -309,7 +346,7 @@ async function testCommand() {
         assert.strictEqual(result, 3);
     });
 
-    testCompilers("compiles macro.lo", { v1 }, async (compile) => {
+    testCompilers("compiles macro.lo", { v1, v2 }, async (compile) => {
         const output = await compile("./examples/test/macro.lo");
 
         const program = await loadWasm(output);
@@ -318,6 +355,26 @@ async function testCommand() {
         assert.strictEqual(result, 16);
     });
 
+    testCompilers("compiles casts.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/casts.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.widen_signed(-3), -3n);
+        assert.strictEqual(program.widen_unsigned(4000000000), 4000000000n);
+        assert.strictEqual(program.narrow(0x100000005n), 5);
+        assert.strictEqual(program.pointer_roundtrip(), 71);
+    });
+
+    testCompilers("compiles macro-args.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/macro-args.lo");
+
+        const program = await loadWasm(output);
+        const result = program.main();
+
+        assert.strictEqual(result, 15);
+    });
+
     testCompilers("compiles wasi.lo", { v1, v2 }, async (compile) => {
         const output = await compile("./examples/lib/wasi.lo");
 
@@ -443,6 +500,352 @@ async function testCommand() {
         assert.strictEqual(output, "14\n");
     });
 
+    testCompilers("compiles string-literals.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/string-literals.lo");
+
+        const program = await loadWasm(output);
+        const memory = new Uint8Array(program.memory.buffer);
+        const decoder = new TextDecoder();
+
+        assert.strictEqual(program.hello(), 16);
+        assert.strictEqual(program.hello_size(), 5);
+        assert.strictEqual(program.world(), 21);
+        assert.strictEqual(
+            decoder.decode(memory.slice(16, 27)),
+            "HelloWorld\0"
+        );
+    });
+
+    testCompilers("compiles sizeof-and-arrays.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/sizeof-and-arrays.lo");
+
+        const program = await loadWasm(output);
+        const view = new DataView(program.memory.buffer);
+
+        assert.strictEqual(program.point_size(), 12);
+        assert.strictEqual(program.bytes(), 0);
+        assert.deepStrictEqual(
+            [0, 1, 2].map((offset) => view.getUint8(offset)),
+            [1, 2, 3]
+        );
+
+        const strings = program.strings();
+        assert.strictEqual(strings, 7);
+        assert.deepStrictEqual(
+            [0, 4, 8, 12].map((offset) => view.getUint32(strings + offset, true)),
+            [3, 2, 5, 2]
+        );
+        assert.strictEqual(program.data_size(), 23);
+    });
+
+    testCompilers("compiles export-existing.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/export-existing.lo");
+
+        const program = await loadWasm(output);
+        const decoder = new TextDecoder();
+
+        assert.strictEqual(program.sum(2, 3), 5);
+        assert.strictEqual(program.plus(4, 5), 9);
+
+        const [ptr, len] = program.message();
+        assert.strictEqual(
+            decoder.decode(new Uint8Array(program.memory.buffer, ptr, len)),
+            "examples/test/export-existing.lo:18:12 - hello"
+        );
+    });
+
+    testCompilers("compiles prefix-ops.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/prefix-ops.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.not(1), 0);
+        assert.strictEqual(program.not(0), 1);
+        assert.strictEqual(program.negative(), -5);
+        assert.strictEqual(program.negative_cast() >>> 0, 0xffffffff);
+        assert.strictEqual(program.positive(), 7n);
+        assert.strictEqual(program.deref_u32(), 42);
+        assert.strictEqual(program.deref_pair(), 3);
+    });
+
+    testCompilers("compiles generic-structs.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/generic-structs.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.pair_sum(), 7);
+        assert.strictEqual(program.swapped_first(), 4n);
+        assert.strictEqual(program.slice_get(), 20);
+        assert.strictEqual(program.sizes(), 809);
+        assert.strictEqual(program.box_values(), 12n);
+        assert.strictEqual(program.box_stored(), 11);
+        assert.strictEqual(program.box_described(), 105);
+        assert.strictEqual(program.nested_boxes(), 14);
+    });
+
+    testCompilers("compiles enums.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/enums.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.empty_area(), 0);
+        assert.strictEqual(program.square_area(), 49);
+        assert.strictEqual(program.rect_area(), 12);
+        assert.strictEqual(program.lines_count(), 1);
+        assert.strictEqual(program.stored_area(), 25);
+        assert.strictEqual(program.first_square(), 9);
+        assert.strictEqual(program.shape_size(), 12);
+        assert.strictEqual(program.overwritten_area(), 34);
+        assert.strictEqual(program.slot_area(), 11);
+    });
+
+    testCompilers("compiles c-style-enums.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/c-style-enums.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.blue_value(), 6);
+        assert.strictEqual(program.from_value(), 1);
+        assert.strictEqual(program.color_size(), 1);
+        assert.strictEqual(program.stored_color(), 6);
+        assert.strictEqual(program.blue_index(), 2);
+        assert.strictEqual(program.big_index(4000000001), 2);
+        assert.strictEqual(program.big_index(4000000000), 1);
+        assert.strictEqual(program.sparse_index(1000000), 3);
+        assert.strictEqual(program.sparse_index(1), 1);
+        assert.strictEqual(program.sparse_index(500), 0);
+        assert.strictEqual(program.sparse_index(2), 0);
+        assert.throws(() => program.big_index(7), /unreachable/);
+        assert.strictEqual(program.sparse_exhaustive_index(500), 2);
+        assert.throws(() => program.sparse_exhaustive_index(2), /unreachable/);
+        assert.strictEqual(program.back_value(), -1);
+        assert.strictEqual(program.offset_step(-1), -10);
+        assert.strictEqual(program.offset_step(1), 10);
+        assert.throws(() => program.offset_step(-2), /unreachable/);
+
+        const decoder = new TextDecoder();
+        const readStr = ([ptr, len]) =>
+            decoder.decode(new Uint8Array(program.memory.buffer, ptr, len));
+        assert.strictEqual(readStr(program.color_name()), "Green");
+        assert.strictEqual(readStr(program.unknown_name()), "");
+        assert.strictEqual(readStr(program.out_of_range_name()), "");
+        assert.strictEqual(readStr(program.sparse_name()), "High");
+        assert.strictEqual(readStr(program.big_name()), "Second");
+    });
+
+    testCompilers("compiles arrays.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/arrays.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.local_sum(), 324);
+        assert.strictEqual(program.stored_sum(), 91);
+        assert.strictEqual(program.global_y(1), 4);
+        assert.strictEqual(program.replaced_point(), 5);
+        assert.strictEqual(program.decayed(), 7);
+        assert.strictEqual(program.out_of_bounds(0), 1);
+        assert.throws(() => program.out_of_bounds(2), WebAssembly.RuntimeError);
+        assert.strictEqual(program.sizes(), 3606);
+        assert.strictEqual(program.local_dynamic(1), 305010);
+        assert.throws(() => program.local_dynamic(3), WebAssembly.RuntimeError);
+        assert.strictEqual(program.local_dynamic_struct(0), 2734);
+        assert.strictEqual(program.local_dynamic_struct(1), 1247);
+        assert.strictEqual(program.local_dynamic_nested(1), 4);
+        assert.strictEqual(program.stored_item_field(), 45);
+        assert.strictEqual(program.local_item_field(0), 50307);
+        assert.strictEqual(program.local_item_field(1), 1255);
+        assert.strictEqual(program.local_grid(1, 2), 106107);
+        assert.strictEqual(program.local_grid(0, 1), 102007);
+        assert.throws(() => program.local_grid(0, 3), WebAssembly.RuntimeError);
+    });
+
+    testCompilers("compiles slices.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/slices.lo");
+
+        const program = await loadWasm(output);
+        const memory = new Uint8Array(program.memory.buffer);
+        const decoder = new TextDecoder();
+
+        assert.strictEqual(program.hello(), 16);
+        assert.strictEqual(program.hello_len(), 5);
+        assert.strictEqual(program.world(), 21);
+        assert.strictEqual(
+            decoder.decode(memory.slice(16, 27)),
+            "HelloWorld\0"
+        );
+        assert.strictEqual(program.count_l(), 3);
+        assert.strictEqual(program.sub_slice_len(), 52);
+        assert.strictEqual(program.sub_slice_first(), "W".charCodeAt(0));
+        assert.strictEqual(program.array_slice(), 32);
+        assert.strictEqual(program.pointer_slice(), "c".charCodeAt(0));
+        assert.strictEqual(program.stored_slice(), 7);
+        assert.strictEqual(program.as_str(), 4);
+        assert.strictEqual(program.as_words(), 32);
+        assert.strictEqual(program.out_of_bounds(4), "o".charCodeAt(0));
+        assert.throws(() => program.out_of_bounds(5), WebAssembly.RuntimeError);
+        assert.strictEqual(program.bad_range(1, 5), 4);
+        assert.throws(() => program.bad_range(3, 2), WebAssembly.RuntimeError);
+        assert.throws(() => program.bad_range(0, 6), WebAssembly.RuntimeError);
+        assert.strictEqual(program.count_items(), 2);
+        assert.strictEqual(program.sum_items(), 45);
+        assert.strictEqual(program.struct_items(), 14);
+    });
+
+    testCompilers("compiles fn-pointers.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/fn-pointers.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.evens(), 5);
+        assert.strictEqual(program.bigs(), 4);
+        assert.strictEqual(program.from_global(), 3);
+        assert.strictEqual(program.switch_global(), 1);
+        assert.strictEqual(program.vtables(), 721);
+        assert.strictEqual(program.same_index(), 1);
+        assert.throws(() => program.null_call(), WebAssembly.RuntimeError);
+    });
+
+    testCompilers("compiles closures.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/closures.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.no_captures(), 40);
+        assert.strictEqual(program.captures_local(), 15);
+        assert.strictEqual(program.returned_closure(), 408);
+        assert.strictEqual(program.captures_struct(), 3);
+        assert.strictEqual(program.block_body(), 36);
+        assert.strictEqual(program.stateful(), 81);
+        assert.strictEqual(program.nested(), 123);
+    });
+
+    testCompilers("compiles int-literals.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/int-literals.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.typed_let(), 5_000_000_001n);
+        assert.strictEqual(program.byte_arithmetic(), 254);
+        assert.strictEqual(program.u64_args(), 51n);
+        assert.strictEqual(program.signed_args(), 42n);
+        assert.strictEqual(program.literal_first(), 90n);
+        assert.strictEqual(program.compare(), 1);
+        assert.strictEqual(program.struct_wide(), 10_000_000_000n);
+        assert.strictEqual(program.struct_small(), 200);
+        assert.strictEqual(program.struct_signed(), -300);
+        assert.strictEqual(program.global_assign(), 42n);
+        assert.strictEqual(program.wide_loop(), 10n);
+        assert.strictEqual(program.returned(), -1);
+    });
+
+    testCompilers("compiles floats.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/floats.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.add_f64(), 3.75);
+        assert.strictEqual(program.f32_math(), 2.5);
+        assert.strictEqual(program.exponents(), 1005.25);
+        assert.strictEqual(program.negated(), -2.5);
+        assert.strictEqual(program.negative_literal(), -0.5);
+        assert.strictEqual(program.tagged(), 2.5);
+        assert.strictEqual(program.compare(), 1);
+        assert.strictEqual(program.compound(), 4.5);
+        assert.strictEqual(program.struct_fields(), 25);
+        assert.strictEqual(program.array_items(), 4);
+        assert.strictEqual(program.int_to_float(), -3.5);
+        assert.strictEqual(program.unsigned_to_float(), 4_000_000_000);
+        assert.strictEqual(program.u64_to_f32(), 16_777_216);
+        assert.strictEqual(program.float_to_int(), -3);
+        assert.strictEqual(program.float_to_u64(), 10_000_000_000n);
+        assert.strictEqual(program.saturate_low(), 0);
+        assert.strictEqual(program.saturate_high(), 2_147_483_647);
+        assert.strictEqual(program.nan_to_int(), 0n);
+        assert.strictEqual(program.promote(), Math.fround(0.1));
+        assert.strictEqual(program.demote(), Math.fround(0.1));
+    });
+
+    testCompilers("compiles bitwise.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/bitwise.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.xor(), 6);
+        assert.strictEqual(program.xor_assign(), 240);
+        assert.strictEqual(program.xor_bool(), 1);
+        assert.strictEqual(program.xor_i64(), -256n);
+        assert.strictEqual(program.bit_not(), -6);
+        assert.strictEqual(program.bit_not_u8(), 240);
+        assert.strictEqual(program.bit_not_u64(), -1n);
+        assert.strictEqual(program.xor_precedence(), 7);
+        assert.strictEqual(program.or_precedence(), 1);
+        assert.strictEqual(program.clz(), 31);
+        assert.strictEqual(program.ctz(), 3n);
+        assert.strictEqual(program.popcnt(), 8);
+        assert.strictEqual(program.rotl(), 3);
+        assert.strictEqual(program.rotr(), -(2n ** 63n));
+    });
+
+    testCompilers("compiles traits.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/traits.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.rect_area(), 12);
+        assert.strictEqual(program.bounded_macro(), 19);
+        assert.strictEqual(program.scaled(), 36);
+        assert.strictEqual(program.primitive_impl(), 7);
+        assert.strictEqual(program.bounded_struct(), 17);
+        assert.strictEqual(program.multiple_bounds(), 125);
+    });
+
+    testCompilers("compiles tuples.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/tuples.lo");
+
+        const program = await loadWasm(output);
+
+        assert.deepStrictEqual(program.multi_value(), [7, 8n]);
+        assert.strictEqual(program.destructure(), 32);
+        assert.strictEqual(program.destructure_skip(), 2);
+        assert.strictEqual(program.item_access(), -4n);
+        assert.strictEqual(program.call_item_access(), 3);
+        assert.strictEqual(program.nested(), 234);
+        assert.strictEqual(program.contextual(), -5000000000n);
+        assert.strictEqual(program.assign_item(), 44);
+        assert.strictEqual(program.stored(), 131n);
+        assert.strictEqual(program.captured(), 42);
+    });
+
+    testCompilers("compiles struct-by-value.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/struct-by-value.lo");
+
+        const program = await loadWasm(output, {
+            utils: {
+                // structs are passed and returned as their flattened fields
+                host_mirror: (/** @type {number} */ x, /** @type {number} */ y) => [y, x],
+                host_weight: (
+                    /** @type {number} */ id,
+                    /** @type {number} */ x,
+                    /** @type {number} */ y,
+                    /** @type {bigint} */ weight
+                ) => BigInt(id * 100 + x * 10 + y) + weight,
+            },
+        });
+
+        assert.strictEqual(program.exported_param(3, 4), 304);
+        assert.deepStrictEqual(program.exported_return(), [3, 1, 2, 5000000000n]);
+        assert.strictEqual(program.method_on_result(), 9);
+        assert.strictEqual(program.field_of_result(), 5);
+        assert.strictEqual(program.fn_pointer(), 21);
+        assert.strictEqual(program.closure(), 10);
+        assert.strictEqual(program.param_copy(), 11);
+        assert.strictEqual(program.nested_arg(), 3);
+        assert.strictEqual(program.from_memory(), 17);
+        assert.strictEqual(program.host(), 5000000421n);
+        assert.strictEqual(program.local_nested(), 44);
+        assert.strictEqual(program.pointer_nested(), 106);
+    });
+
     testCompilers("compiles tracing.lo", { v1 }, async (compile) => {
         const program = await compile("./examples/test/tracing.lo");
 
@@ -509,7 +912,20 @@ async function testCommand() {
         );
     });
 
-    testCompilers("compiles errors.lo", { v1 }, async (compile) => {
+    testCompilers("compiles defer-scoped.lo", { v2 }, async (compile) => {
+        const output = await compile("./examples/test/defer-scoped.lo");
+
+        /** @type {unknown[]} */
+        const logs = [];
+        const program = await loadWasm(output, {
+            utils: { debug: (/** @type {unknown} */ x) => logs.push(x) },
+        });
+
+        program.main();
+        assert.deepEqual(logs, [20, 10, 10, 40, 60, 50, 80, 90, 3, 2, 1]);
+    });
+
+    testCompilers("compiles errors.lo", { v1, v2 }, async (compile) => {
         const program = await compile("./examples/test/errors.lo");
 
         const output = await runWithTmpFile(async (stdout, stdoutFile) => {
@@ -528,6 +944,19 @@ async function testCommand() {
         );
     });
 
+    testCompilers("compiles result.lo", { v1, v2 }, async (compile) => {
+        const output = await compile("./examples/test/result.lo");
+
+        const program = await loadWasm(output);
+
+        assert.strictEqual(program.divide_or_zero(10, 3), 3);
+        assert.strictEqual(program.divide_or_zero(10, 0), 0);
+        assert.strictEqual(program.divide_error(10, 5), 0);
+        assert.strictEqual(program.divide_error(10, 0), 1);
+        assert.strictEqual(program.checked_divide_error(10, 5), 0);
+        assert.strictEqual(program.checked_divide_error(10, 0), 2);
+    });
+
     describe("<stdin> input", async () => {
         const v1 = await loadCompilerWithWasiAPI(
             await fs.readFile(COMPILER_PATH),
@@ -680,6 +1109,340 @@ async function testCommand() {
         }
     );
 
+    testCompilers(
+        "compiler continues after codegen errors in multiple-codegen-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/multiple-codegen-errors.lo"),
+                {
+                    message: m`
+                    examples/test/multiple-codegen-errors.lo:3:12 - Unknown variable: x
+                    examples/test/multiple-codegen-errors.lo:9:9 - Unknown variable: y
+                    examples/test/multiple-codegen-errors.lo:13:12 - Unknown variable: z
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid matches in match-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/match-errors.lo"),
+                {
+                    message: m`
+                    examples/test/match-errors.lo:8:5 - Non-exhaustive match, missing variants: Shape::Square, Shape::Line
+                    examples/test/match-errors.lo:16:9 - Duplicate match arm for Shape::Empty
+                    examples/test/match-errors.lo:23:9 - Unknown variant Shape::Circle of enum Shape
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid indexing in array-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/array-errors.lo"),
+                {
+                    message: m`
+                    examples/test/array-errors.lo:29:5 - Cannot assign to field 'x' of a temporary value
+                    examples/test/array-errors.lo:35:18 - Index 2 is out of bounds for &[u32; 2]
+                    examples/test/array-errors.lo:40:12 - Cannot index value of type u32
+                    examples/test/array-errors.lo:45:13 - Cannot slice *&u8 without an end index
+                    examples/test/array-errors.lo:51:18 - Unknown field size in slice []u8
+                    examples/test/array-errors.lo:60:12 - Invalid function arguments for function words_count: [[]u32], expected [Words]
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid calls in fn-pointer-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/fn-pointer-errors.lo"),
+                {
+                    message: m`
+                    examples/test/fn-pointer-errors.lo:7:12 - Invalid function arguments for function pointer fn(u32): bool: [u64], expected [u32]
+                    examples/test/fn-pointer-errors.lo:11:20 - Unknown function: missing
+                    examples/test/fn-pointer-errors.lo:20:12 - Invalid function arguments for function apply: [u32, fn(u32): bool], expected [u32, fn(u32): u32]
+                    examples/test/fn-pointer-errors.lo:28:34 - Invalid value for local callback: fn(i32): i32, expected fn(u32): u32
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid closures in closure-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/closure-errors.lo"),
+                {
+                    message: m`
+                    examples/test/closure-errors.lo:6:19 - Cannot infer type of closure param x, please annotate it
+                    examples/test/closure-errors.lo:12:12 - Invalid function arguments for closure |u32|: u32: [u64], expected [u32]
+                    examples/test/closure-errors.lo:17:21 - Closure captures locals but closure::alloc_env is not defined, expected fn(u32): &void to allocate its environment
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects out of range literals in int-literal-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/int-literal-errors.lo"),
+                {
+                    message: m`
+                    examples/test/int-literal-errors.lo:10:20 - Integer literal 300 is out of range for u8
+                    examples/test/int-literal-errors.lo:15:22 - Integer literal 256 is out of range for u8
+                    examples/test/int-literal-errors.lo:19:22 - Integer literal -129 is out of range for i8
+                    examples/test/int-literal-errors.lo:24:17 - Integer literal 5_000_000_000 is out of range for u32
+                    examples/test/int-literal-errors.lo:30:20 - Integer literal 70_000 is out of range for u16
+                    examples/test/int-literal-errors.lo:34:23 - Integer literal 256 is out of range for u8
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid float usage in float-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/float-errors.lo"),
+                {
+                    message: m`
+                    examples/test/float-errors.lo:2:22 - Float literal 1e40 is out of range for f32
+                    examples/test/float-errors.lo:7:12 - Float literal 1e400 is out of range for f64
+                    examples/test/float-errors.lo:12:18 - Operator \`%\` is incompatible with operands of type f64
+                    examples/test/float-errors.lo:17:12 - Cannot cast from f64 to u8
+                    examples/test/float-errors.lo:23:18 - Operands are not of the same type: lhs = f32, rhs = f64
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid bitwise usage in bitwise-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/bitwise-errors.lo"),
+                {
+                    message: m`
+                    examples/test/bitwise-errors.lo:2:12 - Cannot apply \`~\` to value of type bool
+                    examples/test/bitwise-errors.lo:7:12 - Unknown function: u8::clz
+                    examples/test/bitwise-errors.lo:13:12 - Invalid function arguments for function u32::rotl: [u32, u64], expected [u32, u32]
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid trait impls in trait-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/trait-errors.lo"),
+                {
+                    message: m`
+                    examples/test/trait-errors.lo:10:17 - Unknown trait: Printable
+                    examples/test/trait-errors.lo:12:1 - Missing method scale in implementation of trait Shape for Circle, declared at examples/test/trait-errors.lo:7:5
+                    examples/test/trait-errors.lo:13:5 - Method Circle::area has type fn(Circle): u64, expected fn(Circle): u32 as declared in trait Shape at examples/test/trait-errors.lo:6:5
+                    examples/test/trait-errors.lo:17:5 - Method perimeter is not a member of trait Shape
+                    examples/test/trait-errors.lo:22:1 - Duplicate implementation of trait Shape for Circle, previously implemented at examples/test/trait-errors.lo:12:1
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects unsatisfied trait bounds in trait-bound-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/trait-bound-errors.lo"),
+                {
+                    message: m`
+                    examples/test/trait-bound-errors.lo:25:12 - Type u32 does not implement trait Shape: missing method u32::area declared at examples/test/trait-bound-errors.lo:6:5, required by bound \`T: Shape\` at examples/test/trait-bound-errors.lo:14:19
+                    examples/test/trait-bound-errors.lo:30:12 - Type Line does not implement trait Shape, required by bound \`T: Shape\` at examples/test/trait-bound-errors.lo:14:19
+                    examples/test/trait-bound-errors.lo:34:18 - Type Line does not implement trait Shape, required by bound \`T: Shape\` at examples/test/trait-bound-errors.lo:10:18
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler notes the instantiation site of errors in generic-instance-errors.lo",
+        { v1 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/generic-instance-errors.lo"),
+                {
+                    message: m`
+                    examples/test/generic-instance-errors.lo:6:23 - Operands of \`+\` have incompatible types: u64 and u32, in Box<u64>::add instantiated at examples/test/generic-instance-errors.lo:12:31
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler notes the instantiation site of errors in generic-instance-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/generic-instance-errors.lo"),
+                {
+                    message: m`
+                    examples/test/generic-instance-errors.lo:6:23 - Operands are not of the same type: lhs = u64, rhs = u32, in Box<u64>::add instantiated at examples/test/generic-instance-errors.lo:12:27
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid tuple usage in tuple-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/tuple-errors.lo"),
+                {
+                    message: m`
+                    examples/test/tuple-errors.lo:6:18 - Cannot destructure value of type u32, expected a tuple
+                    examples/test/tuple-errors.lo:10:5 - Cannot destructure (u32, u32) into 3 locals
+                    examples/test/tuple-errors.lo:15:17 - Unknown item 2 in tuple (u32, u32)
+                    examples/test/tuple-errors.lo:19:30 - Invalid value for locals (a, b): (u32, bool), expected (u32, u64)
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects invalid loop control flow in loop-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/loop-errors.lo"),
+                {
+                    message: m`
+                    examples/test/loop-errors.lo:3:9 - Unknown loop label 'inner
+                    examples/test/loop-errors.lo:9:15 - Cannot break with a value out of a for loop
+                    examples/test/loop-errors.lo:18:15 - Invalid break value type: bool, expected: u32
+                    examples/test/loop-errors.lo:25:13 - Missing break value, expected: u32
+                    examples/test/loop-errors.lo:32:5 - Cannot continue outside of a loop
+                    examples/test/loop-errors.lo:36:11 - Invalid while condition type: u32, expected: bool
+                    examples/test/loop-errors.lo:41:15 - Cannot break with a value out of a while loop
+                    examples/test/loop-errors.lo:46:23 - Range step cannot be zero
+                    examples/test/loop-errors.lo:50:23 - Range step must be an integer literal
+                    examples/test/loop-errors.lo:54:17 - Cannot iterate over value of type u32, expected a slice
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects mismatched return values in return-errors.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/return-errors.lo"),
+                {
+                    message: m`
+                    examples/test/return-errors.lo:2:12 - Invalid return value type: u64, expected: u32
+                    examples/test/return-errors.lo:7:12 - Invalid return value type: u32, expected: u64
+                    examples/test/return-errors.lo:11:5 - Missing return value, expected: u32
+                    examples/test/return-errors.lo:15:12 - Invalid return value type: u32, expected: void
+                    examples/test/return-errors.lo:24:12 - Invalid return value type: u32, expected: Color
+                    examples/test/return-errors.lo:28:12 - Invalid return value type: u8, expected: Color
+
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects by-value cycles in recursive-struct.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/recursive-struct.lo"),
+                {
+                    message: m`
+                    examples/test/recursive-struct.lo:13:5 - Cannot define recursive struct Bad: Bad.inner at examples/test/recursive-struct.lo:13:5 -> Bad
+
+                    `,
+                }
+            );
+        }
+    );
+
+    testCompilers(
+        "compiler rejects by-value cycles through generic instances in recursive-generic-struct.lo",
+        { v2 },
+        async (compile) => {
+            await assert.rejects(
+                compile("./examples/test/recursive-generic-struct.lo"),
+                {
+                    message: m`
+                    examples/test/recursive-generic-struct.lo:12:5 - Cannot define recursive struct Loop: Loop.pair at examples/test/recursive-generic-struct.lo:12:5 -> Pair<u32, Wrap<Loop>>.second at examples/test/recursive-generic-struct.lo:7:5 -> Wrap<Loop>.item at examples/test/recursive-generic-struct.lo:2:5 -> Loop
+
+                    `,
+                }
+            );
+        }
+    );
+
     testCompilers("lexer.test.lo (vS unit test)", { v1 }, async (compile) => {
         const program = await compile("./examples/test/lexer.test.lo");
 
@@ -825,6 +1588,70 @@ async function testCommand() {
         }
     });
 
+    describe("v1/v2 parity", async () => {
+        const compare = await loadCompilerWithWasiAPI(
+            await fs.readFile(COMPILER_PATH),
+            {
+                buildArgs: (fileName) => [
+                    "lo",
+                    fileName ?? "-i",
+                    "--compare-v1-v2",
+                ],
+            }
+        );
+
+        const matchingFiles = [
+            "examples/test/42.lo",
+            "examples/test/add.lo",
+            "examples/test/decl-nesting.lo",
+            "examples/test/export-existing.lo",
+            "examples/test/factorial.lo",
+            "examples/test/globals.lo",
+            "examples/test/hex-and-shifts.lo",
+            "examples/test/import.lo",
+            "examples/test/include.lo",
+            "examples/test/locals.lo",
+            "examples/test/loop.lo",
+            "examples/test/methods.lo",
+            "examples/test/nested-if-break.lo",
+            "examples/test/sizeof-and-arrays.lo",
+            "examples/test/string-literals.lo",
+            "examples/test/struct.lo",
+        ];
+
+        for (const fileName of matchingFiles) {
+            test(`v1 and v2 match on ${fileName}`, async () => {
+                const output = (await compare(fileName)).toString();
+
+                assert.strictEqual(output, "No differences found\n");
+            });
+        }
+
+        // both pipelines compile these, but v2 lowers macros and locals differently
+        const acceptedFiles = [
+            "examples/test/errors.lo",
+            "examples/test/generic-structs.lo",
+        ];
+
+        for (const fileName of acceptedFiles) {
+            test(`v1 and v2 both accept ${fileName}`, async () => {
+                await assert.rejects(compare(fileName), {
+                    message: /^Found \d+ differences between v1 and v2\n$/,
+                });
+            });
+        }
+
+        test("reports features accepted by only one side", async () => {
+            await assert.rejects(compare("examples/test/prefix-ops.lo"), {
+                message: m`
+                Only v2 accepts examples/test/prefix-ops.lo, v1 errors:
+                examples/test/prefix-ops.lo:19:12 - Cannot negate this expression
+
+                `,
+            });
+        });
+    });
+
     if (process.argv.includes("--fast")) {
         return;
     }
@@ -851,6 +1678,14 @@ async function testCommand() {
             );
         });
 
+        test("interprets float-eval.lo", async () => {
+            const res = await interpret("./examples/test/float-eval.lo");
+            assert.strictEqual(
+                res.toString("utf-8"),
+                "result of `main` is: 211111\n"
+            );
+        });
+
         test("interprets else-if.lo", async () => {
             const res = await interpret("examples/test/else-if.lo");
             assert.strictEqual(
@@ -1114,6 +1949,42 @@ async function testCommand() {
             );
             assert.strictEqual(res2.toString("utf-8"), "54265\n");
         });
+
+        test("interprets trapping float truncations", async () => {
+            const evalWasm = await loadCompilerWithWasiAPI(
+                await fs.readFile(COMPILER_PATH),
+                { buildArgs: (fileName) => ["lo", fileName, "--eval-wasm"] }
+            );
+
+            // `main` returning `i32.trunc_f64_s(value)`
+            const truncModule = async (value) => {
+                const constBytes = new Uint8Array(new Float64Array([value]).buffer);
+                const body = [0x00, 0x44, ...constBytes, 0xaa, 0x0b];
+                const fileName = `${TMP_DIR}/${crypto.randomUUID()}.wasm`;
+                await fs.writeFile(
+                    fileName,
+                    new Uint8Array([
+                        ...[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+                        ...[0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f],
+                        ...[0x03, 0x02, 0x01, 0x00],
+                        // export "main" as fn 0
+                        ...[0x07, 0x08, 0x01, 0x04, 0x6d, 0x61, 0x69, 0x6e, 0x00, 0x00],
+                        ...[0x0a, body.length + 2, 0x01, body.length, ...body],
+                    ])
+                );
+                return fileName;
+            };
+
+            const res = await evalWasm(await truncModule(-3.75));
+            assert.strictEqual(res.toString("utf-8"), "result of `main` is: -3\n");
+
+            await assert.rejects(evalWasm(await truncModule(NaN)), {
+                message: /Invalid conversion to integer/,
+            });
+            await assert.rejects(evalWasm(await truncModule(2 ** 31)), {
+                message: /Integer overflow/,
+            });
+        });
     });
 
     /**